@@ -0,0 +1,90 @@
+#import bevy_pbr::{
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::{alpha_discard, apply_pbr_lighting, main_pass_post_lighting_processing, calculate_diffuse_color, calculate_F0},
+    pbr_types::PbrInput,
+    forward_io::{VertexOutput, FragmentOutput},
+    mesh_view_bindings as view_bindings,
+    mesh_view_types::DIRECTIONAL_LIGHT_FLAGS_SHADOWS_ENABLED_BIT,
+    mesh_types::MESH_FLAGS_SHADOW_RECEIVER_BIT,
+    lighting,
+    lighting::LAYER_BASE,
+    shadows::fetch_directional_shadow,
+}
+#import virtual_env::clouds::cloud_shadow
+
+struct CloudShadows {
+    clouds: vec4<f32>,
+    cloud_shape: vec4<f32>,
+    sun: vec4<f32>, // xyz direction towards the sun
+};
+
+@group(2) @binding(100) var<uniform> shadows: CloudShadows;
+@group(2) @binding(101) var cloud_map: texture_2d<f32>;
+@group(2) @binding(102) var cloud_sampler: sampler;
+
+// The sun's direct light on a fragment, worked out the way apply_pbr_lighting adds each
+// directional light, shadow map included. The moon is a directional light too, so the
+// sun is picked out by its direction.
+fn sun_direct_light(in: PbrInput, to_sun: vec3<f32>) -> vec3<f32> {
+    for (var i: u32 = 0u; i < view_bindings::lights.n_directional_lights; i = i + 1u) {
+        let light = &view_bindings::lights.directional_lights[i];
+        if ((*light).skip != 0u || dot((*light).direction_to_light, to_sun) < 0.999) {
+            continue;
+        }
+
+        let base_color = in.material.base_color.rgb;
+        let metallic = in.material.metallic;
+        let perceptual_roughness = in.material.perceptual_roughness;
+        let NdotV = max(dot(in.N, in.V), 0.0001);
+
+        var lighting_input: lighting::LightingInput;
+        lighting_input.layers[LAYER_BASE].NdotV = NdotV;
+        lighting_input.layers[LAYER_BASE].N = in.N;
+        lighting_input.layers[LAYER_BASE].R = reflect(-in.V, in.N);
+        lighting_input.layers[LAYER_BASE].perceptual_roughness = perceptual_roughness;
+        lighting_input.layers[LAYER_BASE].roughness = lighting::perceptualRoughnessToRoughness(perceptual_roughness);
+        lighting_input.P = in.world_position.xyz;
+        lighting_input.V = in.V;
+        lighting_input.diffuse_color = calculate_diffuse_color(
+            base_color,
+            metallic,
+            in.material.specular_transmission,
+            in.material.diffuse_transmission,
+        );
+        lighting_input.F0_ = calculate_F0(base_color, metallic, in.material.reflectance);
+        lighting_input.F_ab = lighting::F_AB(perceptual_roughness, NdotV);
+
+        var shadow = 1.0;
+        if ((in.flags & MESH_FLAGS_SHADOW_RECEIVER_BIT) != 0u
+                && ((*light).flags & DIRECTIONAL_LIGHT_FLAGS_SHADOWS_ENABLED_BIT) != 0u) {
+            let view_z = dot(vec4<f32>(
+                view_bindings::view.view_from_world[0].z,
+                view_bindings::view.view_from_world[1].z,
+                view_bindings::view.view_from_world[2].z,
+                view_bindings::view.view_from_world[3].z
+            ), in.world_position);
+            shadow = fetch_directional_shadow(i, in.world_position, in.world_normal, view_z);
+        }
+        return lighting::directional_light(i, &lighting_input) * shadow * view_bindings::view.exposure;
+    }
+    return vec3<f32>(0.0);
+}
+
+@fragment
+fn fragment(in: VertexOutput, @builtin(front_facing) is_front: bool) -> FragmentOutput {
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+
+    var out: FragmentOutput;
+    out.color = apply_pbr_lighting(pbr_input);
+
+    // Take the clouded share of the sun's direct light back out. Ambient and emissive
+    // light stay as they are, and ground already in the sun's shadow map has no direct
+    // sunlight left to lose.
+    let shadow = cloud_shadow(cloud_map, cloud_sampler, in.world_position.xyz, shadows.sun.xyz, shadows.clouds, shadows.cloud_shape);
+    let sun = sun_direct_light(pbr_input, shadows.sun.xyz);
+    out.color = vec4<f32>(out.color.rgb - sun * shadow, out.color.a);
+
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+    return out;
+}
@@ -0,0 +1,37 @@
+#define_import_path virtual_env::clouds
+
+// Cloud layer shared by the sky, terrain and grass shaders. The uniforms come from
+// CloudLayer in clouds.rs:
+//   clouds: xy drift in metres, z coverage, w shadow strength
+//   shape:  x altitude of the base, y thickness, z map tiles per metre
+
+// Thickness of the layer above a point, 0 for clear sky to 1 where the cloud is deepest
+fn cloud_density(
+    map: texture_2d<f32>,
+    map_sampler: sampler,
+    position: vec2<f32>,
+    clouds: vec4<f32>,
+    shape: vec4<f32>,
+) -> f32 {
+    let uv = (position - clouds.xy) * shape.z;
+    let broad = textureSampleLevel(map, map_sampler, uv, 0.0).r;
+    let detail = textureSampleLevel(map, map_sampler, uv * 3.0 + vec2<f32>(0.37, 0.61), 0.0).r;
+    // The map is evened out, so the coverage is the share of it above the threshold
+    let threshold = 1.0 - clouds.z;
+    return smoothstep(threshold - 0.05, threshold + 0.2, mix(broad, detail, 0.3));
+}
+
+// Share of direct sunlight the clouds take away at a world position: the map projected
+// down the sun's direction from the middle of the layer, like a light cookie
+fn cloud_shadow(
+    map: texture_2d<f32>,
+    map_sampler: sampler,
+    world_position: vec3<f32>,
+    sun: vec3<f32>,
+    clouds: vec4<f32>,
+    shape: vec4<f32>,
+) -> f32 {
+    let height = shape.x + shape.y * 0.5 - world_position.y;
+    let hit = world_position.xz + sun.xz / max(sun.y, 0.1) * height;
+    return cloud_density(map, map_sampler, hit, clouds, shape) * clouds.w;
+}
@@ -0,0 +1,113 @@
+#import bevy_pbr::{
+    mesh_view_bindings::{view, fog},
+    mesh_view_types::FOG_MODE_LINEAR,
+    view_transformations::position_world_to_clip,
+    fog::linear_fog,
+}
+#import virtual_env::clouds::cloud_shadow
+
+struct GrassMaterial {
+    wind: vec4<f32>,      // xy direction, z strength, w wind clock
+    player: vec4<f32>,    // xyz player position, w flatten radius
+    fade: vec4<f32>,      // x fade start, y fade end
+    sun: vec4<f32>,       // xyz direction towards the sun, w light strength
+    sun_color: vec4<f32>,
+    clouds: vec4<f32>,    // Cloud layer, as in clouds.wgsl
+    cloud_shape: vec4<f32>,
+};
+
+@group(2) @binding(0) var<uniform> material: GrassMaterial;
+@group(2) @binding(1) var cloud_map: texture_2d<f32>;
+@group(2) @binding(2) var cloud_sampler: sampler;
+
+// One corner of the unit blade, plus the blade it belongs to from the instance buffer
+struct Vertex {
+    @location(0) position: vec3<f32>, // x across the blade, y from root to tip
+    @location(3) root: vec4<f32>,     // xyz root, w blade height
+    @location(4) shape: vec4<f32>,    // xy facing, z width, w lean
+    @location(5) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) along: f32,
+    @location(3) color: vec4<f32>,
+};
+
+// Same shape as Wind::sway on the CPU so grass and canopies move together
+fn wind_sway(position: vec2<f32>) -> f32 {
+    let direction = material.wind.xy;
+    let phase = dot(position, direction) * 0.15;
+    let base = sin(material.wind.w * 2.0 - phase);
+    let gust = max(sin(material.wind.w * 0.6 - phase * 0.5), 0.0);
+    // Small per-blade flutter on top of the rolling gusts
+    let flutter = sin(material.wind.w * 7.0 + position.x * 3.1 + position.y * 2.3) * 0.15;
+    return (base * 0.6 + gust * 0.4 + flutter) * material.wind.z;
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let blade_height = vertex.root.w;
+    let along = vertex.position.y;
+    let bend = along * along; // Roots stay put, tips move the most
+    let root_y = vertex.root.y;
+
+    // Turn the blade to face its way, stand it up and let the tip hang over
+    let across = vec3<f32>(vertex.shape.x, 0.0, vertex.shape.y);
+    let lean_direction = vec3<f32>(-vertex.shape.y, 0.0, vertex.shape.x);
+    var world_position = vertex.root.xyz
+        + across * vertex.shape.z * vertex.position.x
+        + vec3<f32>(0.0, blade_height * along, 0.0)
+        + lean_direction * vertex.shape.w * blade_height * bend;
+
+    // Wind sway
+    let sway = wind_sway(world_position.xz) * bend * blade_height;
+    world_position.x += material.wind.x * sway;
+    world_position.z += material.wind.y * sway;
+
+    // Flatten and push away from the player
+    let to_blade = world_position.xz - material.player.xz;
+    let distance_to_player = length(to_blade);
+    let flatten = (1.0 - smoothstep(material.player.w * 0.4, material.player.w, distance_to_player)) * along;
+    let push = to_blade / max(distance_to_player, 0.001);
+    world_position.x += push.x * flatten * blade_height * 0.8;
+    world_position.z += push.y * flatten * blade_height * 0.8;
+    world_position.y -= flatten * blade_height * 0.7;
+
+    // Shrink blades into the ground towards the edge of the grass range
+    let camera_distance = length(world_position.xz - view.world_position.xz);
+    let fade = 1.0 - smoothstep(material.fade.x, material.fade.y, camera_distance);
+    world_position.y = root_y + (world_position.y - root_y) * fade;
+
+    var out: VertexOutput;
+    out.clip_position = position_world_to_clip(world_position);
+    out.world_position = world_position;
+    out.world_normal = normalize(vec3<f32>(0.0, 1.0, 0.0) + lean_direction * 0.3);
+    out.along = along;
+    out.color = vertex.color;
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput, @builtin(front_facing) front_facing: bool) -> @location(0) vec4<f32> {
+    var normal = normalize(in.world_normal);
+    if (!front_facing) {
+        normal = -normal;
+    }
+
+    // Darker towards the roots, with soft wrap lighting so blades never go fully black
+    let occlusion = mix(0.45, 1.0, in.along);
+    let shadow = cloud_shadow(cloud_map, cloud_sampler, in.world_position, material.sun.xyz, material.clouds, material.cloud_shape);
+    let diffuse = max(dot(normal, material.sun.xyz) * 0.6 + 0.4, 0.0) * material.sun.w * (1.0 - shadow);
+    let ambient = 0.25;
+    let light = material.sun_color.rgb * diffuse + vec3<f32>(ambient);
+    var color = vec4<f32>(in.color.rgb * light * occlusion, 1.0);
+
+    if (fog.mode == FOG_MODE_LINEAR) {
+        let distance = length(in.world_position - view.world_position);
+        color = linear_fog(fog, color, distance, vec3<f32>(0.0));
+    }
+    return color;
+}
@@ -0,0 +1,75 @@
+#import bevy_pbr::{
+    mesh_view_bindings::{view, fog},
+    mesh_view_types::FOG_MODE_LINEAR,
+    view_transformations::position_world_to_clip,
+    fog::linear_fog,
+}
+
+struct PrecipitationMaterial {
+    camera: vec4<f32>,        // xyz camera position, w snow sway clock
+    size: vec4<f32>,          // xyz size of the box of particles
+    rain_drift: vec4<f32>,    // How far rain has fallen and blown, wrapped to the box
+    rain_velocity: vec4<f32>,
+    snow_drift: vec4<f32>,
+    rain_color: vec4<f32>,
+    snow_color: vec4<f32>,
+};
+
+@group(2) @binding(0) var<uniform> material: PrecipitationMaterial;
+
+// One corner of the drop or flake mesh, plus the particle it belongs to
+struct Vertex {
+    @location(0) position: vec3<f32>,
+    @location(3) particle: vec4<f32>, // xyz starting point, w sway phase
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+};
+
+// Anything that leaves the box round the camera comes back in on the far side
+fn wrap_to_box(position: vec3<f32>) -> vec3<f32> {
+    let size = material.size.xyz;
+    let local = position - material.camera.xyz + size * 0.5;
+    return material.camera.xyz + local - size * floor(local / size) - size * 0.5;
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+#ifdef SNOW
+    // Each flake drifts from side to side on its own phase
+    let clock = material.camera.w;
+    let phase = vertex.particle.w;
+    let sway = vec3<f32>(-cos(clock + phase), 0.0, sin(clock * 0.7 + phase) / 0.7) * 0.4;
+    let center = wrap_to_box(vertex.particle.xyz + material.snow_drift.xyz + sway);
+    let world_position = center + vertex.position;
+#else
+    // Streaks lean with the wind, along the way the rain is falling
+    let center = wrap_to_box(vertex.particle.xyz + material.rain_drift.xyz);
+    let down = normalize(material.rain_velocity.xyz);
+    let side = normalize(cross(down, vec3<f32>(0.0, 0.0, 1.0)));
+    let forward = cross(down, side);
+    let world_position = center + side * vertex.position.x - down * vertex.position.y + forward * vertex.position.z;
+#endif
+
+    var out: VertexOutput;
+    out.clip_position = position_world_to_clip(world_position);
+    out.world_position = world_position;
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+#ifdef SNOW
+    var color = material.snow_color;
+#else
+    var color = material.rain_color;
+#endif
+
+    if (fog.mode == FOG_MODE_LINEAR) {
+        let distance = length(in.world_position - view.world_position);
+        color = linear_fog(fog, color, distance, vec3<f32>(0.0));
+    }
+    return color;
+}
@@ -0,0 +1,235 @@
+#import bevy_pbr::{
+    mesh_functions,
+    mesh_view_bindings::view,
+    view_transformations::position_world_to_clip,
+}
+#import virtual_env::clouds::cloud_density
+
+struct SkyMaterial {
+    sun: vec4<f32>,           // xyz direction towards the sun, w intensity
+    moon: vec4<f32>,          // xyz direction towards the moon, w lit fraction
+    star_rotation: vec4<f32>, // Quaternion from world to the rotating star sphere
+    params: vec4<f32>,        // x aurora strength, y star brightness, z clock, w moon intensity
+    tint: vec4<f32>,          // rgb multiplier from the lighting profile
+    weather: vec4<f32>,       // x overcast, y lightning flash
+    clouds: vec4<f32>,        // Cloud layer, as in clouds.wgsl
+    cloud_shape: vec4<f32>,
+};
+
+@group(2) @binding(0) var<uniform> material: SkyMaterial;
+@group(2) @binding(1) var cloud_map: texture_2d<f32>;
+@group(2) @binding(2) var cloud_sampler: sampler;
+
+// Same constants as sky.rs, which computes the fog colour from this model
+const PI: f32 = 3.14159265;
+const RAYLEIGH: vec3<f32> = vec3<f32>(4.5e-6, 13.5e-6, 33.1e-6);
+const MIE: f32 = 2e-6;
+const RAYLEIGH_HEIGHT: f32 = 8000.0;
+const MIE_HEIGHT: f32 = 1200.0;
+const MIE_G: f32 = 0.76;
+const NIGHT_SKY: vec3<f32> = vec3<f32>(0.002, 0.003, 0.008);
+const SUN_RADIUS: f32 = 0.012; // Radians; both discs are drawn larger than in life
+const MOON_RADIUS: f32 = 0.03;
+const CLOUD_STEPS: i32 = 16;
+const CLOUD_EXTINCTION: f32 = 0.03; // Per metre in the thickest cloud
+const CLOUD_FADE: vec2<f32> = vec2<f32>(3000.0, 9000.0); // Distances the layer thins out over
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+};
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let world_from_local = mesh_functions::get_world_from_local(vertex.instance_index);
+    let world_position = mesh_functions::mesh_position_local_to_world(world_from_local, vec4<f32>(vertex.position, 1.0)).xyz;
+    var out: VertexOutput;
+    out.world_position = world_position;
+    out.clip_position = position_world_to_clip(world_position);
+    return out;
+}
+
+fn air_mass(cos_zenith: f32) -> f32 {
+    let c = max(cos_zenith, 0.0);
+    let zenith_degrees = degrees(acos(min(c, 1.0)));
+    return 1.0 / (c + 0.50572 * pow(96.07995 - zenith_degrees, -1.6364));
+}
+
+fn transmittance(cos_zenith: f32) -> vec3<f32> {
+    let mass = air_mass(cos_zenith);
+    return exp(-(RAYLEIGH * RAYLEIGH_HEIGHT + vec3<f32>(MIE * MIE_HEIGHT)) * mass);
+}
+
+fn scatter(view_dir: vec3<f32>, light: vec3<f32>, intensity: f32) -> vec3<f32> {
+    let cos_angle = dot(view_dir, light);
+    let rayleigh_phase = 3.0 / (16.0 * PI) * (1.0 + cos_angle * cos_angle);
+    let g2 = MIE_G * MIE_G;
+    let mie_phase = (1.0 - g2) / (4.0 * PI * pow(1.0 + g2 - 2.0 * MIE_G * cos_angle, 1.5));
+
+    let view_mass = air_mass(view_dir.y);
+    let rayleigh_depth = RAYLEIGH * RAYLEIGH_HEIGHT;
+    let mie_depth = MIE * MIE_HEIGHT;
+    let inscatter = (1.0 - exp(-rayleigh_depth * view_mass)) * rayleigh_phase
+        + vec3<f32>((1.0 - exp(-mie_depth * view_mass)) * mie_phase);
+    let above_horizon = clamp((light.y + 0.1) / 0.1, 0.0, 1.0);
+    return intensity * above_horizon * transmittance(light.y) * inscatter;
+}
+
+fn rotate(q: vec4<f32>, v: vec3<f32>) -> vec3<f32> {
+    let t = 2.0 * cross(q.xyz, v);
+    return v + q.w * t + cross(q.xyz, t);
+}
+
+fn hash33(p: vec3<f32>) -> vec3<f32> {
+    var q = fract(p * vec3<f32>(0.1031, 0.1030, 0.0973));
+    q += dot(q, q.yxz + 33.33);
+    return fract((q.xxy + q.yxx) * q.zyx);
+}
+
+// One star at a random spot in some of the cells of a grid wrapped around the sky
+fn star_layer(direction: vec3<f32>, scale: f32, density: f32) -> vec3<f32> {
+    let p = direction * scale;
+    let cell = floor(p);
+    let h = hash33(cell);
+    if (h.x > density) {
+        return vec3<f32>(0.0);
+    }
+    let star = cell + 0.25 + 0.5 * hash33(cell + 17.0);
+    let d = length(p - star);
+    let twinkle = 0.75 + 0.25 * sin(material.params.z * (2.0 + h.y * 3.0) + h.z * 40.0);
+    let brightness = pow(h.y, 6.0) * 6.0 + 0.3;
+    // Hotter stars are bluer
+    let tint = mix(vec3<f32>(1.0, 0.8, 0.65), vec3<f32>(0.75, 0.85, 1.0), h.z);
+    return tint * brightness * twinkle * smoothstep(0.3, 0.0, d);
+}
+
+// Green curtains with violet tops, hanging over the northern (-Z) sky
+fn aurora(direction: vec3<f32>) -> vec3<f32> {
+    if (direction.y <= 0.02) {
+        return vec3<f32>(0.0);
+    }
+    let clock = material.params.z * 0.05;
+    var light = vec3<f32>(0.0);
+    for (var i = 0; i < 12; i++) {
+        let height = 1.0 + f32(i) * 0.06;
+        let p = direction.xz / direction.y * height;
+        // Band across the sky to the north, rippling along its length
+        let wave = sin(p.x * 0.35 + clock * 3.0) * 0.8 + sin(p.x * 0.9 - clock * 5.0) * 0.3;
+        let band = exp(-abs(p.y + 3.5 + wave) * 2.5);
+        let rays = 0.6 + 0.4 * sin(p.x * 6.0 + sin(p.x * 1.7 + clock * 4.0) * 2.0);
+        let color = mix(vec3<f32>(0.1, 1.0, 0.45), vec3<f32>(0.6, 0.2, 0.9), f32(i) / 11.0);
+        light += color * band * rays * (1.0 - f32(i) / 12.0);
+    }
+    return light * 0.04 * smoothstep(0.02, 0.2, direction.y);
+}
+
+fn cloud_at(position: vec3<f32>) -> f32 {
+    let height = (position.y - material.cloud_shape.x) / material.cloud_shape.y;
+    let thickness = cloud_density(cloud_map, cloud_sampler, position.xz, material.clouds, material.cloud_shape);
+    // Flat bases, and deeper cloud piles up higher
+    return thickness * smoothstep(0.0, 0.1, height) * (1.0 - smoothstep(thickness - 0.25, thickness, height));
+}
+
+// March the view ray through the cloud layer. Returns the light the clouds scatter
+// towards the viewer, and in w how much of the sky behind them still shows.
+fn march_clouds(origin: vec3<f32>, direction: vec3<f32>, light: vec3<f32>, ambient: vec3<f32>) -> vec4<f32> {
+    if (abs(direction.y) < 0.01) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+    let bottom = (material.cloud_shape.x - origin.y) / direction.y;
+    let top = (material.cloud_shape.x + material.cloud_shape.y - origin.y) / direction.y;
+    let near = max(min(bottom, top), 0.0);
+    let far = min(max(bottom, top), near + material.cloud_shape.y * 4.0);
+    if (far <= near) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+
+    let step_length = (far - near) / f32(CLOUD_STEPS);
+    let sun = material.sun.xyz;
+    // Bright rim where the sun shines through towards the viewer
+    let phase = 0.6 + 0.8 * pow(max(dot(direction, sun), 0.0), 8.0);
+    var transmittance = 1.0;
+    var scattered = vec3<f32>(0.0);
+    for (var i = 0; i < CLOUD_STEPS; i++) {
+        let position = origin + direction * (near + (f32(i) + 0.5) * step_length);
+        let density = cloud_at(position);
+        if (density <= 0.0) {
+            continue;
+        }
+        // Cloud between this point and the sun
+        var towards_sun = 0.0;
+        for (var j = 1; j <= 2; j++) {
+            towards_sun += cloud_at(position + sun * material.cloud_shape.y * 0.3 * f32(j));
+        }
+        let sunlit = exp(-towards_sun * 1.5);
+        let extinction = exp(-density * CLOUD_EXTINCTION * step_length);
+        scattered += transmittance * (1.0 - extinction) * (light * sunlit * phase + ambient);
+        transmittance *= extinction;
+    }
+
+    let fade = 1.0 - smoothstep(CLOUD_FADE.x, CLOUD_FADE.y, near);
+    return vec4<f32>(scattered * fade, mix(1.0, transmittance, fade));
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let direction = normalize(in.world_position - view.world_position);
+    let sun = material.sun.xyz;
+    let moon = material.moon.xyz;
+    let moon_light = material.sun.w * material.params.w * material.moon.w;
+
+    var radiance = NIGHT_SKY + scatter(direction, sun, material.sun.w) + scatter(direction, moon, moon_light);
+    let sky_brightness = dot(radiance, vec3<f32>(0.2126, 0.7152, 0.0722));
+    let horizon = smoothstep(-0.02, 0.1, direction.y);
+
+    // Stars and aurora only show once the sky is dark enough
+    let darkness = 1.0 - smoothstep(0.01, 0.08, sky_brightness);
+    let star_direction = rotate(material.star_rotation, direction);
+    var stars = star_layer(star_direction, 160.0, 0.12) + star_layer(star_direction, 320.0, 0.06) * 0.5;
+    stars *= material.params.y * darkness * horizon * 0.08;
+    let northern_lights = aurora(direction) * material.params.x * darkness;
+
+    // Moon disc, lit from the sun's side so its phase shows, hiding the stars behind it
+    let to_moon = dot(direction, moon);
+    if (to_moon > cos(MOON_RADIUS)) {
+        let offset = (direction - moon * to_moon) / sin(MOON_RADIUS);
+        let normal = offset - moon * sqrt(max(1.0 - dot(offset, offset), 0.0));
+        let lit = smoothstep(-0.05, 0.1, dot(normal, sun));
+        let edge = smoothstep(cos(MOON_RADIUS), cos(MOON_RADIUS * 0.95), to_moon);
+        stars *= 1.0 - edge;
+        radiance += vec3<f32>(0.95, 0.93, 0.85) * (0.02 + lit * 1.2) * edge * transmittance(moon.y) * horizon;
+    }
+
+    // Sun disc, reddened by the air in front of it
+    let to_sun = dot(direction, sun);
+    let sun_disc = smoothstep(cos(SUN_RADIUS), cos(SUN_RADIUS * 0.9), to_sun);
+    radiance += vec3<f32>(30.0) * sun_disc * transmittance(sun.y) * horizon;
+
+    radiance += stars + northern_lights;
+
+    // Heavy cloud hides everything behind a flat grey
+    let overcast = material.weather.x;
+    let grey = vec3<f32>(sky_brightness * 0.7);
+    radiance = mix(radiance, grey, overcast);
+
+    // The cloud layer in front, lit by the sun and moon through the air and filled in by
+    // the sky overhead
+    let up = vec3<f32>(0.0, 1.0, 0.0);
+    let sunlight = material.sun.w * transmittance(sun.y) * smoothstep(-0.1, 0.05, sun.y);
+    let moonlight = moon_light * transmittance(moon.y) * smoothstep(-0.1, 0.05, moon.y);
+    let cloud_light = (sunlight + moonlight) * 0.075;
+    let cloud_ambient = (NIGHT_SKY + scatter(up, sun, material.sun.w) + scatter(up, moon, moon_light)) * 1.5;
+    let clouds = march_clouds(view.world_position, direction, cloud_light, cloud_ambient);
+    radiance = radiance * clouds.w + clouds.rgb;
+
+    // Lightning lights up the whole sky
+    radiance += vec3<f32>(0.6, 0.65, 0.8) * material.weather.y * overcast;
+    // Below the horizon the ground hides the sky; darken it a little in case it shows
+    radiance *= mix(0.6, 1.0, smoothstep(-0.3, 0.0, direction.y));
+    return vec4<f32>((1.0 - exp(-radiance)) * material.tint.rgb, 1.0);
+}
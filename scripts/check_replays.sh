@@ -0,0 +1,23 @@
+#!/usr/bin/env sh
+# Plays every recording in replays/ headless and fails if any of them ends
+# somewhere other than its recorded final pose. Extra arguments go to cargo,
+# e.g. `scripts/check_replays.sh --release`.
+set -eu
+
+cd "$(dirname "$0")/.."
+cargo build "$@"
+
+status=0
+for replay in replays/*.replay; do
+    log=$(mktemp)
+    if cargo run -q "$@" -- --headless --replay "$replay" >"$log" 2>&1 \
+        && grep -q "Replay matches the recorded final pose" "$log"; then
+        echo "ok      $replay"
+    else
+        echo "FAILED  $replay"
+        tail -n 20 "$log"
+        status=1
+    fi
+    rm -f "$log"
+done
+exit $status
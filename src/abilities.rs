@@ -0,0 +1,217 @@
+use bevy::prelude::*;
+
+use crate::collision::SpatialIndex;
+use crate::health::Health;
+use crate::input::{Action, ActionState};
+use crate::noclip::Noclip;
+use crate::player::{self, MovementState, Player, PlayerSettings, WaterState};
+
+pub struct AbilitiesPlugin;
+
+impl Plugin for AbilitiesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CrouchStarted>()
+            .add_event::<CrouchEnded>()
+            .add_event::<SlideStarted>()
+            .add_event::<SlideEnded>()
+            .add_event::<MantleStarted>()
+            .add_event::<MantleFinished>()
+            .add_systems(
+                Update,
+                (update_movement_state.before(player::player_movement), log_ability_events),
+            );
+    }
+}
+
+// Hooks for animation and audio; sent on every state change
+#[derive(Event)]
+pub struct CrouchStarted;
+
+#[derive(Event)]
+pub struct CrouchEnded;
+
+#[derive(Event)]
+pub struct SlideStarted {
+    pub speed: f32,
+}
+
+#[derive(Event)]
+pub struct SlideEnded;
+
+#[derive(Event)]
+pub struct MantleStarted {
+    pub ledge: Vec3, // Where the feet end up
+}
+
+#[derive(Event)]
+pub struct MantleFinished;
+
+#[derive(bevy::ecs::system::SystemParam)]
+struct AbilityEvents<'w> {
+    crouch_started: EventWriter<'w, CrouchStarted>,
+    crouch_ended: EventWriter<'w, CrouchEnded>,
+    slide_started: EventWriter<'w, SlideStarted>,
+    slide_ended: EventWriter<'w, SlideEnded>,
+    mantle_started: EventWriter<'w, MantleStarted>,
+    mantle_finished: EventWriter<'w, MantleFinished>,
+}
+
+// Transitions between walking, crouching, sliding and mantling. Runs before the
+// controller step, which reads the resulting state.
+fn update_movement_state(
+    actions: Res<ActionState>,
+    time: Res<Time>,
+    settings: Res<PlayerSettings>,
+    index: Res<SpatialIndex>,
+    noclip: Res<Noclip>,
+    mut player_query: Query<(&mut Transform, &mut Player, Option<&Health>)>,
+    mut events: AbilityEvents,
+) {
+    if noclip.enabled {
+        return;
+    }
+    let Ok((mut transform, mut player, health)) = player_query.get_single_mut() else {
+        return;
+    };
+    if health.is_some_and(Health::is_dead) {
+        return;
+    }
+    let dt = time.delta_seconds();
+    let crouch_held = actions.pressed(Action::Crouch) && player.water != WaterState::Swimming;
+    let speed = player.velocity.xz().length();
+
+    let next = match player.state {
+        MovementState::Walking => {
+            if crouch_held && player.sprinting && speed >= settings.slide_min_speed {
+                let boosted = player.velocity.xz() * settings.slide_boost;
+                player.velocity.x = boosted.x;
+                player.velocity.z = boosted.y;
+                events.slide_started.send(SlideStarted { speed: boosted.length() });
+                MovementState::Sliding { elapsed: 0.0 }
+            } else if crouch_held && player.on_ground {
+                events.crouch_started.send(CrouchStarted);
+                MovementState::Crouching
+            } else {
+                MovementState::Walking
+            }
+        }
+        MovementState::Crouching => {
+            if !crouch_held && can_stand(&index, &settings, transform.translation) {
+                events.crouch_ended.send(CrouchEnded);
+                MovementState::Walking
+            } else {
+                MovementState::Crouching
+            }
+        }
+        MovementState::Sliding { elapsed } => {
+            let elapsed = elapsed + dt;
+            let finished = speed < settings.slide_end_speed
+                || elapsed > settings.slide_max_time
+                || !crouch_held
+                || actions.just_pressed(Action::Jump)
+                || player.air_time > 0.2;
+            if finished {
+                events.slide_ended.send(SlideEnded);
+                // Stay low if there is no room to stand or crouch is still held
+                if crouch_held || !can_stand(&index, &settings, transform.translation) {
+                    events.crouch_started.send(CrouchStarted);
+                    MovementState::Crouching
+                } else {
+                    MovementState::Walking
+                }
+            } else {
+                MovementState::Sliding { elapsed }
+            }
+        }
+        MovementState::Mantling { from, to, elapsed } => {
+            let elapsed = elapsed + dt;
+            let t = (elapsed / settings.mantle_time).min(1.0);
+            // Pull up first, then step forward onto the ledge
+            transform.translation = if t < 0.6 {
+                let rise = t / 0.6;
+                Vec3::new(from.x, from.y.lerp(to.y, rise * rise * (3.0 - 2.0 * rise)), from.z)
+            } else {
+                let step = (t - 0.6) / 0.4;
+                Vec3::new(from.x.lerp(to.x, step), to.y, from.z.lerp(to.z, step))
+            };
+            if t >= 1.0 {
+                player.on_ground = true;
+                player.air_time = 0.0;
+                events.mantle_finished.send(MantleFinished);
+                MovementState::Walking
+            } else {
+                MovementState::Mantling { from, to, elapsed }
+            }
+        }
+    };
+    player.state = next;
+
+    // Jumping at a chest-high ledge climbs onto it instead
+    let wants_up = actions.just_pressed(Action::Jump) || (!player.on_ground && actions.pressed(Action::Jump));
+    if matches!(player.state, MovementState::Walking | MovementState::Crouching)
+        && player.water != WaterState::Swimming
+        && wants_up
+        && let Some(ledge) = find_ledge(&index, &settings, &transform)
+    {
+        if player.state == MovementState::Crouching {
+            events.crouch_ended.send(CrouchEnded);
+        }
+        player.state = MovementState::Mantling {
+            from: transform.translation,
+            to: ledge,
+            elapsed: 0.0,
+        };
+        player.velocity = Vec3::ZERO;
+        player.jump_buffer = 0.0;
+        player.sprinting = false;
+        events.mantle_started.send(MantleStarted { ledge });
+    }
+
+    // Ease the eye height between standing and crouching
+    let target_eye = if player.state.is_low() { settings.crouch_eye_height } else { settings.eye_height };
+    player.eye_height = player.eye_height.lerp(target_eye, 1.0 - (-12.0 * dt).exp());
+}
+
+// Nothing may overlap the full standing capsule
+fn can_stand(index: &SpatialIndex, settings: &PlayerSettings, feet: Vec3) -> bool {
+    let (correction, _) = player::resolve(index, settings, feet, settings.eye_height);
+    correction.length() < 0.01
+}
+
+// Look for a collider top in front of the player that is too high to step onto but low
+// enough to climb, with room to stand on it
+fn find_ledge(index: &SpatialIndex, settings: &PlayerSettings, transform: &Transform) -> Option<Vec3> {
+    let feet = transform.translation;
+    let forward = transform.forward().with_y(0.0).normalize_or_zero();
+    let probe = feet + forward * (settings.collision_radius + 0.35);
+
+    let mut best: Option<Vec3> = None;
+    for collider in index.overlapping(probe + Vec3::Y * settings.step_height, settings.collision_radius + 0.3) {
+        let top = collider.top();
+        let rise = top - feet.y;
+        if rise <= settings.step_height || rise > settings.mantle_max_height {
+            continue;
+        }
+        let ledge = Vec3::new(probe.x, top, probe.z);
+        let (correction, _) = player::resolve(index, settings, ledge, settings.eye_height);
+        if correction.length() > 0.05 {
+            continue;
+        }
+        if best.is_none_or(|b| ledge.y > b.y) {
+            best = Some(ledge);
+        }
+    }
+    best
+}
+
+fn log_ability_events(
+    mut slide_started: EventReader<SlideStarted>,
+    mut mantle_started: EventReader<MantleStarted>,
+) {
+    for event in slide_started.read() {
+        debug!("Slide started at {:.1} m/s", event.speed);
+    }
+    for event in mantle_started.read() {
+        debug!("Mantling onto ledge at {:?}", event.ledge);
+    }
+}
@@ -0,0 +1,466 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use std::fs;
+use std::path::Path;
+
+use crate::collision::SpatialIndex;
+use crate::input::{Action, ActionState, InputMap, TextEntry};
+use crate::player::{self, MovementState, Player, PlayerCamera, PlayerSettings};
+use crate::terrain::{TerrainChunk, TerrainQuery};
+
+pub struct BookmarksPlugin;
+
+impl Plugin for BookmarksPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BookmarkPanel>()
+            .add_systems(Startup, (load_bookmarks, setup_bookmark_panel))
+            .add_systems(
+                Update,
+                (name_bookmark, add_bookmark, toggle_panel, choose_bookmark, update_bookmark_panel).chain(),
+            )
+            .add_systems(Update, teleport.after(player::player_movement));
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Bookmark {
+    pub name: String,
+    pub position: Vec3, // Feet
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+// Saved spots for the current world. Stored per terrain seed, since a position only
+// means something in the world it was taken in.
+#[derive(Resource, Default)]
+pub struct Bookmarks {
+    pub list: Vec<Bookmark>,
+    pub seed: u32,
+}
+
+impl Bookmarks {
+    fn file(seed: u32) -> String {
+        format!("config/bookmarks/world_{}.cfg", seed)
+    }
+
+    fn parse(text: &str, seed: u32) -> Self {
+        let mut bookmarks = Bookmarks { list: Vec::new(), seed };
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match parse_bookmark(line) {
+                Some(bookmark) => bookmarks.list.push(bookmark),
+                None => warn!("{}:{}: invalid bookmark '{}'", Bookmarks::file(seed), number + 1, line),
+            }
+        }
+        bookmarks
+    }
+
+    fn serialize(&self) -> String {
+        let mut text = String::from("# x y z yaw pitch name\n");
+        for bookmark in &self.list {
+            let p = bookmark.position;
+            text.push_str(&format!("{} {} {} {} {} {}\n", p.x, p.y, p.z, bookmark.yaw, bookmark.pitch, bookmark.name));
+        }
+        text
+    }
+
+    fn save(&self) {
+        let path = Bookmarks::file(self.seed);
+        if let Some(parent) = Path::new(&path).parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Err(error) = fs::write(&path, self.serialize()) {
+            warn!("Could not save bookmarks to {}: {}", path, error);
+        }
+    }
+}
+
+// Five numbers, then the rest of the line is the name
+fn parse_bookmark(line: &str) -> Option<Bookmark> {
+    let mut fields = line.splitn(6, ' ');
+    let mut number = || fields.next()?.parse::<f32>().ok().filter(|value| value.is_finite());
+    let (x, y, z, yaw, pitch) = (number()?, number()?, number()?, number()?, number()?);
+    let name = fields.next().unwrap_or("").trim().to_string();
+    Some(Bookmark {
+        name,
+        position: Vec3::new(x, y, z),
+        yaw,
+        pitch,
+    })
+}
+
+// Bookmarks on one page of the list, one for each slot action
+const SLOTS: usize = Action::BOOKMARK_SLOTS.len();
+
+#[derive(Resource, Default)]
+struct BookmarkPanel {
+    open: bool,
+    naming: Option<String>, // Name being typed for the newest bookmark
+    page: usize,
+}
+
+// A teleport in progress: the player hovers at the target until the chunks there have
+// streamed in and their colliders are indexed, then is put down on the ground
+#[derive(Component)]
+struct Teleporting {
+    target: Vec3,
+    settle_frames: u32,
+}
+
+// Frames to wait after the target chunks exist, so trees and props on them have spawned
+// and been added to the spatial index
+const SETTLE_FRAMES: u32 = 4;
+
+#[derive(Component)]
+struct BookmarkPanelRoot;
+
+#[derive(Component)]
+struct BookmarkPanelText;
+
+fn load_bookmarks(mut commands: Commands, terrain: TerrainQuery) {
+    let seed = terrain.config.seed;
+    let path = Bookmarks::file(seed);
+    let bookmarks = match fs::read_to_string(&path) {
+        Ok(text) => {
+            let bookmarks = Bookmarks::parse(&text, seed);
+            info!("Loaded {} bookmarks from {}", bookmarks.list.len(), path);
+            bookmarks
+        }
+        Err(_) => Bookmarks { list: Vec::new(), seed },
+    };
+    commands.insert_resource(bookmarks);
+}
+
+// Drop a bookmark where the player stands, named after the ground and position until
+// the player types a better name
+fn add_bookmark(
+    actions: Res<ActionState>,
+    terrain: TerrainQuery,
+    bookmarks: Option<ResMut<Bookmarks>>,
+    mut panel: ResMut<BookmarkPanel>,
+    mut text_entry: ResMut<TextEntry>,
+    body_query: Query<&Transform, With<Player>>,
+    camera_query: Query<&PlayerCamera>,
+) {
+    let Some(mut bookmarks) = bookmarks else {
+        return;
+    };
+    if !actions.just_pressed(Action::AddBookmark) {
+        return;
+    }
+    let (Ok(body), Ok(camera)) = (body_query.get_single(), camera_query.get_single()) else {
+        return;
+    };
+    let position = body.translation;
+    let (yaw, _, _) = body.rotation.to_euler(EulerRot::YXZ);
+    let name = format!(
+        "{:?} {:.0}, {:.0}",
+        terrain.biome(position.x, position.z),
+        position.x,
+        position.z
+    );
+    info!("Bookmark added: {}", name);
+    bookmarks.list.push(Bookmark {
+        name,
+        position,
+        yaw,
+        pitch: camera.pitch,
+    });
+    bookmarks.save();
+
+    panel.open = true;
+    panel.page = (bookmarks.list.len() - 1) / SLOTS;
+    panel.naming = Some(String::new());
+    text_entry.active = true;
+}
+
+// Typing while a new bookmark is being named; Enter keeps the typed name, Escape or
+// an empty name keeps the generated one. Runs before add_bookmark so the key that
+// started naming is not typed into the name.
+fn name_bookmark(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    bookmarks: Option<ResMut<Bookmarks>>,
+    mut panel: ResMut<BookmarkPanel>,
+    mut text_entry: ResMut<TextEntry>,
+) {
+    let (Some(mut bookmarks), Some(mut name)) = (bookmarks, panel.naming.take()) else {
+        keyboard_events.clear();
+        return;
+    };
+    let mut done = false;
+    for event in keyboard_events.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Enter => {
+                let name = name.trim();
+                if let Some(bookmark) = bookmarks.list.last_mut()
+                    && !name.is_empty()
+                {
+                    bookmark.name = name.to_string();
+                    bookmarks.save();
+                }
+                done = true;
+            }
+            Key::Escape => done = true,
+            Key::Backspace => {
+                name.pop();
+            }
+            Key::Space => name.push(' '),
+            Key::Character(text) => name.extend(text.chars().filter(|c| !c.is_control())),
+            _ => {}
+        }
+        if done {
+            break;
+        }
+    }
+    if done {
+        text_entry.active = false;
+    } else {
+        panel.naming = Some(name);
+    }
+}
+
+fn toggle_panel(actions: Res<ActionState>, mut panel: ResMut<BookmarkPanel>) {
+    if actions.just_pressed(Action::Bookmarks) {
+        panel.open = !panel.open;
+    }
+}
+
+// Slot actions (number keys by default) pick a bookmark on the open page; the next page
+// action (0) flips through the pages
+fn choose_bookmark(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    bookmarks: Option<Res<Bookmarks>>,
+    mut panel: ResMut<BookmarkPanel>,
+    mut body_query: Query<(Entity, &mut Transform, &mut Player)>,
+    mut camera_query: Query<&mut PlayerCamera>,
+) {
+    let Some(bookmarks) = bookmarks else {
+        return;
+    };
+    if !panel.open || panel.naming.is_some() {
+        return;
+    }
+    let pages = bookmarks.list.len().div_ceil(SLOTS).max(1);
+    if actions.just_pressed(Action::BookmarkNextPage) {
+        panel.page = (panel.page + 1) % pages;
+        return;
+    }
+    let Some(slot) = Action::BOOKMARK_SLOTS.iter().position(|slot| actions.just_pressed(*slot)) else {
+        return;
+    };
+    let Some(bookmark) = bookmarks.list.get(panel.page * SLOTS + slot) else {
+        return;
+    };
+    let (Ok((entity, mut transform, mut player)), Ok(mut camera)) =
+        (body_query.get_single_mut(), camera_query.get_single_mut())
+    else {
+        return;
+    };
+
+    info!("Teleporting to {}", bookmark.name);
+    transform.translation = bookmark.position;
+    transform.rotation = Quat::from_rotation_y(bookmark.yaw);
+    camera.pitch = bookmark.pitch;
+    player.velocity = Vec3::ZERO;
+    commands.entity(entity).insert(Teleporting {
+        target: bookmark.position,
+        settle_frames: SETTLE_FRAMES,
+    });
+    panel.open = false;
+}
+
+// Hold the body at the target while the world streams in around it, then stand it on
+// whatever is under the bookmark
+fn teleport(
+    mut commands: Commands,
+    terrain: TerrainQuery,
+    index: Res<SpatialIndex>,
+    settings: Res<PlayerSettings>,
+    chunk_query: Query<&TerrainChunk>,
+    mut body_query: Query<(Entity, &mut Transform, &mut Player, &mut Teleporting)>,
+) {
+    let Ok((entity, mut transform, mut player, mut teleporting)) = body_query.get_single_mut() else {
+        return;
+    };
+    let target = teleporting.target;
+    transform.translation = target;
+    player.velocity = Vec3::ZERO;
+    player.jump_buffer = 0.0;
+
+    // The chunk under the target and its neighbours must all exist
+    let size = terrain.config.chunk_size;
+    let centre = ((target.x / size).floor() as i32, (target.z / size).floor() as i32);
+    let loaded = (-1..=1).all(|dx| {
+        (-1..=1).all(|dz| {
+            chunk_query
+                .iter()
+                .any(|chunk| chunk.chunk_x == centre.0 + dx && chunk.chunk_z == centre.1 + dz)
+        })
+    });
+    if !loaded {
+        teleporting.settle_frames = SETTLE_FRAMES;
+        return;
+    }
+    if teleporting.settle_frames > 0 {
+        teleporting.settle_frames -= 1;
+        return;
+    }
+
+    // Terrain height, or the saved height if the bookmark was on top of a rock or log
+    // and that spot is still clear; otherwise push out of whatever grew there since
+    let ground = terrain.height(target.x, target.z).max(terrain.water_level() - settings.swim_depth);
+    let on_top = Vec3::new(target.x, target.y.max(ground), target.z);
+    let (correction, _) = player::resolve(&index, &settings, on_top, settings.eye_height);
+    let mut position = if correction.length() < 0.01 { on_top } else { Vec3::new(target.x, ground, target.z) };
+    let (correction, _) = player::resolve(&index, &settings, position, settings.eye_height);
+    position += correction;
+    position.y = position.y.max(terrain.height(position.x, position.z));
+
+    transform.translation = position;
+    player.on_ground = true;
+    player.air_time = 0.0;
+    player.state = MovementState::Walking;
+    commands.entity(entity).remove::<Teleporting>();
+    info!("Arrived at {:?}", position);
+}
+
+fn setup_bookmark_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(15.0),
+                    right: Val::Px(15.0),
+                    padding: UiRect::all(Val::Px(18.0)),
+                    min_width: Val::Px(300.0),
+                    ..default()
+                },
+                background_color: BackgroundColor(Color::srgba(0.08, 0.12, 0.18, 0.92)),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            BookmarkPanelRoot,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 18.0,
+                        color: Color::srgb(0.98, 0.98, 0.98),
+                        ..default()
+                    },
+                ),
+                BookmarkPanelText,
+            ));
+        });
+}
+
+fn update_bookmark_panel(
+    panel: Res<BookmarkPanel>,
+    input_map: Res<InputMap>,
+    bookmarks: Option<Res<Bookmarks>>,
+    body_query: Query<(&Transform, Option<&Teleporting>), With<Player>>,
+    mut root_query: Query<&mut Visibility, With<BookmarkPanelRoot>>,
+    mut text_query: Query<&mut Text, With<BookmarkPanelText>>,
+) {
+    let teleporting = body_query.get_single().is_ok_and(|(_, teleporting)| teleporting.is_some());
+    let visible = panel.open || teleporting;
+    for mut visibility in root_query.iter_mut() {
+        *visibility = if visible { Visibility::Visible } else { Visibility::Hidden };
+    }
+    let (Some(bookmarks), Ok(mut text)) = (bookmarks, text_query.get_single_mut()) else {
+        return;
+    };
+    if !visible {
+        return;
+    }
+
+    let mut info = format!("🔖 BOOKMARKS (world {})\n", bookmarks.seed);
+    info.push_str("━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+    if teleporting {
+        info.push_str("  Loading the area...\n");
+    } else if let Some(name) = &panel.naming {
+        info.push_str(&format!("  Name: {}_\n", name));
+        info.push_str("  [Enter] Save  [Esc] Keep default\n");
+    } else if bookmarks.list.is_empty() {
+        info.push_str("  No bookmarks yet\n");
+    } else {
+        // Each line shows the key its slot is bound to
+        let key = |action: Action| {
+            input_map.bindings_for(action).first().map_or_else(|| "-".to_string(), |binding| binding.label())
+        };
+        let here = body_query.get_single().map_or(Vec3::ZERO, |(transform, _)| transform.translation);
+        let page = bookmarks.list.iter().skip(panel.page * SLOTS).take(SLOTS);
+        for (slot, bookmark) in Action::BOOKMARK_SLOTS.into_iter().zip(page) {
+            info.push_str(&format!(
+                "  [{}] {:<20} {:>6.0} m\n",
+                key(slot),
+                bookmark.name,
+                bookmark.position.distance(here)
+            ));
+        }
+        let pages = bookmarks.list.len().div_ceil(SLOTS);
+        if pages > 1 {
+            info.push_str(&format!("  [{}] Next page ({}/{})\n", key(Action::BookmarkNextPage), panel.page + 1, pages));
+        }
+    }
+    text.sections[0].value = info;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bookmarks_survive_a_round_trip() {
+        let bookmarks = Bookmarks {
+            list: vec![
+                Bookmark {
+                    name: "Lake shore at dusk".to_string(),
+                    position: Vec3::new(12.5, 3.25, -40.0),
+                    yaw: 1.5,
+                    pitch: -0.25,
+                },
+                Bookmark {
+                    name: String::new(),
+                    position: Vec3::new(-1.0, 0.0, 2.0),
+                    yaw: 0.0,
+                    pitch: 0.0,
+                },
+            ],
+            seed: 7,
+        };
+        let parsed = Bookmarks::parse(&bookmarks.serialize(), 7);
+        assert_eq!(parsed.list.len(), 2);
+        for (parsed, original) in parsed.list.iter().zip(&bookmarks.list) {
+            assert_eq!(parsed.name, original.name);
+            assert_eq!(parsed.position, original.position);
+            assert_eq!((parsed.yaw, parsed.pitch), (original.yaw, original.pitch));
+        }
+    }
+
+    #[test]
+    fn invalid_bookmark_lines_are_skipped() {
+        let text = "\
+            # x y z yaw pitch name
+            1 2 3 0.5 0 Old oak
+            1 2 three 0.5 0 Typo
+            1 2 3 0.5
+            nan 2 3 0 0 Nowhere
+            4 5 6 0 0.1 Ridge
+        ";
+        let bookmarks = Bookmarks::parse(text, 0);
+        let names: Vec<&str> = bookmarks.list.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["Old oak", "Ridge"]);
+        assert_eq!(bookmarks.list[1].position, Vec3::new(4.0, 5.0, 6.0));
+    }
+}
@@ -0,0 +1,259 @@
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+use crate::collision::SpatialIndex;
+use crate::input::{Action, ActionState};
+use crate::player::{Player, PlayerCamera, PlayerSettings};
+use crate::terrain::TerrainQuery;
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraMode>()
+            .init_resource::<CameraSettings>()
+            .add_systems(Update, (spawn_avatar, cycle_camera_mode, zoom_orbit, update_avatar_visibility, crouch_avatar))
+            .add_systems(PostUpdate, update_camera_rig.before(TransformSystem::TransformPropagate));
+    }
+}
+
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CameraMode {
+    #[default]
+    FirstPerson,
+    ThirdPerson, // Follows behind the body and pulls in when something is in the way
+    Orbit,       // Circles a fixed point; the body stands still
+}
+
+impl CameraMode {
+    pub fn next(self) -> Self {
+        match self {
+            CameraMode::FirstPerson => CameraMode::ThirdPerson,
+            CameraMode::ThirdPerson => CameraMode::Orbit,
+            CameraMode::Orbit => CameraMode::FirstPerson,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct CameraSettings {
+    pub follow_distance: f32,
+    pub follow_height: f32, // Pivot height above the eyes in third person
+    pub orbit_distance: f32, // Starting distance when entering orbit mode
+    pub orbit_min_distance: f32,
+    pub orbit_max_distance: f32,
+    pub zoom_speed: f32,
+    pub collision_margin: f32, // Keep the camera this far from terrain and colliders
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            follow_distance: 4.5,
+            follow_height: 0.4,
+            orbit_distance: 8.0,
+            orbit_min_distance: 2.0,
+            orbit_max_distance: 40.0,
+            zoom_speed: 1.0,
+            collision_margin: 0.25,
+        }
+    }
+}
+
+// Body mesh shown in third-person and orbit views
+#[derive(Component)]
+pub struct PlayerAvatar;
+
+fn spawn_avatar(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    settings: Res<PlayerSettings>,
+    new_players: Query<Entity, Added<Player>>,
+) {
+    for body in new_players.iter() {
+        let radius = settings.collision_radius;
+        let height = settings.eye_height + 0.2;
+        let avatar = commands
+            .spawn((
+                PbrBundle {
+                    mesh: meshes.add(Capsule3d::new(radius, height - radius * 2.0)),
+                    material: materials.add(StandardMaterial {
+                        base_color: Color::srgb(0.55, 0.35, 0.2),
+                        perceptual_roughness: 0.8,
+                        ..default()
+                    }),
+                    transform: Transform::from_xyz(0.0, height / 2.0, 0.0),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                PlayerAvatar,
+            ))
+            .with_children(|avatar| {
+                // Visor so the facing direction reads from behind and from afar
+                avatar.spawn(PbrBundle {
+                    mesh: meshes.add(Cuboid::new(radius * 1.2, 0.15, 0.1)),
+                    material: materials.add(StandardMaterial {
+                        base_color: Color::srgb(0.15, 0.15, 0.2),
+                        ..default()
+                    }),
+                    transform: Transform::from_xyz(0.0, settings.eye_height - height / 2.0, -radius),
+                    ..default()
+                });
+            })
+            .id();
+        commands.entity(body).add_child(avatar);
+    }
+}
+
+fn cycle_camera_mode(
+    actions: Res<ActionState>,
+    mut mode: ResMut<CameraMode>,
+    camera_settings: Res<CameraSettings>,
+    settings: Res<PlayerSettings>,
+    body_query: Query<&Transform, With<Player>>,
+    mut camera_query: Query<&mut PlayerCamera>,
+) {
+    if !actions.just_pressed(Action::CameraMode) {
+        return;
+    }
+    *mode = mode.next();
+    info!("Camera mode: {:?}", *mode);
+
+    // Orbit around where the player is standing, starting from the current view
+    if *mode == CameraMode::Orbit
+        && let (Ok(body), Ok(mut camera)) = (body_query.get_single(), camera_query.get_single_mut())
+    {
+        let (yaw, _, _) = body.rotation.to_euler(EulerRot::YXZ);
+        camera.orbit_focus = body.translation + Vec3::Y * settings.eye_height;
+        camera.orbit_yaw = yaw;
+        camera.orbit_pitch = camera.pitch;
+        camera.orbit_distance = camera_settings.orbit_distance;
+    }
+}
+
+fn zoom_orbit(
+    mode: Res<CameraMode>,
+    camera_settings: Res<CameraSettings>,
+    mut wheel_events: EventReader<MouseWheel>,
+    mut camera_query: Query<&mut PlayerCamera>,
+) {
+    let scroll: f32 = wheel_events
+        .read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 40.0,
+        })
+        .sum();
+    if *mode != CameraMode::Orbit || scroll == 0.0 {
+        return;
+    }
+    if let Ok(mut camera) = camera_query.get_single_mut() {
+        // Zoom in proportion to the distance so it feels the same near and far
+        let distance = camera.orbit_distance * (1.0 - scroll * 0.1 * camera_settings.zoom_speed);
+        camera.orbit_distance = distance.clamp(camera_settings.orbit_min_distance, camera_settings.orbit_max_distance);
+    }
+}
+
+fn update_avatar_visibility(mode: Res<CameraMode>, mut avatar_query: Query<&mut Visibility, With<PlayerAvatar>>) {
+    if !mode.is_changed() {
+        return;
+    }
+    for mut visibility in avatar_query.iter_mut() {
+        *visibility = if *mode == CameraMode::FirstPerson { Visibility::Hidden } else { Visibility::Inherited };
+    }
+}
+
+// Squash the avatar down with the eye height while crouching or sliding
+fn crouch_avatar(
+    settings: Res<PlayerSettings>,
+    player_query: Query<&Player>,
+    mut avatar_query: Query<&mut Transform, With<PlayerAvatar>>,
+) {
+    let Ok(player) = player_query.get_single() else {
+        return;
+    };
+    let height = settings.eye_height + 0.2;
+    let scale = (player.eye_height + 0.2) / height;
+    for mut transform in avatar_query.iter_mut() {
+        transform.scale.y = scale;
+        transform.translation.y = height * scale / 2.0;
+    }
+}
+
+// How far the camera can back away from the pivot before hitting terrain or a collider
+fn clear_distance(
+    terrain: &TerrainQuery,
+    index: &SpatialIndex,
+    pivot: Vec3,
+    direction: Vec3,
+    max_distance: f32,
+    margin: f32,
+) -> f32 {
+    let step = 0.2;
+    let mut distance = step;
+    while distance <= max_distance {
+        let point = pivot + direction * distance;
+        if point.y < terrain.height(point.x, point.z) + margin || index.overlaps_sphere(point, margin) {
+            return (distance - step).max(0.0);
+        }
+        distance += step;
+    }
+    max_distance
+}
+
+// Rebuild the camera's local transform from its offsets and the active mode, so bob
+// and shake never accumulate into the body's position
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn update_camera_rig(
+    time: Res<Time>,
+    mode: Res<CameraMode>,
+    camera_settings: Res<CameraSettings>,
+    terrain: TerrainQuery,
+    index: Res<SpatialIndex>,
+    body_query: Query<(&Transform, &Player), Without<PlayerCamera>>,
+    mut camera_query: Query<(&mut Transform, &mut PlayerCamera)>,
+) {
+    let (Ok((body, player)), Ok((mut transform, mut camera))) = (body_query.get_single(), camera_query.get_single_mut()) else {
+        return;
+    };
+    let margin = camera_settings.collision_margin;
+
+    match *mode {
+        CameraMode::FirstPerson => {
+            transform.translation = Vec3::Y * player.eye_height + camera.bob_offset + camera.shake_offset;
+            transform.rotation = Quat::from_rotation_x(camera.pitch);
+        }
+        CameraMode::ThirdPerson => {
+            let rotation = Quat::from_rotation_x(camera.pitch);
+            let pivot = Vec3::Y * (player.eye_height + camera_settings.follow_height);
+            let back = rotation * Vec3::Z;
+            let clear = clear_distance(
+                &terrain,
+                &index,
+                body.transform_point(pivot),
+                body.rotation * back,
+                camera_settings.follow_distance,
+                margin,
+            );
+            // Snap in when blocked, ease back out once the view clears
+            camera.follow_distance = if clear < camera.follow_distance {
+                clear
+            } else {
+                camera.follow_distance.lerp(clear, 1.0 - (-4.0 * time.delta_seconds()).exp())
+            };
+            transform.translation = pivot + back * camera.follow_distance + camera.shake_offset;
+            transform.rotation = rotation;
+        }
+        CameraMode::Orbit => {
+            let rotation = Quat::from_euler(EulerRot::YXZ, camera.orbit_yaw, camera.orbit_pitch, 0.0);
+            let back = rotation * Vec3::Z;
+            let clear = clear_distance(&terrain, &index, camera.orbit_focus, back, camera.orbit_distance, margin);
+            let world = Transform::from_translation(camera.orbit_focus + back * clear + camera.shake_offset)
+                .with_rotation(rotation);
+            // The rig is parented to the body, so express the world pose relative to it
+            *transform = Transform::from_matrix(body.compute_matrix().inverse() * world.compute_matrix());
+        }
+    }
+}
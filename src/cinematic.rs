@@ -0,0 +1,336 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use std::fs;
+use std::path::Path;
+
+use crate::camera;
+use crate::day_night::{self, TimeOfDay};
+use crate::input::{Action, ActionState};
+use crate::player::{Player, PlayerCamera};
+
+pub struct CinematicPlugin;
+
+impl Plugin for CinematicPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CameraPath::load_or_default(PATH_FILE))
+            .init_resource::<PathPlayback>()
+            .add_systems(
+                Update,
+                (edit_path, control_playback, drive_time_of_day)
+                    .chain()
+                    .before(day_night::update_day_night_cycle),
+            )
+            .add_systems(Update, draw_path)
+            .add_systems(
+                PostUpdate,
+                play_path
+                    .after(camera::update_camera_rig)
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+const PATH_FILE: &str = "config/camera_path.cfg";
+
+// A camera pose placed in the world. The time of day is captured with it so a path can
+// also sweep the sky from dawn to dusk.
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe {
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub fov: f32, // Degrees
+    pub time_of_day: f32,
+}
+
+#[derive(Resource)]
+pub struct CameraPath {
+    pub keyframes: Vec<Keyframe>,
+    pub segment_duration: f32, // Seconds between neighbouring keyframes
+    pub drive_time_of_day: bool,
+}
+
+impl Default for CameraPath {
+    fn default() -> Self {
+        Self {
+            keyframes: Vec::new(),
+            segment_duration: 4.0,
+            drive_time_of_day: false,
+        }
+    }
+}
+
+impl CameraPath {
+    pub fn duration(&self) -> f32 {
+        self.keyframes.len().saturating_sub(1) as f32 * self.segment_duration
+    }
+
+    // Pose along the path for t in [0, 1]. Position, rotation, FOV and time of day all
+    // follow uniform Catmull-Rom splines through the keyframes.
+    pub fn sample(&self, t: f32) -> Option<Keyframe> {
+        let last = self.keyframes.len().checked_sub(1)?;
+        if last == 0 {
+            return Some(self.keyframes[0]);
+        }
+        let scaled = t.clamp(0.0, 1.0) * last as f32;
+        let segment = (scaled.floor() as usize).min(last - 1);
+        let local = scaled - segment as f32;
+        // Endpoints repeat so the spline passes through the first and last keyframes
+        let at = |i: isize| self.keyframes[i.clamp(0, last as isize) as usize];
+        let i = segment as isize;
+        let [k0, k1, k2, k3] = [at(i - 1), at(i), at(i + 1), at(i + 2)];
+
+        // Keep quaternions in one hemisphere so the blend takes the short way round
+        let q1 = k1.rotation;
+        let align = |q: Quat| if q.dot(q1) < 0.0 { -q } else { q };
+        let (q0, q2) = (align(k0.rotation), align(k2.rotation));
+        let q3 = if k3.rotation.dot(q2) < 0.0 { -k3.rotation } else { k3.rotation };
+        let rotation = Quat::from_vec4(catmull_rom(
+            Vec4::from(q0),
+            Vec4::from(q1),
+            Vec4::from(q2),
+            Vec4::from(q3),
+            local,
+        ))
+        .normalize();
+
+        // Times of day are unwrapped so the clock always runs forwards through midnight
+        let mut times = [k0.time_of_day, k1.time_of_day, k2.time_of_day, k3.time_of_day];
+        for j in 1..4 {
+            while times[j] < times[j - 1] {
+                times[j] += 24.0;
+            }
+        }
+
+        Some(Keyframe {
+            position: catmull_rom(k0.position, k1.position, k2.position, k3.position, local),
+            rotation,
+            fov: catmull_rom(k0.fov, k1.fov, k2.fov, k3.fov, local),
+            time_of_day: catmull_rom(times[0], times[1], times[2], times[3], local).rem_euclid(24.0),
+        })
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut path = CameraPath::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                warn!("{}:{}: expected `key = value`", PATH_FILE, number + 1);
+                continue;
+            };
+            let value = value.trim();
+            let parsed = match key.trim() {
+                "segment_duration" => value.parse().map(|v| path.segment_duration = v).is_ok(),
+                "drive_time_of_day" => value.parse().map(|v| path.drive_time_of_day = v).is_ok(),
+                "keyframe" => parse_keyframe(value).map(|k| path.keyframes.push(k)).is_some(),
+                _ => false,
+            };
+            if !parsed {
+                warn!("{}:{}: invalid line '{}'", PATH_FILE, number + 1, line);
+            }
+        }
+        path
+    }
+
+    fn serialize(&self) -> String {
+        let mut text = String::from("# Camera path: keyframe = x y z rotation_x rotation_y rotation_z rotation_w fov time_of_day\n");
+        text.push_str(&format!("segment_duration = {}\n", self.segment_duration));
+        text.push_str(&format!("drive_time_of_day = {}\n", self.drive_time_of_day));
+        for k in &self.keyframes {
+            let (p, r) = (k.position, k.rotation);
+            text.push_str(&format!(
+                "keyframe = {} {} {} {} {} {} {} {} {}\n",
+                p.x, p.y, p.z, r.x, r.y, r.z, r.w, k.fov, k.time_of_day
+            ));
+        }
+        text
+    }
+
+    fn load_or_default(path: &str) -> Self {
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                let camera_path = CameraPath::parse(&text);
+                info!("Loaded camera path with {} keyframes from {}", camera_path.keyframes.len(), path);
+                camera_path
+            }
+            Err(_) => CameraPath::default(),
+        }
+    }
+
+    fn save(&self, path: &str) {
+        if let Some(parent) = Path::new(path).parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Err(error) = fs::write(path, self.serialize()) {
+            warn!("Could not save camera path to {}: {}", path, error);
+        }
+    }
+}
+
+fn parse_keyframe(value: &str) -> Option<Keyframe> {
+    let numbers: Vec<f32> = value.split_whitespace().map(str::parse).collect::<Result<_, _>>().ok()?;
+    let [x, y, z, rx, ry, rz, rw, fov, time_of_day] = numbers[..] else {
+        return None;
+    };
+    Some(Keyframe {
+        position: Vec3::new(x, y, z),
+        rotation: Quat::from_xyzw(rx, ry, rz, rw).normalize(),
+        fov,
+        time_of_day,
+    })
+}
+
+fn catmull_rom<T>(p0: T, p1: T, p2: T, p3: T, t: f32) -> T
+where
+    T: Copy + std::ops::Add<Output = T> + std::ops::Sub<Output = T> + std::ops::Mul<f32, Output = T>,
+{
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p1 * 2.0 + (p2 - p0) * t + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2 + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+        * 0.5
+}
+
+// Whether a path is playing and how far along it is
+#[derive(Resource, Default)]
+pub struct PathPlayback {
+    pub playing: bool,
+    pub elapsed: f32,
+}
+
+impl PathPlayback {
+    // Eased progress: starts and stops gently rather than at full speed
+    fn progress(&self, path: &CameraPath) -> f32 {
+        let duration = path.duration();
+        if duration <= 0.0 {
+            return 1.0;
+        }
+        let t = (self.elapsed / duration).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+fn edit_path(
+    actions: Res<ActionState>,
+    playback: Res<PathPlayback>,
+    time_of_day: Res<TimeOfDay>,
+    mut path: ResMut<CameraPath>,
+    camera_query: Query<(&GlobalTransform, &Projection), With<PlayerCamera>>,
+) {
+    if playback.playing {
+        return;
+    }
+    let mut changed = false;
+    if actions.just_pressed(Action::PlaceKeyframe)
+        && let Ok((transform, projection)) = camera_query.get_single()
+    {
+        let (_, rotation, position) = transform.to_scale_rotation_translation();
+        let fov = match projection {
+            Projection::Perspective(perspective) => perspective.fov.to_degrees(),
+            Projection::Orthographic(_) => 60.0,
+        };
+        path.keyframes.push(Keyframe {
+            position,
+            rotation,
+            fov,
+            time_of_day: time_of_day.time,
+        });
+        info!("Camera keyframe {} placed", path.keyframes.len());
+        changed = true;
+    }
+    if actions.just_pressed(Action::RemoveKeyframe) && path.keyframes.pop().is_some() {
+        info!("Camera keyframe removed, {} left", path.keyframes.len());
+        changed = true;
+    }
+    if actions.just_pressed(Action::PathTimeOfDay) {
+        path.drive_time_of_day = !path.drive_time_of_day;
+        info!("Camera path drives time of day: {}", path.drive_time_of_day);
+        changed = true;
+    }
+    if changed {
+        path.save(PATH_FILE);
+    }
+}
+
+fn control_playback(
+    actions: Res<ActionState>,
+    time: Res<Time>,
+    path: Res<CameraPath>,
+    mut playback: ResMut<PathPlayback>,
+) {
+    if actions.just_pressed(Action::PlayPath) {
+        if playback.playing {
+            playback.playing = false;
+            info!("Camera path stopped");
+        } else if path.keyframes.len() >= 2 {
+            playback.playing = true;
+            playback.elapsed = 0.0;
+            info!("Playing camera path ({:.1}s)", path.duration());
+        } else {
+            info!("A camera path needs at least two keyframes");
+        }
+        return;
+    }
+    if playback.playing {
+        playback.elapsed += time.delta_seconds();
+        if playback.elapsed >= path.duration() {
+            playback.playing = false;
+            info!("Camera path finished");
+        }
+    }
+}
+
+// Hold the day/night clock on the path's time while it plays, and let it run freely again after
+fn drive_time_of_day(path: Res<CameraPath>, playback: Res<PathPlayback>, mut time_of_day: ResMut<TimeOfDay>) {
+    time_of_day.override_time = if playback.playing && path.drive_time_of_day {
+        path.sample(playback.progress(&path)).map(|k| k.time_of_day)
+    } else {
+        None
+    };
+}
+
+// The rig has already been placed for the current camera mode; replace that with the
+// path's world pose, expressed relative to the body it is parented to
+fn play_path(
+    path: Res<CameraPath>,
+    playback: Res<PathPlayback>,
+    body_query: Query<&Transform, (With<Player>, Without<PlayerCamera>)>,
+    mut camera_query: Query<(&mut Transform, &mut Projection), With<PlayerCamera>>,
+) {
+    if !playback.playing {
+        return;
+    }
+    let (Some(pose), Ok(body), Ok((mut transform, mut projection))) = (
+        path.sample(playback.progress(&path)),
+        body_query.get_single(),
+        camera_query.get_single_mut(),
+    ) else {
+        return;
+    };
+    let world = Transform::from_translation(pose.position).with_rotation(pose.rotation);
+    *transform = Transform::from_matrix(body.compute_matrix().inverse() * world.compute_matrix());
+    if let Projection::Perspective(perspective) = projection.as_mut() {
+        perspective.fov = pose.fov.to_radians();
+    }
+}
+
+// Keyframes and the spline through them, shown while editing
+fn draw_path(path: Res<CameraPath>, playback: Res<PathPlayback>, mut gizmos: Gizmos) {
+    if playback.playing || path.keyframes.is_empty() {
+        return;
+    }
+    for keyframe in &path.keyframes {
+        gizmos.sphere(keyframe.position, keyframe.rotation, 0.3, Color::srgb(1.0, 0.8, 0.2));
+        gizmos.arrow(
+            keyframe.position,
+            keyframe.position + keyframe.rotation * Vec3::NEG_Z * 1.5,
+            Color::srgb(1.0, 0.5, 0.1),
+        );
+    }
+    let steps = path.keyframes.len() * 16;
+    gizmos.linestrip(
+        (0..=steps).filter_map(|i| path.sample(i as f32 / steps as f32).map(|k| k.position)),
+        Color::srgb(0.9, 0.9, 0.3),
+    );
+}
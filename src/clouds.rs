@@ -0,0 +1,202 @@
+use bevy::pbr::{ExtendedMaterial, MaterialExtension};
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{AsBindGroup, Extent3d, ShaderRef, TextureDimension, TextureFormat};
+use bevy::render::texture::{ImageAddressMode, ImageFilterMode, ImageSampler, ImageSamplerDescriptor};
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+use std::f64::consts::TAU;
+
+use crate::day_night::{self, SunLight};
+use crate::sky;
+use crate::terrain::TerrainConfig;
+use crate::weather::Weather;
+use crate::wind::Wind;
+
+pub struct CloudsPlugin;
+
+impl Plugin for CloudsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<CloudShadowedMaterial>::default())
+            .init_resource::<CloudSettings>()
+            .init_resource::<CloudLayer>()
+            .add_systems(
+                Update,
+                (update_clouds, update_cloud_shadows)
+                    .chain()
+                    .after(day_night::update_day_night_cycle)
+                    .before(sky::update_sky),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct CloudSettings {
+    pub altitude: f32,        // Base of the layer, in metres
+    pub thickness: f32,       // Tallest clouds reach this far above the base
+    pub map_size: f32,        // Metres covered by one tile of the cloud map
+    pub drift_speed: f32,     // Metres per second in a calm; stronger wind carries them faster
+    pub shadow_strength: f32, // Share of direct sunlight a thick cloud takes away
+}
+
+impl Default for CloudSettings {
+    fn default() -> Self {
+        Self {
+            altitude: 300.0,
+            thickness: 150.0,
+            map_size: 2400.0,
+            drift_speed: 4.0,
+            shadow_strength: 0.6,
+        }
+    }
+}
+
+const MAP_RESOLUTION: u32 = 256;
+
+// The cloud layer shared by every shader that draws or is shaded by clouds. The map is a
+// tiling noise texture; shaders threshold it by the coverage, so more of it turns to cloud
+// as the weather closes in, and slide it by the drift as the wind carries it along.
+#[derive(Resource)]
+pub struct CloudLayer {
+    pub map: Handle<Image>,
+    pub drift: Vec2,   // Metres the layer has moved
+    pub coverage: f32, // 0 clear to 1 fully clouded over
+    pub sun: Vec4,     // xyz direction towards the sun, w shadow strength for the light now
+    // Keeps shaders/clouds.wgsl loaded so the other shaders can import it
+    _shader: Handle<Shader>,
+}
+
+impl FromWorld for CloudLayer {
+    fn from_world(world: &mut World) -> Self {
+        let seed = world.get_resource::<TerrainConfig>().map_or(0, |config| config.seed);
+        let map = world.resource_mut::<Assets<Image>>().add(cloud_map(seed ^ 0xc10d));
+        let shader = world.resource::<AssetServer>().load("shaders/clouds.wgsl");
+        Self {
+            map,
+            drift: Vec2::ZERO,
+            coverage: 0.0,
+            sun: Vec4::new(0.0, 1.0, 0.0, 0.0),
+            _shader: shader,
+        }
+    }
+}
+
+impl CloudLayer {
+    // Uniforms in the layout shaders/clouds.wgsl expects
+    pub fn params(&self) -> Vec4 {
+        Vec4::new(self.drift.x, self.drift.y, self.coverage, self.sun.w)
+    }
+
+    pub fn shape(&self, settings: &CloudSettings) -> Vec4 {
+        Vec4::new(settings.altitude, settings.thickness, 1.0 / settings.map_size, 0.0)
+    }
+}
+
+// Fractal noise wrapped round a torus in 4D so the texture tiles without seams, then
+// evened out so a coverage of 0.3 turns 30% of the map to cloud
+fn cloud_map(seed: u32) -> Image {
+    let noise = Fbm::<Perlin>::new(seed).set_octaves(5);
+    let size = MAP_RESOLUTION as usize;
+    let mut values = Vec::with_capacity(size * size);
+    for y in 0..size {
+        for x in 0..size {
+            let (a, b) = (x as f64 / size as f64 * TAU, y as f64 / size as f64 * TAU);
+            values.push(noise.get([a.cos(), a.sin(), b.cos(), b.sin()]));
+        }
+    }
+
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&i, &j| values[i].total_cmp(&values[j]));
+    let mut data = vec![0u8; values.len()];
+    for (rank, &index) in order.iter().enumerate() {
+        data[index] = (rank * 256 / values.len()) as u8;
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            width: MAP_RESOLUTION,
+            height: MAP_RESOLUTION,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::R8Unorm,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        mag_filter: ImageFilterMode::Linear,
+        min_filter: ImageFilterMode::Linear,
+        ..default()
+    });
+    image
+}
+
+// Standard material that also takes the clouds' shadows out of its direct sunlight
+pub type CloudShadowedMaterial = ExtendedMaterial<StandardMaterial, CloudShadows>;
+
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+pub struct CloudShadows {
+    #[uniform(100)]
+    pub clouds: Vec4, // CloudLayer::params
+    #[uniform(100)]
+    pub cloud_shape: Vec4, // CloudLayer::shape
+    #[uniform(100)]
+    pub sun: Vec4, // xyz direction towards the sun
+    #[texture(101)]
+    #[sampler(102)]
+    pub cloud_map: Handle<Image>,
+}
+
+impl CloudShadows {
+    pub fn new(layer: &CloudLayer) -> Self {
+        Self {
+            clouds: Vec4::ZERO,
+            cloud_shape: Vec4::ZERO,
+            sun: Vec4::Y,
+            cloud_map: layer.map.clone(),
+        }
+    }
+}
+
+impl MaterialExtension for CloudShadows {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/cloud_shadows.wgsl".into()
+    }
+}
+
+// Follow the weather's cloud cover, blow the layer along with the wind and project it
+// from the sun
+fn update_clouds(
+    time: Res<Time>,
+    settings: Res<CloudSettings>,
+    weather: Res<Weather>,
+    wind: Res<Wind>,
+    mut layer: ResMut<CloudLayer>,
+    sun_query: Query<(&GlobalTransform, &DirectionalLight), With<SunLight>>,
+) {
+    layer.coverage = weather.effects().cloud_cover;
+    let speed = settings.drift_speed * (1.0 + 3.0 * wind.strength);
+    layer.drift += wind.direction * speed * time.delta_seconds();
+    // Keep the drift within a tile so it stays precise on long runs
+    layer.drift = layer.drift.rem_euclid(Vec2::splat(settings.map_size));
+
+    if let Ok((sun_transform, light)) = sun_query.get_single() {
+        let to_sun = -Vec3::from(sun_transform.forward());
+        // Shadows fade with the sunlight, so they go with the sun at dusk and under overcast
+        let strength = (light.illuminance / 10000.0).clamp(0.0, 1.0) * settings.shadow_strength;
+        layer.sun = to_sun.extend(strength);
+    }
+}
+
+fn update_cloud_shadows(
+    settings: Res<CloudSettings>,
+    layer: Res<CloudLayer>,
+    mut materials: ResMut<Assets<CloudShadowedMaterial>>,
+) {
+    for (_, material) in materials.iter_mut() {
+        material.extension.clouds = layer.params();
+        material.extension.cloud_shape = layer.shape(&settings);
+        material.extension.sun = layer.sun;
+    }
+}
@@ -0,0 +1,321 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialIndex>()
+            .add_systems(PreUpdate, update_spatial_index);
+    }
+}
+
+// Static collision shape, in the entity's local space
+#[derive(Component, Clone, Copy, Debug)]
+pub enum Collider {
+    Capsule { radius: f32, height: f32 }, // Upright, standing on the entity origin
+    Sphere { center: Vec3, radius: f32 },
+    Box { center: Vec3, half_extents: Vec3 }, // Oriented with the entity
+}
+
+// Collider resolved into world space when it enters the index
+#[derive(Clone, Copy, Debug)]
+pub enum WorldCollider {
+    Capsule { a: Vec3, b: Vec3, radius: f32 },
+    Sphere { center: Vec3, radius: f32 },
+    Box { center: Vec3, half_extents: Vec3, rotation: Quat },
+}
+
+impl WorldCollider {
+    fn from_local(collider: &Collider, transform: &GlobalTransform) -> Self {
+        let (scale, rotation, translation) = transform.to_scale_rotation_translation();
+        let uniform_scale = scale.max_element();
+        match *collider {
+            Collider::Capsule { radius, height } => {
+                let radius = radius * uniform_scale;
+                let height = (height * uniform_scale).max(radius * 2.0);
+                WorldCollider::Capsule {
+                    a: translation + Vec3::Y * radius,
+                    b: translation + Vec3::Y * (height - radius),
+                    radius,
+                }
+            }
+            Collider::Sphere { center, radius } => WorldCollider::Sphere {
+                center: transform.transform_point(center),
+                radius: radius * uniform_scale,
+            },
+            Collider::Box { center, half_extents } => WorldCollider::Box {
+                center: transform.transform_point(center),
+                half_extents: half_extents * scale,
+                rotation,
+            },
+        }
+    }
+
+    // Horizontal bounds used to bucket the collider into grid cells
+    fn bounds(&self) -> (Vec2, Vec2) {
+        match *self {
+            WorldCollider::Capsule { a, b, radius } => {
+                let min = a.xz().min(b.xz()) - Vec2::splat(radius);
+                let max = a.xz().max(b.xz()) + Vec2::splat(radius);
+                (min, max)
+            }
+            WorldCollider::Sphere { center, radius } => {
+                (center.xz() - Vec2::splat(radius), center.xz() + Vec2::splat(radius))
+            }
+            WorldCollider::Box { center, half_extents, .. } => {
+                let reach = half_extents.length();
+                (center.xz() - Vec2::splat(reach), center.xz() + Vec2::splat(reach))
+            }
+        }
+    }
+
+    // Closest point on the collider surface region to a point (the point itself if inside)
+    fn closest_point(&self, point: Vec3) -> Vec3 {
+        match *self {
+            WorldCollider::Capsule { a, b, radius } => {
+                let on_axis = closest_point_on_segment(point, a, b);
+                let offset = point - on_axis;
+                if offset.length() <= radius {
+                    point
+                } else {
+                    on_axis + offset.normalize() * radius
+                }
+            }
+            WorldCollider::Sphere { center, radius } => {
+                let offset = point - center;
+                if offset.length() <= radius {
+                    point
+                } else {
+                    center + offset.normalize() * radius
+                }
+            }
+            WorldCollider::Box { center, half_extents, rotation } => {
+                let local = rotation.inverse() * (point - center);
+                center + rotation * local.clamp(-half_extents, half_extents)
+            }
+        }
+    }
+
+    // Highest point of the collider, used to find ledges to climb onto
+    pub fn top(&self) -> f32 {
+        match *self {
+            WorldCollider::Capsule { a, b, radius } => a.y.max(b.y) + radius,
+            WorldCollider::Sphere { center, radius } => center.y + radius,
+            WorldCollider::Box { center, half_extents, rotation } => {
+                let reach = (rotation * Vec3::X * half_extents.x).y.abs()
+                    + (rotation * Vec3::Y * half_extents.y).y.abs()
+                    + (rotation * Vec3::Z * half_extents.z).y.abs();
+                center.y + reach
+            }
+        }
+    }
+
+    // Penetration of an upright capsule into this collider, as a push-out vector
+    fn capsule_penetration(&self, bottom: Vec3, top: Vec3, radius: f32) -> Option<Vec3> {
+        let (on_player, on_collider, extra_radius) = match *self {
+            WorldCollider::Capsule { a, b, radius } => {
+                let (p, q) = closest_points_between_segments(bottom, top, a, b);
+                (p, q, radius)
+            }
+            WorldCollider::Sphere { center, radius } => (closest_point_on_segment(center, bottom, top), center, radius),
+            WorldCollider::Box { .. } => {
+                // Alternate between the two shapes a few times to converge on the closest pair
+                let mut on_player = closest_point_on_segment(self.closest_point((bottom + top) * 0.5), bottom, top);
+                let mut on_box = self.closest_point(on_player);
+                for _ in 0..3 {
+                    on_player = closest_point_on_segment(on_box, bottom, top);
+                    on_box = self.closest_point(on_player);
+                }
+                (on_player, on_box, 0.0)
+            }
+        };
+
+        let offset = on_player - on_collider;
+        let distance = offset.length();
+        let reach = radius + extra_radius;
+        if distance >= reach {
+            return None;
+        }
+
+        // Degenerate case: the player axis passes through the collider core
+        let normal = if distance > 1e-4 {
+            offset / distance
+        } else {
+            let away = (bottom - on_collider).with_y(0.0);
+            if away.length_squared() > 1e-6 { away.normalize() } else { Vec3::X }
+        };
+        Some(normal * (reach - distance))
+    }
+}
+
+// Uniform grid over the XZ plane holding every static collider
+#[derive(Resource)]
+pub struct SpatialIndex {
+    pub cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<Entity>>,
+    colliders: HashMap<Entity, WorldCollider>,
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        Self {
+            cell_size: 8.0,
+            cells: HashMap::new(),
+            colliders: HashMap::new(),
+        }
+    }
+}
+
+impl SpatialIndex {
+    fn cell_range(&self, min: Vec2, max: Vec2) -> impl Iterator<Item = (i32, i32)> {
+        let min_x = (min.x / self.cell_size).floor() as i32;
+        let min_z = (min.y / self.cell_size).floor() as i32;
+        let max_x = (max.x / self.cell_size).floor() as i32;
+        let max_z = (max.y / self.cell_size).floor() as i32;
+        (min_x..=max_x).flat_map(move |x| (min_z..=max_z).map(move |z| (x, z)))
+    }
+
+    pub fn insert(&mut self, entity: Entity, collider: WorldCollider) {
+        self.remove(entity);
+        let (min, max) = collider.bounds();
+        let cells: Vec<(i32, i32)> = self.cell_range(min, max).collect();
+        for cell in cells {
+            self.cells.entry(cell).or_default().push(entity);
+        }
+        self.colliders.insert(entity, collider);
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        let Some(collider) = self.colliders.remove(&entity) else {
+            return;
+        };
+        let (min, max) = collider.bounds();
+        let cells: Vec<(i32, i32)> = self.cell_range(min, max).collect();
+        for cell in cells {
+            if let Some(entities) = self.cells.get_mut(&cell) {
+                entities.retain(|e| *e != entity);
+                if entities.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+
+    // Colliders whose cells overlap a horizontal rectangle; each is returned once
+    pub fn query(&self, min: Vec2, max: Vec2) -> Vec<WorldCollider> {
+        let mut seen = Vec::new();
+        let mut result = Vec::new();
+        for cell in self.cell_range(min, max) {
+            for entity in self.cells.get(&cell).into_iter().flatten() {
+                if !seen.contains(entity) {
+                    seen.push(*entity);
+                    result.push(self.colliders[entity]);
+                }
+            }
+        }
+        result
+    }
+
+    pub fn overlaps_sphere(&self, center: Vec3, radius: f32) -> bool {
+        !self.overlapping(center, radius).is_empty()
+    }
+
+    // Colliders touching a sphere
+    pub fn overlapping(&self, center: Vec3, radius: f32) -> Vec<WorldCollider> {
+        let reach = Vec2::splat(radius);
+        self.query(center.xz() - reach, center.xz() + reach)
+            .into_iter()
+            .filter(|collider| collider.capsule_penetration(center, center, radius).is_some())
+            .collect()
+    }
+
+    // Push an upright capsule out of every collider it overlaps. Returns the total
+    // correction and the contact normals so the caller can slide its velocity.
+    pub fn resolve_capsule(&self, bottom: Vec3, top: Vec3, radius: f32) -> (Vec3, Vec<Vec3>) {
+        let reach = Vec2::splat(radius + 0.5);
+        let nearby = self.query(bottom.xz().min(top.xz()) - reach, bottom.xz().max(top.xz()) + reach);
+
+        let mut correction = Vec3::ZERO;
+        let mut normals = Vec::new();
+        for _ in 0..4 {
+            let mut deepest: Option<Vec3> = None;
+            for collider in &nearby {
+                if let Some(push) = collider.capsule_penetration(bottom + correction, top + correction, radius)
+                    && deepest.is_none_or(|d| push.length_squared() > d.length_squared())
+                {
+                    deepest = Some(push);
+                }
+            }
+            let Some(push) = deepest else {
+                break;
+            };
+            correction += push;
+            normals.push(push.normalize());
+        }
+        (correction, normals)
+    }
+}
+
+fn closest_point_on_segment(point: Vec3, a: Vec3, b: Vec3) -> Vec3 {
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+    if length_squared < 1e-8 {
+        return a;
+    }
+    let t = ((point - a).dot(ab) / length_squared).clamp(0.0, 1.0);
+    a + ab * t
+}
+
+// Closest points between segments p1-q1 and p2-q2 (Ericson, Real-Time Collision Detection 5.1.9)
+fn closest_points_between_segments(p1: Vec3, q1: Vec3, p2: Vec3, q2: Vec3) -> (Vec3, Vec3) {
+    let d1 = q1 - p1;
+    let d2 = q2 - p2;
+    let r = p1 - p2;
+    let a = d1.length_squared();
+    let e = d2.length_squared();
+    let f = d2.dot(r);
+
+    let (s, t) = if a <= 1e-8 && e <= 1e-8 {
+        (0.0, 0.0)
+    } else if a <= 1e-8 {
+        (0.0, (f / e).clamp(0.0, 1.0))
+    } else {
+        let c = d1.dot(r);
+        if e <= 1e-8 {
+            ((-c / a).clamp(0.0, 1.0), 0.0)
+        } else {
+            let b = d1.dot(d2);
+            let denom = a * e - b * b;
+            let mut s = if denom > 1e-8 { ((b * f - c * e) / denom).clamp(0.0, 1.0) } else { 0.0 };
+            let mut t = (b * s + f) / e;
+            if t < 0.0 {
+                t = 0.0;
+                s = (-c / a).clamp(0.0, 1.0);
+            } else if t > 1.0 {
+                t = 1.0;
+                s = ((b - c) / a).clamp(0.0, 1.0);
+            }
+            (s, t)
+        }
+    };
+
+    (p1 + d1 * s, p2 + d2 * t)
+}
+
+type ColliderChanged = Or<(Added<Collider>, Changed<GlobalTransform>)>;
+
+// Keep the index in sync with collider entities as chunks stream in and out. Colliders
+// that move or are rescaled (trees growing up) are re-inserted at their new size.
+fn update_spatial_index(
+    mut index: ResMut<SpatialIndex>,
+    changed: Query<(Entity, &Collider, &GlobalTransform), ColliderChanged>,
+    mut removed: RemovedComponents<Collider>,
+) {
+    for entity in removed.read() {
+        index.remove(entity);
+    }
+    for (entity, collider, transform) in changed.iter() {
+        index.insert(entity, WorldCollider::from_local(collider, transform));
+    }
+}
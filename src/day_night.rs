@@ -1,20 +1,43 @@
 use bevy::prelude::*;
 use std::f32::consts::PI;
 
+use crate::input::{Action, ActionState};
+use crate::lighting::{LightingProfile, PROFILE_SUNRISE, PROFILE_SUNSET};
+use crate::weather::Weather;
+
 pub struct DayNightPlugin;
 
 impl Plugin for DayNightPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<TimeOfDay>()
+            .add_event::<SunriseStarted>()
+            .add_event::<DayStarted>()
+            .add_event::<SunsetStarted>()
+            .add_event::<NightStarted>()
             .add_systems(Startup, setup_lighting)
-            .add_systems(Update, (update_day_night_cycle, update_sky_color));
+            .add_systems(
+                Update,
+                (
+                    control_time.before(update_day_night_cycle),
+                    update_day_night_cycle,
+                    send_day_phase_events.after(update_day_night_cycle),
+                    apply_lighting_profile,
+                ),
+            );
     }
 }
 
-#[derive(Resource)]
+#[derive(Resource, Clone)]
 pub struct TimeOfDay {
     pub time: f32, // 0.0 to 24.0
     pub speed: f32, // Time multiplier
+    pub time_scale: f32, // Fast-forward on top of the normal speed
+    pub paused: bool,
+    pub day: u32, // Whole days elapsed since the start
+    pub override_time: Option<f32>, // Holds the clock at this time instead of advancing, e.g. during a camera path
+    pub latitude: f32,    // Degrees north; negative is the southern hemisphere
+    pub day_of_year: u32, // 0 is the 1st of January; moved on by the season clock
+    pub axial_tilt: f32,  // Degrees; sets how much the seasons change the sun's path
 }
 
 impl Default for TimeOfDay {
@@ -22,13 +45,193 @@ impl Default for TimeOfDay {
         Self {
             time: 12.0, // Start at noon
             speed: 0.1, // Slow time progression
+            time_scale: 1.0,
+            paused: false,
+            day: 0,
+            override_time: None,
+            latitude: 47.0,
+            day_of_year: 110, // Late April
+            axial_tilt: 23.44,
         }
     }
 }
 
+const DAYS_PER_YEAR: u32 = 365;
+const SYNODIC_MONTH: f32 = 29.53; // New moon to new moon, in days
+const SIDEREAL_MONTH: f32 = 27.32; // One lunar orbit, which sets the moon's declination cycle
+const LUNAR_INCLINATION: f32 = 5.14; // Degrees between the moon's orbit and the ecliptic
+const MOON_PHASE_AT_START: f32 = 0.35; // Waxing gibbous on day 0
+const TWILIGHT_ELEVATION: f32 = -6.0; // Civil twilight: sunrise starts and night falls with the sun here
+const DAYLIGHT_ELEVATION: f32 = 6.0; // Full day starts and sunset begins with the sun here
+const TIME_SCALES: [f32; 3] = [1.0, 10.0, 60.0]; // Fast-forward steps
+const PHASE_WALK_STEP: f32 = 0.25; // Hours between probes of the sun when the clock jumps ahead
+const PHASE_WALK_LIMIT: f32 = 48.0; // Longer jumps only announce the phase they land in
+
+// Parts of the day, by the height of the sun
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DayPhase {
+    Night,
+    Sunrise,
+    Day,
+    Sunset,
+}
+
+// Sent as the sun crosses into each part of the day, so other systems can react to the
+// change rather than each watching the clock. A jump ahead of up to two days sends every
+// phase crossed on the way; turning the clock back sends only the phase it lands in.
+#[derive(Event)]
+pub struct SunriseStarted;
+
+#[derive(Event)]
+pub struct DayStarted;
+
+#[derive(Event)]
+pub struct SunsetStarted;
+
+#[derive(Event)]
+pub struct NightStarted;
+
+impl TimeOfDay {
+    // Days since the start, including the fraction of today
+    fn elapsed_days(&self) -> f32 {
+        self.day as f32 + self.time / 24.0
+    }
+
+    // Sun's declination for the current date, in degrees
+    pub fn solar_declination(&self) -> f32 {
+        let year = (self.day_of_year as f32 + self.time / 24.0 + 10.0) / DAYS_PER_YEAR as f32;
+        -self.axial_tilt * (year * 2.0 * PI).cos()
+    }
+
+    // Unit vector pointing at the sun. East is +X, north is -Z. The clock is local solar
+    // time, so the sun is due south (north of the equator) at 12:00.
+    pub fn sun_direction(&self) -> Vec3 {
+        let hour_angle = (self.time - 12.0) * 15.0;
+        sky_direction(self.latitude, self.solar_declination(), hour_angle)
+    }
+
+    // Degrees above the horizon; negative at night
+    pub fn sun_elevation(&self) -> f32 {
+        self.sun_direction().y.clamp(-1.0, 1.0).asin().to_degrees()
+    }
+
+    // Hours between sunrise and sunset today
+    pub fn day_length(&self) -> f32 {
+        let cos_hour_angle = -self.latitude.to_radians().tan() * self.solar_declination().to_radians().tan();
+        2.0 * cos_hour_angle.clamp(-1.0, 1.0).acos().to_degrees() / 15.0
+    }
+
+    // The hour to sample the lighting profile at: the same share of the way through the
+    // day or the night on the profile's reference day as the clock is today
+    pub fn profile_hour(&self) -> f32 {
+        let half_day = self.day_length() * 0.5;
+        let (sunrise, sunset) = (12.0 - half_day, 12.0 + half_day);
+        if self.time >= sunrise && self.time < sunset {
+            let t = (self.time - sunrise) / (sunset - sunrise);
+            return PROFILE_SUNRISE + t * (PROFILE_SUNSET - PROFILE_SUNRISE);
+        }
+        let t = (self.time - sunset).rem_euclid(24.0) / (24.0 - (sunset - sunrise));
+        (PROFILE_SUNSET + t * (24.0 - (PROFILE_SUNSET - PROFILE_SUNRISE))).rem_euclid(24.0)
+    }
+
+    // 0 is new moon, 0.5 is full moon
+    pub fn moon_phase(&self) -> f32 {
+        (MOON_PHASE_AT_START + self.elapsed_days() / SYNODIC_MONTH).fract()
+    }
+
+    // Lit fraction of the moon's disc as seen from the ground
+    pub fn moon_illumination(&self) -> f32 {
+        (1.0 - (self.moon_phase() * 2.0 * PI).cos()) * 0.5
+    }
+
+    // The moon trails the sun across the sky by its phase, about 50 minutes later each
+    // day, and swings north and south of the equator once per orbit
+    pub fn moon_direction(&self) -> Vec3 {
+        let hour_angle = (self.time - 12.0) * 15.0 - self.moon_phase() * 360.0;
+        let orbit = self.elapsed_days() / SIDEREAL_MONTH * 2.0 * PI;
+        let declination = (self.axial_tilt + LUNAR_INCLINATION) * orbit.sin();
+        sky_direction(self.latitude, declination, hour_angle)
+    }
+
+    pub fn is_day(&self) -> bool {
+        self.sun_elevation() > 0.0
+    }
+
+    pub fn day_phase(&self) -> DayPhase {
+        let elevation = self.sun_elevation();
+        if elevation < TWILIGHT_ELEVATION {
+            DayPhase::Night
+        } else if elevation >= DAYLIGHT_ELEVATION {
+            DayPhase::Day
+        } else if self.time < 12.0 {
+            DayPhase::Sunrise
+        } else {
+            DayPhase::Sunset
+        }
+    }
+
+    // Clock time in the morning when the sun climbs through `elevation` degrees; None if
+    // it stays above or below that all day, as near the poles
+    fn morning_hour_at(&self, elevation: f32) -> Option<f32> {
+        let (sin_lat, cos_lat) = self.latitude.to_radians().sin_cos();
+        let (sin_dec, cos_dec) = self.solar_declination().to_radians().sin_cos();
+        let cos_hour_angle = (elevation.to_radians().sin() - sin_lat * sin_dec) / (cos_lat * cos_dec);
+        (-1.0..=1.0)
+            .contains(&cos_hour_angle)
+            .then(|| 12.0 - cos_hour_angle.acos().to_degrees() / 15.0)
+    }
+
+    // Move the clock on by some hours, counting the days passed. Negative hours turn it
+    // back, across midnight into the day before, but never before the first morning.
+    pub fn advance(&mut self, hours: f32) {
+        let time = self.time + hours;
+        let days = (time / 24.0).floor() as i64;
+        if self.day as i64 + days < 0 {
+            self.day = 0;
+            self.time = 0.0;
+            return;
+        }
+        self.day = (self.day as i64 + days) as u32;
+        self.time = time.rem_euclid(24.0);
+    }
+
+    // Set the clock to an hour of the current day
+    pub fn set_time(&mut self, hour: f32) {
+        self.time = hour.rem_euclid(24.0);
+    }
+
+    // Move the clock on to the start of the next sunrise. Returns false if the sun does
+    // not rise today.
+    pub fn skip_to_dawn(&mut self) -> bool {
+        let Some(dawn) = self.morning_hour_at(TWILIGHT_ELEVATION) else {
+            return false;
+        };
+        // A minute past, so the sun is already on its way up
+        let dawn = dawn + 1.0 / 60.0;
+        let hours = if dawn > self.time { dawn - self.time } else { dawn + 24.0 - self.time };
+        self.advance(hours);
+        true
+    }
+}
+
+// Direction to a body at the given declination and hour angle (both in degrees) for an
+// observer at the given latitude
+fn sky_direction(latitude: f32, declination: f32, hour_angle: f32) -> Vec3 {
+    let (sin_lat, cos_lat) = latitude.to_radians().sin_cos();
+    let (sin_dec, cos_dec) = declination.to_radians().sin_cos();
+    let (sin_hour, cos_hour) = hour_angle.to_radians().sin_cos();
+    let east = -cos_dec * sin_hour;
+    let north = cos_lat * sin_dec - sin_lat * cos_dec * cos_hour;
+    let up = sin_lat * sin_dec + cos_lat * cos_dec * cos_hour;
+    Vec3::new(east, up, -north).normalize()
+}
+
 #[derive(Component)]
 pub struct SunLight;
 
+#[derive(Component)]
+pub struct MoonLight;
+
 fn setup_lighting(mut commands: Commands) {
     // Spawn directional light (sun)
     commands.spawn((
@@ -46,73 +249,232 @@ fn setup_lighting(mut commands: Commands) {
         },
         SunLight,
     ));
+
+    // Dim, cool moonlight; no shadows to keep nights cheap
+    commands.spawn((
+        DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                color: Color::srgb(0.65, 0.72, 0.95),
+                illuminance: 0.0,
+                shadows_enabled: false,
+                ..default()
+            },
+            ..default()
+        },
+        MoonLight,
+    ));
 }
 
-fn update_day_night_cycle(
+pub(crate) fn update_day_night_cycle(
     time: Res<Time>,
     mut time_of_day: ResMut<TimeOfDay>,
-    mut light_query: Query<&mut Transform, With<SunLight>>,
+    mut sun_query: Query<&mut Transform, (With<SunLight>, Without<MoonLight>)>,
+    mut moon_query: Query<(&mut Transform, &mut DirectionalLight), With<MoonLight>>,
 ) {
     // Update time
-    time_of_day.time += time.delta_seconds() * time_of_day.speed;
-    if time_of_day.time >= 24.0 {
-        time_of_day.time -= 24.0;
+    if let Some(time) = time_of_day.override_time {
+        time_of_day.set_time(time);
+    } else if !time_of_day.paused {
+        let hours = time.delta_seconds() * time_of_day.speed * time_of_day.time_scale;
+        time_of_day.advance(hours);
     }
 
-    // Calculate sun position (0 = midnight, 12 = noon)
-    let sun_angle = (time_of_day.time / 24.0) * 2.0 * PI - PI / 2.0;
-    
-    // Update sun rotation
-    if let Ok(mut sun_transform) = light_query.get_single_mut() {
-        sun_transform.rotation = Quat::from_rotation_x(sun_angle);
+    // Directional lights shine along their forward axis, away from the body in the sky
+    if let Ok(mut sun_transform) = sun_query.get_single_mut() {
+        sun_transform.rotation = Quat::from_rotation_arc(Vec3::NEG_Z, -time_of_day.sun_direction());
+    }
+
+    if let Ok((mut moon_transform, mut moon_light)) = moon_query.get_single_mut() {
+        let direction = time_of_day.moon_direction();
+        moon_transform.rotation = Quat::from_rotation_arc(Vec3::NEG_Z, -direction);
+        // Brightest at full moon and high in the sky; washed out while the sun is up
+        let above_horizon = smoothstep(-2.0, 10.0, direction.y.asin().to_degrees());
+        let night = 1.0 - smoothstep(-6.0, 6.0, time_of_day.sun_elevation());
+        moon_light.illuminance = 2000.0 * (0.15 + 0.85 * time_of_day.moon_illumination()) * above_horizon * night;
+    }
+}
+
+// Pause, fast-forward, step the clock an hour at a time or skip to dawn
+fn control_time(actions: Res<ActionState>, mut time_of_day: ResMut<TimeOfDay>) {
+    if actions.just_pressed(Action::PauseTime) {
+        time_of_day.paused = !time_of_day.paused;
+        info!("Time {}", if time_of_day.paused { "paused" } else { "running" });
+    }
+    if actions.just_pressed(Action::FastForward) {
+        let next = TIME_SCALES
+            .iter()
+            .position(|&scale| scale == time_of_day.time_scale)
+            .map_or(0, |i| (i + 1) % TIME_SCALES.len());
+        time_of_day.time_scale = TIME_SCALES[next];
+        info!("Time speed: x{}", time_of_day.time_scale);
+    }
+    if actions.just_pressed(Action::HourForward) {
+        time_of_day.advance(1.0);
+    }
+    if actions.just_pressed(Action::HourBack) {
+        time_of_day.advance(-1.0);
+    }
+    if actions.just_pressed(Action::SkipToDawn) && !time_of_day.skip_to_dawn() {
+        info!("The sun does not rise today");
     }
 }
 
-fn update_sky_color(
+// Watch the sun cross the phase thresholds and announce each new part of the day. When the
+// clock jumps ahead, e.g. skipping to dawn, the skipped hours are stepped through so every
+// phase crossed on the way is announced in order.
+pub(crate) fn send_day_phase_events(
     time_of_day: Res<TimeOfDay>,
-    mut clear_color: ResMut<ClearColor>,
-    mut light_query: Query<&mut DirectionalLight, With<SunLight>>,
+    mut last: Local<Option<(DayPhase, u32, f32)>>, // Phase, day and time at the last check
+    mut sunrise: EventWriter<SunriseStarted>,
+    mut day: EventWriter<DayStarted>,
+    mut sunset: EventWriter<SunsetStarted>,
+    mut night: EventWriter<NightStarted>,
 ) {
-    let t = time_of_day.time;
-    
-    // Calculate light intensity and color based on time
-    let (intensity, color, sky_color) = if t >= 6.0 && t < 8.0 {
-        // Dawn
-        let factor = (t - 6.0) / 2.0;
-        (
-            5000.0 + factor * 5000.0,
-            Color::srgb(1.0, 0.7 + factor * 0.25, 0.5 + factor * 0.3),
-            Color::srgb(0.3 + factor * 0.2, 0.4 + factor * 0.3, 0.5 + factor * 0.2),
-        )
-    } else if t >= 8.0 && t < 18.0 {
-        // Day
-        (
-            10000.0,
-            Color::srgb(1.0, 0.95, 0.8),
-            Color::srgb(0.5, 0.7, 1.0),
-        )
-    } else if t >= 18.0 && t < 20.0 {
-        // Dusk
-        let factor = (t - 18.0) / 2.0;
-        (
-            10000.0 - factor * 8000.0,
-            Color::srgb(1.0, 0.95 - factor * 0.65, 0.8 - factor * 0.5),
-            Color::srgb(0.5 - factor * 0.45, 0.7 - factor * 0.65, 1.0 - factor * 0.8),
-        )
-    } else {
-        // Night
-        (
-            2000.0,
-            Color::srgb(0.3, 0.3, 0.8),
-            Color::srgb(0.05, 0.05, 0.2),
-        )
+    let phase = time_of_day.day_phase();
+    // Nothing to announce on the first frame; listeners start from TimeOfDay::day_phase
+    let Some((mut last_phase, last_day, last_time)) = last.replace((phase, time_of_day.day, time_of_day.time)) else {
+        return;
     };
+    let hours = (time_of_day.day as i64 - last_day as i64) as f32 * 24.0 + time_of_day.time - last_time;
+
+    let mut crossed = Vec::new();
+    // Turning the clock back, or a jump of days, only announces where it ends up
+    if hours > 0.0 && hours <= PHASE_WALK_LIMIT {
+        let steps = (hours / PHASE_WALK_STEP).ceil();
+        let mut probe = time_of_day.clone();
+        probe.day = last_day;
+        probe.time = last_time;
+        for _ in 0..steps as u32 {
+            probe.advance(hours / steps);
+            let probed = probe.day_phase();
+            if probed != last_phase {
+                crossed.push(probed);
+                last_phase = probed;
+            }
+        }
+    }
+    if last_phase != phase {
+        crossed.push(phase);
+    }
+
+    for phase in crossed {
+        match phase {
+            DayPhase::Sunrise => {
+                sunrise.send(SunriseStarted);
+            }
+            DayPhase::Day => {
+                day.send(DayStarted);
+            }
+            DayPhase::Sunset => {
+                sunset.send(SunsetStarted);
+            }
+            DayPhase::Night => {
+                night.send(NightStarted);
+            }
+        }
+    }
+}
 
-    clear_color.0 = sky_color;
-    
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Sun and ambient light follow the lighting profile for the time of day, dimmed by cloud.
+// The profile is sampled relative to today's sunrise and sunset.
+fn apply_lighting_profile(
+    time_of_day: Res<TimeOfDay>,
+    profile: Res<LightingProfile>,
+    weather: Res<Weather>,
+    mut ambient_light: ResMut<AmbientLight>,
+    mut light_query: Query<&mut DirectionalLight, With<SunLight>>,
+) {
+    let hour = time_of_day.profile_hour();
     if let Ok(mut light) = light_query.get_single_mut() {
-        light.color = color;
-        light.illuminance = intensity;
+        let color = profile.sun_color.sample(hour);
+        light.color = Color::srgb(color.x, color.y, color.z);
+        // Out whenever the sun is below the horizon, wherever the curve's keys are
+        let above_horizon = smoothstep(-2.0, 2.0, time_of_day.sun_elevation());
+        light.illuminance = profile.sun_illuminance.sample(hour).max(0.0) * above_horizon * weather.effects().sunlight;
     }
+    let ambient = profile.ambient_color.sample(hour);
+    ambient_light.color = Color::srgb(ambient.x, ambient.y, ambient.z);
+    ambient_light.brightness = profile.ambient_brightness.sample(hour).max(0.0);
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(latitude: f32, day_of_year: u32, time: f32) -> TimeOfDay {
+        TimeOfDay {
+            latitude,
+            day_of_year,
+            time,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn skipping_to_dawn_announces_every_phase_crossed() {
+        let mut app = App::new();
+        app.insert_resource(at(47.0, 110, 15.0))
+            .add_event::<SunriseStarted>()
+            .add_event::<DayStarted>()
+            .add_event::<SunsetStarted>()
+            .add_event::<NightStarted>()
+            .add_systems(Update, send_day_phase_events);
+        app.update();
+        assert!(app.world_mut().resource_mut::<TimeOfDay>().skip_to_dawn());
+        app.update();
+
+        let world = app.world();
+        assert_eq!(world.resource::<Events<SunsetStarted>>().len(), 1);
+        assert_eq!(world.resource::<Events<NightStarted>>().len(), 1);
+        assert_eq!(world.resource::<Events<SunriseStarted>>().len(), 1);
+        assert_eq!(world.resource::<Events<DayStarted>>().len(), 0);
+    }
+
+    #[test]
+    fn day_length_through_the_year() {
+        const MARCH_EQUINOX: u32 = 80;
+        const JUNE_SOLSTICE: u32 = 172;
+        const DECEMBER_SOLSTICE: u32 = 355;
+        let length = |latitude: f32, day_of_year: u32| at(latitude, day_of_year, 12.0).day_length();
+
+        // Twelve hours everywhere at the equinox, and all year on the equator
+        for latitude in [-47.0, 0.0, 47.0, 60.0] {
+            assert!((length(latitude, MARCH_EQUINOX) - 12.0).abs() < 0.1, "latitude {latitude}");
+        }
+        for day_of_year in [JUNE_SOLSTICE, DECEMBER_SOLSTICE] {
+            assert!((length(0.0, day_of_year) - 12.0).abs() < 0.01);
+        }
+
+        // About 15h 40m and 8h 20m at 47 degrees north, the other way round in the south
+        assert!((length(47.0, JUNE_SOLSTICE) - 15.7).abs() < 0.1);
+        assert!((length(47.0, DECEMBER_SOLSTICE) - 8.3).abs() < 0.1);
+        assert!((length(-47.0, JUNE_SOLSTICE) - length(47.0, DECEMBER_SOLSTICE)).abs() < 0.05);
+
+        // Midnight sun and polar night
+        assert_eq!(length(80.0, JUNE_SOLSTICE), 24.0);
+        assert_eq!(length(80.0, DECEMBER_SOLSTICE), 0.0);
+    }
+
+    #[test]
+    fn profile_hours_follow_sunrise_and_sunset() {
+        let midsummer = at(47.0, 172, 12.0);
+        let half_day = midsummer.day_length() * 0.5;
+        let hour_at = |time: f32| at(47.0, 172, time).profile_hour();
+        assert!((hour_at(12.0 - half_day) - PROFILE_SUNRISE).abs() < 0.05);
+        assert!((hour_at(12.0 + half_day) - PROFILE_SUNSET).abs() < 0.05);
+        assert!((hour_at(12.0) - 12.0).abs() < 0.05);
+        assert!(hour_at(0.0) < 0.05 || hour_at(0.0) > 23.95);
+
+        // No sunrise or sunset at all near the poles
+        for day_of_year in [0, 172] {
+            for time in [0.0, 6.0, 12.0, 18.0] {
+                assert!(at(80.0, day_of_year, time).profile_hour().is_finite());
+            }
+        }
+    }
+}
@@ -1,11 +1,15 @@
 use bevy::prelude::*;
 
+use crate::lighting::LightingProfile;
+use crate::sky::{self, SkyColor};
+use crate::weather::Weather;
+
 pub struct FogPlugin;
 
 impl Plugin for FogPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup_fog)
-            .add_systems(Update, update_fog);
+            .add_systems(Update, update_fog.after(sky::update_sky));
     }
 }
 
@@ -25,36 +29,38 @@ fn setup_fog(mut camera_query: Query<&mut FogSettings, Added<Camera3d>>) {
 fn update_fog(
     mut camera_query: Query<&mut FogSettings, With<Camera3d>>,
     time_of_day: Option<Res<super::day_night::TimeOfDay>>,
+    water_view: Res<super::water::WaterView>,
+    sky_color: Res<SkyColor>,
+    profile: Res<LightingProfile>,
+    weather: Res<Weather>,
 ) {
+    // Murky green-blue fog close around the camera while under water
+    if water_view.underwater {
+        for mut fog in camera_query.iter_mut() {
+            fog.color = Color::srgb(0.06, 0.22, 0.28);
+            fog.falloff = FogFalloff::Linear {
+                start: 0.0,
+                end: 14.0,
+            };
+        }
+        return;
+    }
+
     if let Some(ref time) = time_of_day {
+        // Fog takes the colour of the sky at the horizon; its tint and distance come from
+        // the lighting profile, and bad weather draws it in
+        let hour = time.profile_hour();
+        let tint = profile.fog_tint.sample(hour);
+        let distance = weather.effects().fog_distance;
+        let start = profile.fog_start.sample(hour).max(0.0) * distance;
+        let end = (profile.fog_end.sample(hour) * distance).max(start + 1.0);
         for mut fog in camera_query.iter_mut() {
-            // Adjust fog based on time of day
-            let t = time.time;
-            
-            if t >= 20.0 || t < 6.0 {
-                // Night - more fog
-                fog.color = Color::srgb(0.05, 0.05, 0.15);
-                fog.falloff = FogFalloff::Linear {
-                    start: 10.0,
-                    end: 50.0,
-                };
-            } else if t >= 6.0 && t < 8.0 {
-                // Dawn - moderate fog
-                let factor = (t - 6.0) / 2.0;
-                fog.color = Color::srgb(0.3 + factor * 0.2, 0.4 + factor * 0.3, 0.5 + factor * 0.2);
-                fog.falloff = FogFalloff::Linear {
-                    start: 15.0,
-                    end: 60.0,
-                };
-            } else {
-                // Day - light fog
-                fog.color = Color::srgb(0.7, 0.8, 0.9);
-                fog.falloff = FogFalloff::Linear {
-                    start: 30.0,
-                    end: 100.0,
-                };
-            }
+            fog.color = Color::linear_rgb(
+                sky_color.horizon.x * tint.x,
+                sky_color.horizon.y * tint.y,
+                sky_color.horizon.z * tint.z,
+            );
+            fog.falloff = FogFalloff::Linear { start, end };
         }
     }
 }
-
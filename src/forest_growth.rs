@@ -0,0 +1,402 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::day_night::TimeOfDay;
+use crate::placement;
+use crate::terrain::TerrainQuery;
+use crate::trees::{ForestState, TreeConfig, TreeRecord, TreeSpecies, TREE_LAYER};
+
+pub struct ForestGrowthPlugin;
+
+impl Plugin for ForestGrowthPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GrowthConfig>()
+            .init_resource::<GrowthClock>()
+            .add_systems(Update, run_growth_tick);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GrowthStage {
+    Sapling,
+    Young,
+    Mature,
+    Old,
+    Snag, // Dead but still standing
+}
+
+impl GrowthStage {
+    // Mesh scale relative to the full-grown tree
+    pub fn scale(self) -> f32 {
+        match self {
+            GrowthStage::Sapling => 0.25,
+            GrowthStage::Young => 0.6,
+            GrowthStage::Mature => 1.0,
+            GrowthStage::Old => 1.1,
+            GrowthStage::Snag => 0.9,
+        }
+    }
+
+    pub fn is_alive(self) -> bool {
+        self != GrowthStage::Snag
+    }
+
+    pub fn can_seed(self) -> bool {
+        matches!(self, GrowthStage::Mature | GrowthStage::Old)
+    }
+}
+
+// All ages and rates are in in-game days
+#[derive(Resource)]
+pub struct GrowthConfig {
+    pub young_age: f32,
+    pub mature_age: f32,
+    pub old_age: f32,
+    pub lifespan: f32,           // Average age at death, varied per tree
+    pub snag_decay_days: f32,    // How long a snag stands before it falls and leaves a clearing
+    pub seed_chance: f32,        // Daily chance that a mature tree drops a sapling
+    pub seed_radius: f32,        // Max distance from the parent for a new sapling
+    pub germination_chance: f32, // Daily chance per chunk of a sapling appearing in open ground
+    pub sapling_mortality: f32,  // Daily chance that a crowded sapling dies
+    pub min_spacing: f32,        // Saplings never sprout closer than this to another tree
+    pub crowding_radius: f32,    // Saplings within this distance of a larger tree count as crowded
+    pub max_trees_per_chunk: usize,
+}
+
+impl Default for GrowthConfig {
+    fn default() -> Self {
+        Self {
+            young_age: 20.0,
+            mature_age: 60.0,
+            old_age: 250.0,
+            lifespan: 320.0,
+            snag_decay_days: 40.0,
+            seed_chance: 0.01,
+            seed_radius: 6.0,
+            germination_chance: 0.05,
+            sapling_mortality: 0.02,
+            min_spacing: 1.5,
+            crowding_radius: 2.5,
+            max_trees_per_chunk: 80,
+        }
+    }
+}
+
+impl GrowthConfig {
+    pub fn stage_for_age(&self, age: f32) -> GrowthStage {
+        if age < self.young_age {
+            GrowthStage::Sapling
+        } else if age < self.mature_age {
+            GrowthStage::Young
+        } else if age < self.old_age {
+            GrowthStage::Mature
+        } else {
+            GrowthStage::Old
+        }
+    }
+}
+
+// Last in-game day the forest was simulated up to
+#[derive(Resource, Default)]
+pub struct GrowthClock {
+    pub last_day: Option<u32>,
+}
+
+// Tree counts across every known chunk
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ForestStats {
+    pub saplings: usize,
+    pub young: usize,
+    pub mature: usize,
+    pub old: usize,
+    pub snags: usize,
+    pub area: f32, // Square metres covered by the counted chunks
+}
+
+impl ForestStats {
+    pub fn living(&self) -> usize {
+        self.saplings + self.young + self.mature + self.old
+    }
+
+    pub fn living_per_hectare(&self) -> f32 {
+        if self.area <= 0.0 {
+            return 0.0;
+        }
+        self.living() as f32 / (self.area / 10_000.0)
+    }
+}
+
+impl ForestState {
+    // Advance every known chunk by a number of days, starting with `first_day`. Used by
+    // the daily tick and to skip years ahead at once. `ground` gives the terrain height for
+    // a new sapling of a species, or None where that species cannot grow.
+    //
+    // Each chunk draws from its own rng for each day, seeded by the world, the chunk and
+    // the day, so a chunk grows the same way whichever other chunks are loaded.
+    pub fn simulate_days(
+        &mut self,
+        config: &GrowthConfig,
+        chunk_size: f32,
+        world_seed: u32,
+        first_day: u32,
+        days: u32,
+        ground: impl Fn(TreeSpecies, f32, f32) -> Option<f32>,
+    ) {
+        for day in first_day..first_day + days {
+            for (&key, records) in self.chunks.iter_mut() {
+                let day_seed = (day as u64).wrapping_mul(0x2545_F491_4F6C_DD1D);
+                let mut rng = StdRng::seed_from_u64(placement::chunk_seed(world_seed, key, TREE_LAYER) ^ day_seed);
+                if step_chunk(records, key, config, chunk_size, &mut rng, &ground) {
+                    self.dirty_chunks.insert(key);
+                }
+            }
+        }
+    }
+
+    pub fn stats(&self, chunk_size: f32) -> ForestStats {
+        let mut stats = ForestStats {
+            area: self.chunks.len() as f32 * chunk_size * chunk_size,
+            ..default()
+        };
+        for record in self.chunks.values().flatten() {
+            match record.stage {
+                GrowthStage::Sapling => stats.saplings += 1,
+                GrowthStage::Young => stats.young += 1,
+                GrowthStage::Mature => stats.mature += 1,
+                GrowthStage::Old => stats.old += 1,
+                GrowthStage::Snag => stats.snags += 1,
+            }
+        }
+        stats
+    }
+}
+
+// Simulate one day for a chunk. Returns true if anything visible changed.
+fn step_chunk(
+    records: &mut Vec<TreeRecord>,
+    chunk: (i32, i32),
+    config: &GrowthConfig,
+    chunk_size: f32,
+    rng: &mut impl Rng,
+    ground: &impl Fn(TreeSpecies, f32, f32) -> Option<f32>,
+) -> bool {
+    let mut changed = false;
+
+    // Ageing, death and decay
+    for record in records.iter_mut() {
+        if record.stage.is_alive() {
+            record.age += 1.0;
+            let stage = if record.age >= record.lifespan {
+                GrowthStage::Snag
+            } else {
+                config.stage_for_age(record.age)
+            };
+            if stage != record.stage {
+                record.stage = stage;
+                changed = true;
+            }
+        } else {
+            record.days_dead += 1.0;
+        }
+    }
+
+    // Fallen snags and crowded saplings leave gaps
+    let before = records.len();
+    let snapshot: Vec<(Vec3, GrowthStage)> = records.iter().map(|r| (r.position, r.stage)).collect();
+    records.retain(|record| {
+        if record.stage == GrowthStage::Snag {
+            return record.days_dead < config.snag_decay_days;
+        }
+        if record.stage == GrowthStage::Sapling {
+            let crowded = snapshot.iter().any(|(position, stage)| {
+                *stage != GrowthStage::Sapling
+                    && stage.is_alive()
+                    && position.xz().distance(record.position.xz()) < config.crowding_radius
+            });
+            if crowded && rng.gen_range(0.0..1.0) < config.sapling_mortality {
+                return false;
+            }
+        }
+        true
+    });
+    changed |= records.len() != before;
+
+    // Seed dispersal from mature trees
+    let min_x = chunk.0 as f32 * chunk_size;
+    let min_z = chunk.1 as f32 * chunk_size;
+    let mut candidates = Vec::new();
+    // Seeds would all land too close to their parent to sprout
+    let can_disperse = config.seed_radius > config.min_spacing;
+    for record in records.iter() {
+        if can_disperse && record.stage.can_seed() && rng.gen_range(0.0..1.0) < config.seed_chance {
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            let distance = rng.gen_range(config.min_spacing..config.seed_radius);
+            candidates.push((
+                record.species,
+                record.position.x + angle.cos() * distance,
+                record.position.z + angle.sin() * distance,
+                record.height,
+            ));
+        }
+    }
+
+    // Germination in open ground slowly refills clearings
+    if rng.gen_range(0.0..1.0) < config.germination_chance {
+        let height = records.iter().map(|r| r.height).sum::<f32>() / records.len().max(1) as f32;
+        let species = if records.is_empty() {
+            TreeSpecies::Broadleaf
+        } else {
+            records[rng.gen_range(0..records.len())].species
+        };
+        candidates.push((
+            species,
+            min_x + rng.gen_range(0.0..chunk_size),
+            min_z + rng.gen_range(0.0..chunk_size),
+            if height > 0.0 { height } else { 3.0 },
+        ));
+    }
+
+    for (species, x, z, parent_height) in candidates {
+        if records.len() >= config.max_trees_per_chunk {
+            break;
+        }
+        let inside = x >= min_x && x < min_x + chunk_size && z >= min_z && z < min_z + chunk_size;
+        if !inside {
+            continue;
+        }
+        let blocked = records
+            .iter()
+            .any(|r| r.position.xz().distance(Vec2::new(x, z)) < config.min_spacing);
+        if blocked {
+            continue;
+        }
+        let Some(ground_height) = ground(species, x, z) else {
+            continue;
+        };
+
+        records.push(TreeRecord {
+            species,
+            position: Vec3::new(x, ground_height, z),
+            height: parent_height * rng.gen_range(0.85..1.15),
+            age: 0.0,
+            lifespan: config.lifespan * rng.gen_range(0.8..1.2),
+            stage: GrowthStage::Sapling,
+            days_dead: 0.0,
+            seed: rng.r#gen(),
+        });
+        changed = true;
+    }
+
+    changed
+}
+
+// Low-frequency tick: runs the simulation once for every in-game day that passed
+fn run_growth_tick(
+    time_of_day: Res<TimeOfDay>,
+    config: Res<GrowthConfig>,
+    tree_config: Res<TreeConfig>,
+    terrain: TerrainQuery,
+    mut clock: ResMut<GrowthClock>,
+    mut forest: ResMut<ForestState>,
+) {
+    let last_day = *clock.last_day.get_or_insert(time_of_day.day);
+    if time_of_day.day <= last_day {
+        return;
+    }
+
+    let days = time_of_day.day - last_day;
+    clock.last_day = Some(time_of_day.day);
+
+    forest.simulate_days(&config, terrain.config.chunk_size, terrain.config.seed, last_day + 1, days, |species, x, z| {
+        tree_config
+            .rules_for(species)
+            .and_then(|rules| rules.ground_height(&terrain, x, z))
+    });
+
+    let stats = forest.stats(terrain.config.chunk_size);
+    info!(
+        "Forest day {}: {} living trees ({:.0}/ha), {} saplings, {} snags",
+        time_of_day.day,
+        stats.living(),
+        stats.living_per_hectare(),
+        stats.saplings,
+        stats.snags,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHUNK_SIZE: f32 = 32.0;
+
+    // A few chunks of young forest, laid out the same way for a given seed
+    fn young_forest(seed: u64) -> ForestState {
+        let config = GrowthConfig::default();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut forest = ForestState::default();
+        for key in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            let min = Vec2::new(key.0 as f32, key.1 as f32) * CHUNK_SIZE;
+            let records = (0..20)
+                .map(|_| {
+                    let age = rng.gen_range(0.0..config.old_age);
+                    TreeRecord {
+                        species: if rng.gen_bool(0.5) { TreeSpecies::Broadleaf } else { TreeSpecies::Pine },
+                        position: Vec3::new(
+                            min.x + rng.gen_range(0.0..CHUNK_SIZE),
+                            0.0,
+                            min.y + rng.gen_range(0.0..CHUNK_SIZE),
+                        ),
+                        height: rng.gen_range(4.0..8.0),
+                        age,
+                        lifespan: config.lifespan * rng.gen_range(0.8..1.2),
+                        stage: config.stage_for_age(age),
+                        days_dead: 0.0,
+                        seed: rng.r#gen(),
+                    }
+                })
+                .collect();
+            forest.chunks.insert(key, records);
+        }
+        forest
+    }
+
+    fn simulate_ten_years(seed: u64) -> ForestStats {
+        let mut forest = young_forest(seed);
+        forest.simulate_days(&GrowthConfig::default(), CHUNK_SIZE, seed as u32, 1, 3650, |_, _, _| Some(0.0));
+        forest.stats(CHUNK_SIZE)
+    }
+
+    // Starting from about 200 trees per hectare, the forest fills in but thins itself
+    // out well short of the cap of 80 trees a chunk (about 780 per hectare)
+    #[test]
+    fn density_stays_in_band_over_ten_years() {
+        let density = simulate_ten_years(7).living_per_hectare();
+        assert!((450.0..=750.0).contains(&density), "{density} living trees per hectare");
+    }
+
+    #[test]
+    fn snags_and_saplings_appear() {
+        let stats = simulate_ten_years(7);
+        assert!(stats.snags > 0, "no snags in {stats:?}");
+        assert!(stats.saplings > 0, "no saplings in {stats:?}");
+    }
+
+    #[test]
+    fn spacing_wider_than_seed_radius_only_germinates() {
+        let config = GrowthConfig {
+            seed_radius: 2.0,
+            min_spacing: 3.0,
+            ..default()
+        };
+        let mut forest = young_forest(3);
+        forest.simulate_days(&config, CHUNK_SIZE, 3, 1, 365, |_, _, _| Some(0.0));
+        assert!(forest.stats(CHUNK_SIZE).living_per_hectare() > 0.0);
+    }
+
+    #[test]
+    fn same_seed_grows_the_same_forest() {
+        assert_eq!(simulate_ten_years(11), simulate_ten_years(11));
+    }
+}
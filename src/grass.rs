@@ -0,0 +1,651 @@
+use bevy::core_pipeline::core_3d::{Opaque3d, Opaque3dBinKey};
+use bevy::ecs::system::lifetimeless::SRes;
+use bevy::ecs::system::SystemParamItem;
+use bevy::pbr::{
+    MeshPipeline, MeshPipelineKey, RenderMeshInstances, SetMeshBindGroup, SetMeshViewBindGroup,
+};
+use bevy::prelude::*;
+use bevy::render::mesh::{GpuBufferInfo, GpuMesh, Indices, MeshVertexBufferLayoutRef, PrimitiveTopology};
+use bevy::render::primitives::Aabb;
+use bevy::render::render_asset::{RenderAssetUsages, RenderAssets};
+use bevy::render::render_phase::{
+    AddRenderCommand, BinnedRenderPhaseType, DrawFunctions, PhaseItem, RenderCommand,
+    RenderCommandResult, SetItemPipeline, TrackedRenderPass, ViewBinnedRenderPhases,
+};
+use bevy::render::render_resource::{
+    AsBindGroup, BindGroup, BindGroupLayout, Buffer, BufferInitDescriptor, BufferUsages,
+    PipelineCache, RenderPipelineDescriptor, SpecializedMeshPipeline, SpecializedMeshPipelineError,
+    SpecializedMeshPipelines, VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode,
+};
+use bevy::render::renderer::RenderDevice;
+use bevy::render::texture::{FallbackImage, GpuImage};
+use bevy::render::view::{ExtractedView, VisibleEntities, WithMesh};
+use bevy::render::{Extract, ExtractSchedule, Render, RenderApp, RenderSet};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::clouds::{CloudLayer, CloudSettings};
+use crate::day_night::SunLight;
+use crate::input::{Action, ActionState};
+use crate::placement;
+use crate::player::Player;
+use crate::settings::GameSettings;
+use crate::terrain::{Biome, TerrainChunk, TerrainQuery};
+use crate::wind::Wind;
+
+pub struct GrassPlugin;
+
+impl Plugin for GrassPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GrassConfig>()
+            .add_systems(Startup, setup_grass)
+            .add_systems(Update, (cycle_grass_quality, update_grass_patches, update_grass_material).chain());
+
+        // Headless runs have no renderer; the patches are still kept up to date
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<GrassInstanceBuffers>()
+            .init_resource::<SpecializedMeshPipelines<GrassPipeline>>()
+            .add_render_command::<Opaque3d, DrawGrass>()
+            .add_systems(ExtractSchedule, extract_grass)
+            .add_systems(
+                Render,
+                (
+                    queue_grass.in_set(RenderSet::QueueMeshes),
+                    prepare_grass_buffers.in_set(RenderSet::PrepareResources),
+                    prepare_grass_bind_group.in_set(RenderSet::PrepareBindGroups),
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.init_resource::<GrassPipeline>();
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct GrassConfig {
+    pub blades_per_square_metre: f32, // At full density on a meadow with High quality
+    pub min_blade_height: f32,
+    pub max_blade_height: f32,
+    pub blade_width: f32,
+    pub fade_start: f32, // Blades shrink away between these camera distances
+    pub fade_end: f32,
+    pub flatten_radius: f32, // Grass bends away from the player inside this radius
+}
+
+impl Default for GrassConfig {
+    fn default() -> Self {
+        Self {
+            blades_per_square_metre: 6.0,
+            min_blade_height: 0.25,
+            max_blade_height: 0.6,
+            blade_width: 0.06,
+            fade_start: 45.0,
+            fade_end: 70.0,
+            flatten_radius: 1.2,
+        }
+    }
+}
+
+// Uniforms shared by every patch, written once a frame; wind sway, distance fade and
+// flattening happen in the vertex shader
+#[derive(Resource, AsBindGroup, Clone)]
+pub struct GrassMaterial {
+    #[uniform(0)]
+    pub wind: Vec4, // xy direction, z strength, w wind clock
+    #[uniform(0)]
+    pub player: Vec4, // xyz player position, w flatten radius
+    #[uniform(0)]
+    pub fade: Vec4, // x fade start, y fade end
+    #[uniform(0)]
+    pub sun: Vec4, // xyz direction towards the sun, w light strength
+    #[uniform(0)]
+    pub sun_color: Vec4,
+    #[uniform(0)]
+    pub clouds: Vec4, // CloudLayer::params, for cloud shadows
+    #[uniform(0)]
+    pub cloud_shape: Vec4,
+    #[texture(1)]
+    #[sampler(2)]
+    pub cloud_map: Handle<Image>,
+}
+
+// The one blade every patch draws instances of
+#[derive(Resource)]
+pub struct GrassBladeMesh(pub Handle<Mesh>);
+
+// Grass for one terrain chunk, parented to the chunk entity
+#[derive(Component)]
+pub struct GrassPatch {
+    pub chunk: (i32, i32),
+    pub density: f32, // Fraction of the full blade count this patch was built with
+}
+
+// One blade as the vertex shader reads it from the instance buffer
+#[derive(Clone, Copy)]
+pub struct GrassBlade {
+    pub root: Vec3,
+    pub height: f32,
+    pub facing: Vec2, // Cosine and sine of the way the blade's width runs
+    pub width: f32,
+    pub lean: f32, // How far the tip hangs over, as a share of the height
+    pub color: Vec4,
+}
+
+impl GrassBlade {
+    fn to_array(self) -> [f32; 12] {
+        [
+            self.root.x,
+            self.root.y,
+            self.root.z,
+            self.height,
+            self.facing.x,
+            self.facing.y,
+            self.width,
+            self.lean,
+            self.color.x,
+            self.color.y,
+            self.color.z,
+            self.color.w,
+        ]
+    }
+}
+
+// The blades of a patch, shared with the render world without copying
+#[derive(Component, Clone)]
+pub struct GrassBlades(pub Arc<Vec<GrassBlade>>);
+
+const GRASS_LAYER: u64 = 2;
+
+fn setup_grass(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    config: Res<GrassConfig>,
+    clouds: Res<CloudLayer>,
+) {
+    commands.insert_resource(GrassMaterial {
+        wind: Vec4::ZERO,
+        player: Vec4::new(0.0, 0.0, 0.0, config.flatten_radius),
+        fade: Vec4::new(config.fade_start, config.fade_end, 0.0, 0.0),
+        sun: Vec4::new(0.0, 1.0, 0.0, 1.0),
+        sun_color: Vec4::ONE,
+        clouds: Vec4::ZERO,
+        cloud_shape: Vec4::ZERO,
+        cloud_map: clouds.map.clone(),
+    });
+    commands.insert_resource(GrassBladeMesh(meshes.add(build_blade_mesh())));
+}
+
+fn cycle_grass_quality(actions: Res<ActionState>, mut settings: ResMut<GameSettings>) {
+    if actions.just_pressed(Action::GrassQuality) {
+        settings.grass_quality = settings.grass_quality.next();
+        info!("Grass quality: {:?}", settings.grass_quality);
+    }
+}
+
+// Density per chunk falls off with distance from the camera and follows the quality setting
+fn patch_density(quality_scale: f32, radius: i32, ring: i32) -> f32 {
+    if ring > radius {
+        0.0
+    } else if ring <= 1 {
+        quality_scale
+    } else {
+        quality_scale * 0.5
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_grass_patches(
+    mut commands: Commands,
+    config: Res<GrassConfig>,
+    settings: Res<GameSettings>,
+    blade_mesh: Res<GrassBladeMesh>,
+    terrain: TerrainQuery,
+    camera_query: Query<&GlobalTransform, With<Camera3d>>,
+    chunk_query: Query<(Entity, &TerrainChunk)>,
+    patch_query: Query<(Entity, &GrassPatch)>,
+) {
+    let Ok(camera_transform) = camera_query.get_single() else {
+        return;
+    };
+    let camera_position = camera_transform.translation();
+    let camera_chunk_x = (camera_position.x / terrain.config.chunk_size).floor() as i32;
+    let camera_chunk_z = (camera_position.z / terrain.config.chunk_size).floor() as i32;
+
+    let quality = settings.grass_quality;
+    let existing: HashMap<(i32, i32), (Entity, f32)> = patch_query
+        .iter()
+        .map(|(entity, patch)| (patch.chunk, (entity, patch.density)))
+        .collect();
+
+    for (chunk_entity, chunk) in chunk_query.iter() {
+        let key = (chunk.chunk_x, chunk.chunk_z);
+        let ring = (key.0 - camera_chunk_x).abs().max((key.1 - camera_chunk_z).abs());
+        let density = patch_density(quality.density_scale(), quality.chunk_radius(), ring);
+
+        let current = existing.get(&key);
+        if current.map(|(_, d)| *d) == Some(density) || (current.is_none() && density == 0.0) {
+            continue;
+        }
+
+        if let Some((entity, _)) = current {
+            commands.entity(*entity).despawn_recursive();
+        }
+        if density > 0.0 {
+            let (blades, bounds) = scatter_blades(&config, &terrain, key, density);
+            // The blade mesh sits at the origin, so the patch gets the chunk's bounds for
+            // frustum culling instead of the mesh's
+            let patch = commands
+                .spawn((
+                    blade_mesh.0.clone(),
+                    SpatialBundle::INHERITED_IDENTITY,
+                    bounds,
+                    GrassPatch { chunk: key, density },
+                    GrassBlades(Arc::new(blades)),
+                ))
+                .id();
+            commands.entity(chunk_entity).add_child(patch);
+        }
+    }
+}
+
+// A unit blade, a tapered strip of three triangles: x runs across the blade and y from the
+// root to the tip. The vertex shader places, turns and sizes it from the blade's instance.
+fn build_blade_mesh() -> Mesh {
+    let positions = vec![
+        [-0.5, 0.0, 0.0],
+        [0.5, 0.0, 0.0],
+        [-0.3, 0.5, 0.0],
+        [0.3, 0.5, 0.0],
+        [0.0, 1.0, 0.0],
+    ];
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_indices(Indices::U16(vec![0, 1, 2, 1, 3, 2, 2, 3, 4]));
+    mesh
+}
+
+// Scatter blades over a chunk, returning them with the bounds they can sway and bend within
+fn scatter_blades(
+    config: &GrassConfig,
+    terrain: &TerrainQuery,
+    chunk: (i32, i32),
+    density: f32,
+) -> (Vec<GrassBlade>, Aabb) {
+    let mut rng = StdRng::seed_from_u64(placement::chunk_seed(terrain.config.seed, chunk, GRASS_LAYER));
+    let chunk_size = terrain.config.chunk_size;
+    let origin = Vec2::new(chunk.0 as f32, chunk.1 as f32) * chunk_size;
+    let candidates = (chunk_size * chunk_size * config.blades_per_square_metre * density) as usize;
+
+    let mut blades = Vec::new();
+    let (mut low, mut high) = (f32::MAX, f32::MIN);
+
+    for _ in 0..candidates {
+        let x = origin.x + rng.gen_range(0.0..chunk_size);
+        let z = origin.y + rng.gen_range(0.0..chunk_size);
+
+        // Blade density and tint come from the biome underneath
+        let (biome_density, tint) = match terrain.biome(x, z) {
+            Biome::Meadow => (1.0, Vec3::new(0.35, 0.7, 0.25)),
+            Biome::Forest => (0.6, Vec3::new(0.2, 0.5, 0.18)),
+            Biome::Rock => (0.08, Vec3::new(0.45, 0.5, 0.3)),
+            Biome::Water => (0.0, Vec3::ZERO),
+        };
+        if rng.gen_range(0.0..1.0) >= biome_density {
+            continue;
+        }
+
+        let ground = terrain.height(x, z);
+        let height = rng.gen_range(config.min_blade_height..config.max_blade_height);
+        let width = config.blade_width * rng.gen_range(0.7..1.3);
+        let facing = rng.gen_range(0.0..std::f32::consts::TAU);
+        let lean = rng.gen_range(0.0..0.3);
+        let shade = rng.gen_range(0.85..1.15);
+
+        low = low.min(ground);
+        high = high.max(ground + height);
+        blades.push(GrassBlade {
+            root: Vec3::new(x, ground, z),
+            height,
+            facing: Vec2::new(facing.cos(), facing.sin()),
+            width,
+            lean,
+            color: (tint * shade).extend(1.0),
+        });
+    }
+
+    // Tips can sway and be pushed aside by up to about a blade's height
+    let margin = config.max_blade_height;
+    let bounds = if blades.is_empty() {
+        Aabb::default()
+    } else {
+        Aabb::from_min_max(
+            Vec3::new(origin.x - margin, low, origin.y - margin),
+            Vec3::new(origin.x + chunk_size + margin, high, origin.y + chunk_size + margin),
+        )
+    };
+    (blades, bounds)
+}
+
+// Feed the wind field, player position, sunlight and clouds into the shared uniforms
+#[allow(clippy::too_many_arguments)]
+fn update_grass_material(
+    wind: Res<Wind>,
+    config: Res<GrassConfig>,
+    clouds: Res<CloudLayer>,
+    cloud_settings: Res<CloudSettings>,
+    mut material: ResMut<GrassMaterial>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    sun_query: Query<(&GlobalTransform, &DirectionalLight), With<SunLight>>,
+) {
+    material.wind = Vec4::new(wind.direction.x, wind.direction.y, wind.strength, wind.time);
+    material.fade = Vec4::new(config.fade_start, config.fade_end, 0.0, 0.0);
+    material.clouds = clouds.params();
+    material.cloud_shape = clouds.shape(&cloud_settings);
+
+    if let Ok(player_transform) = player_query.get_single() {
+        material.player = player_transform.translation().extend(config.flatten_radius);
+    }
+
+    if let Ok((sun_transform, light)) = sun_query.get_single() {
+        let to_sun = -Vec3::from(sun_transform.forward());
+        let strength = (light.illuminance / 10000.0).clamp(0.0, 1.5);
+        material.sun = to_sun.extend(strength);
+        material.sun_color = light.color.to_linear().to_vec4();
+    }
+}
+
+// Render world: each patch is drawn as a single instanced draw of the blade mesh, one
+// instance per blade, with the blades in a vertex buffer stepped per instance
+
+fn extract_grass(
+    mut commands: Commands,
+    material: Extract<Option<Res<GrassMaterial>>>,
+    patch_query: Extract<Query<(Entity, &GrassBlades)>>,
+) {
+    if let Some(material) = material.as_ref() {
+        commands.insert_resource(GrassMaterial::clone(material));
+    }
+    // Every patch comes across, seen or not, so its instance buffer survives looking away
+    let patches: Vec<_> = patch_query
+        .iter()
+        .map(|(entity, blades)| (entity, blades.clone()))
+        .collect();
+    commands.insert_or_spawn_batch(patches);
+}
+
+#[derive(Resource)]
+struct GrassPipeline {
+    shader: Handle<Shader>,
+    mesh_pipeline: MeshPipeline,
+    material_layout: BindGroupLayout,
+}
+
+impl FromWorld for GrassPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let material_layout = GrassMaterial::bind_group_layout(world.resource::<RenderDevice>());
+        Self {
+            shader: world.load_asset("shaders/grass.wgsl"),
+            mesh_pipeline: world.resource::<MeshPipeline>().clone(),
+            material_layout,
+        }
+    }
+}
+
+impl SpecializedMeshPipeline for GrassPipeline {
+    type Key = MeshPipelineKey;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayoutRef,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let mut descriptor = self.mesh_pipeline.specialize(key, layout)?;
+        descriptor.label = Some("grass_pipeline".into());
+        descriptor.vertex.shader = self.shader.clone();
+        descriptor.vertex.buffers.push(VertexBufferLayout {
+            array_stride: VertexFormat::Float32x4.size() * 3,
+            step_mode: VertexStepMode::Instance,
+            attributes: vec![
+                // Root and height, facing, width and lean, colour; the mesh has position only
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 0,
+                    shader_location: 3,
+                },
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: VertexFormat::Float32x4.size(),
+                    shader_location: 4,
+                },
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: VertexFormat::Float32x4.size() * 2,
+                    shader_location: 5,
+                },
+            ],
+        });
+        descriptor.layout.push(self.material_layout.clone());
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            fragment.shader = self.shader.clone();
+        }
+        // Blades are single-sided strips seen from both sides
+        descriptor.primitive.cull_mode = None;
+        Ok(descriptor)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn queue_grass(
+    draw_functions: Res<DrawFunctions<Opaque3d>>,
+    grass_pipeline: Res<GrassPipeline>,
+    msaa: Res<Msaa>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<GrassPipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    meshes: Res<RenderAssets<GpuMesh>>,
+    mesh_instances: Res<RenderMeshInstances>,
+    bind_group: Option<Res<GrassBindGroup>>,
+    patch_query: Query<(), With<GrassBlades>>,
+    mut opaque_phases: ResMut<ViewBinnedRenderPhases<Opaque3d>>,
+    views: Query<(Entity, &ExtractedView, &VisibleEntities)>,
+) {
+    // Nothing to draw with until the uniforms have made it to the GPU
+    if bind_group.is_none() {
+        return;
+    }
+    let draw_grass = draw_functions.read().id::<DrawGrass>();
+    let msaa_key = MeshPipelineKey::from_msaa_samples(msaa.samples());
+
+    for (view_entity, view, visible_entities) in &views {
+        let Some(opaque_phase) = opaque_phases.get_mut(&view_entity) else {
+            continue;
+        };
+        let view_key = msaa_key | MeshPipelineKey::from_hdr(view.hdr);
+
+        for &entity in visible_entities.iter::<WithMesh>() {
+            if !patch_query.contains(entity) {
+                continue;
+            }
+            let Some(mesh_instance) = mesh_instances.render_mesh_queue_data(entity) else {
+                continue;
+            };
+            let Some(mesh) = meshes.get(mesh_instance.mesh_asset_id) else {
+                continue;
+            };
+            let key = view_key | MeshPipelineKey::from_primitive_topology(mesh.primitive_topology());
+            let pipeline = match pipelines.specialize(&pipeline_cache, &grass_pipeline, key, &mesh.layout) {
+                Ok(pipeline) => pipeline,
+                Err(err) => {
+                    error!("Grass pipeline: {err}");
+                    continue;
+                }
+            };
+            // Every patch shares the blade mesh, so each one is its own unbatched draw
+            opaque_phase.add(
+                Opaque3dBinKey {
+                    pipeline,
+                    draw_function: draw_grass,
+                    asset_id: mesh_instance.mesh_asset_id.into(),
+                    material_bind_group_id: None,
+                    lightmap_image: None,
+                },
+                entity,
+                BinnedRenderPhaseType::UnbatchableMesh,
+            );
+        }
+    }
+}
+
+struct GrassInstanceBuffer {
+    blades: Arc<Vec<GrassBlade>>, // What the buffer was filled from
+    buffer: Buffer,
+    count: u32,
+}
+
+// Instance buffers by patch, kept across frames so blades only go to the GPU once
+#[derive(Resource, Default)]
+struct GrassInstanceBuffers(HashMap<Entity, GrassInstanceBuffer>);
+
+fn prepare_grass_buffers(
+    mut buffers: ResMut<GrassInstanceBuffers>,
+    render_device: Res<RenderDevice>,
+    patch_query: Query<(Entity, &GrassBlades)>,
+) {
+    // Despawned patches give their buffers back
+    buffers.0.retain(|entity, _| patch_query.contains(*entity));
+
+    for (entity, blades) in &patch_query {
+        let current = buffers.0.get(&entity);
+        if current.is_some_and(|existing| Arc::ptr_eq(&existing.blades, &blades.0)) {
+            continue;
+        }
+        let contents: Vec<u8> = blades
+            .0
+            .iter()
+            .flat_map(|blade| blade.to_array())
+            .flat_map(f32::to_le_bytes)
+            .collect();
+        let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("grass_blades"),
+            contents: &contents,
+            usage: BufferUsages::VERTEX,
+        });
+        buffers.0.insert(
+            entity,
+            GrassInstanceBuffer {
+                blades: blades.0.clone(),
+                buffer,
+                count: blades.0.len() as u32,
+            },
+        );
+    }
+}
+
+#[derive(Resource)]
+struct GrassBindGroup(BindGroup);
+
+fn prepare_grass_bind_group(
+    mut commands: Commands,
+    grass_pipeline: Res<GrassPipeline>,
+    material: Option<Res<GrassMaterial>>,
+    render_device: Res<RenderDevice>,
+    images: Res<RenderAssets<GpuImage>>,
+    fallback_image: Res<FallbackImage>,
+) {
+    let Some(material) = material else {
+        return;
+    };
+    // Until the cloud map reaches the GPU this fails and the grass waits
+    if let Ok(prepared) = material.as_bind_group(
+        &grass_pipeline.material_layout,
+        &render_device,
+        &images,
+        &fallback_image,
+    ) {
+        commands.insert_resource(GrassBindGroup(prepared.bind_group));
+    }
+}
+
+type DrawGrass = (
+    SetItemPipeline,
+    SetMeshViewBindGroup<0>,
+    SetMeshBindGroup<1>,
+    SetGrassBindGroup<2>,
+    DrawGrassBlades,
+);
+
+struct SetGrassBindGroup<const I: usize>;
+
+impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetGrassBindGroup<I> {
+    type Param = SRes<GrassBindGroup>;
+    type ViewQuery = ();
+    type ItemQuery = ();
+
+    fn render<'w>(
+        _item: &P,
+        _view: (),
+        _entity: Option<()>,
+        bind_group: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        pass.set_bind_group(I, &bind_group.into_inner().0, &[]);
+        RenderCommandResult::Success
+    }
+}
+
+struct DrawGrassBlades;
+
+impl<P: PhaseItem> RenderCommand<P> for DrawGrassBlades {
+    type Param = (
+        SRes<RenderAssets<GpuMesh>>,
+        SRes<RenderMeshInstances>,
+        SRes<GrassInstanceBuffers>,
+    );
+    type ViewQuery = ();
+    type ItemQuery = ();
+
+    fn render<'w>(
+        item: &P,
+        _view: (),
+        _entity: Option<()>,
+        (meshes, mesh_instances, buffers): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(mesh_instance) = mesh_instances.render_mesh_queue_data(item.entity()) else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(gpu_mesh) = meshes.into_inner().get(mesh_instance.mesh_asset_id) else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(blades) = buffers.into_inner().0.get(&item.entity()) else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
+        pass.set_vertex_buffer(1, blades.buffer.slice(..));
+        match &gpu_mesh.buffer_info {
+            GpuBufferInfo::Indexed {
+                buffer,
+                index_format,
+                count,
+            } => {
+                pass.set_index_buffer(buffer.slice(..), 0, *index_format);
+                pass.draw_indexed(0..*count, 0, 0..blades.count);
+            }
+            GpuBufferInfo::NonIndexed => {
+                pass.draw(0..gpu_mesh.vertex_count, 0..blades.count);
+            }
+        }
+        RenderCommandResult::Success
+    }
+}
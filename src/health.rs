@@ -0,0 +1,480 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::controls::CameraShake;
+use crate::noclip::Noclip;
+use crate::placement;
+use crate::player::{self, MovementState, Player, PlayerCamera, Stamina, WaterState};
+use crate::terrain::{TerrainChunk, TerrainQuery};
+
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HealthSettings>()
+            .init_resource::<RespawnPoint>()
+            .add_event::<DamageEvent>()
+            .add_systems(Startup, (setup_campfire_assets, setup_death_screen))
+            .add_systems(
+                Update,
+                (
+                    insert_health,
+                    spawn_campfires,
+                    flicker_campfires,
+                    hazard_damage,
+                    apply_damage.after(player::player_movement).after(hazard_damage),
+                    regenerate_health,
+                    track_safe_position,
+                    light_campfires,
+                    respawn.after(apply_damage),
+                    update_death_screen,
+                ),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct HealthSettings {
+    pub max_health: f32,
+    pub regen_rate: f32,     // Health per second once regeneration starts
+    pub regen_delay: f32,    // Seconds after the last damage before regeneration starts
+    pub safe_delay: f32,     // Seconds without damage before the ground underfoot counts as safe
+    pub respawn_delay: f32,
+    pub campfire_chance: f64, // Chance that a chunk gets a campfire
+    pub campfire_radius: f32, // Walking this close to a campfire makes it the respawn point
+    pub fire_damage: f32,    // Damage per second standing in the flames
+}
+
+impl Default for HealthSettings {
+    fn default() -> Self {
+        Self {
+            max_health: 100.0,
+            regen_rate: 2.0,
+            regen_delay: 6.0,
+            safe_delay: 2.0,
+            respawn_delay: 3.0,
+            campfire_chance: 0.15,
+            campfire_radius: 3.0,
+            fire_damage: 15.0,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+    pub since_damage: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self {
+            current: max,
+            max,
+            since_damage: f32::MAX,
+        }
+    }
+
+    pub fn fraction(&self) -> f32 {
+        self.current / self.max
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+}
+
+// Anything that hurts the player sends one of these; health, death and camera
+// feedback are handled in one place
+#[derive(Event)]
+pub struct DamageEvent {
+    pub amount: f32,
+    pub source: DamageSource,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DamageSource {
+    Fall,
+    Hazard, // A DamageZone such as campfire flames
+}
+
+// Environmental hazard: hurts the player while their feet are inside the sphere
+#[derive(Component)]
+pub struct DamageZone {
+    pub radius: f32,
+    pub damage_per_second: f32,
+}
+
+// Respawn point once lit by walking up to it
+#[derive(Component)]
+pub struct Campfire;
+
+#[derive(Component)]
+struct CampfireLight;
+
+// Where the player comes back after dying: the last lit campfire, otherwise the last
+// place they stood safely
+#[derive(Resource)]
+pub struct RespawnPoint {
+    pub safe_position: Vec3,
+    pub campfire: Option<Vec3>,
+    countdown: f32,
+}
+
+impl Default for RespawnPoint {
+    fn default() -> Self {
+        Self {
+            safe_position: Vec3::new(0.0, 2.5, 0.0),
+            campfire: None,
+            countdown: 0.0,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct CampfireAssets {
+    log_mesh: Handle<Mesh>,
+    log_material: Handle<StandardMaterial>,
+    ember_mesh: Handle<Mesh>,
+    ember_material: Handle<StandardMaterial>,
+}
+
+#[derive(Component)]
+struct DeathScreen;
+
+#[derive(Component)]
+struct DeathCountdownText;
+
+fn insert_health(mut commands: Commands, settings: Res<HealthSettings>, new_players: Query<Entity, Added<Player>>) {
+    for entity in new_players.iter() {
+        commands.entity(entity).insert(Health::new(settings.max_health));
+    }
+}
+
+fn setup_campfire_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(CampfireAssets {
+        log_mesh: meshes.add(Cuboid::new(0.14, 0.14, 0.9)),
+        log_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.28, 0.18, 0.1),
+            perceptual_roughness: 0.9,
+            ..default()
+        }),
+        ember_mesh: meshes.add(Sphere::new(0.22)),
+        ember_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(1.0, 0.45, 0.1),
+            emissive: LinearRgba::rgb(6.0, 2.0, 0.3),
+            ..default()
+        }),
+    });
+}
+
+const CAMPFIRE_LAYER: u64 = 200;
+
+// A few chunks get a campfire on flat, dry ground. The starting chunk always has one
+// so there is somewhere to come back to from the beginning.
+fn spawn_campfires(
+    mut commands: Commands,
+    assets: Option<Res<CampfireAssets>>,
+    settings: Res<HealthSettings>,
+    terrain: TerrainQuery,
+    new_chunks: Query<(Entity, &TerrainChunk), Added<TerrainChunk>>,
+) {
+    let Some(assets) = assets else {
+        return;
+    };
+    let size = terrain.config.chunk_size;
+
+    for (chunk_entity, chunk) in new_chunks.iter() {
+        let key = (chunk.chunk_x, chunk.chunk_z);
+        let mut rng = StdRng::seed_from_u64(placement::chunk_seed(terrain.config.seed, key, CAMPFIRE_LAYER));
+        let origin = Vec2::new(chunk.chunk_x as f32, chunk.chunk_z as f32) * size;
+        let position = if key == (0, 0) {
+            Some(Vec2::new(3.0, 4.0))
+        } else if rng.gen_bool(settings.campfire_chance) {
+            (0..8)
+                .map(|_| origin + Vec2::new(rng.gen_range(0.1..0.9), rng.gen_range(0.1..0.9)) * size)
+                .find(|p| {
+                    terrain.slope_degrees(p.x, p.y) < 12.0 && terrain.height(p.x, p.y) > terrain.water_level() + 0.5
+                })
+        } else {
+            None
+        };
+        let Some(position) = position else {
+            continue;
+        };
+
+        let ground = terrain.height(position.x, position.y);
+        let campfire = commands
+            .spawn((
+                SpatialBundle::from_transform(Transform::from_xyz(position.x, ground, position.y)),
+                Campfire,
+                DamageZone {
+                    radius: 0.6,
+                    damage_per_second: settings.fire_damage,
+                },
+            ))
+            .with_children(|fire| {
+                // Logs leaning into a loose star around the embers
+                for i in 0..4 {
+                    let angle = i as f32 * std::f32::consts::FRAC_PI_4 * 2.0 + 0.3;
+                    fire.spawn(PbrBundle {
+                        mesh: assets.log_mesh.clone(),
+                        material: assets.log_material.clone(),
+                        transform: Transform::from_xyz(0.0, 0.1, 0.0)
+                            .with_rotation(Quat::from_rotation_y(angle) * Quat::from_rotation_x(0.2)),
+                        ..default()
+                    });
+                }
+                fire.spawn(PbrBundle {
+                    mesh: assets.ember_mesh.clone(),
+                    material: assets.ember_material.clone(),
+                    transform: Transform::from_xyz(0.0, 0.12, 0.0).with_scale(Vec3::new(1.0, 0.6, 1.0)),
+                    ..default()
+                });
+                fire.spawn((
+                    PointLightBundle {
+                        point_light: PointLight {
+                            color: Color::srgb(1.0, 0.6, 0.3),
+                            intensity: 60_000.0,
+                            range: 12.0,
+                            shadows_enabled: false,
+                            ..default()
+                        },
+                        transform: Transform::from_xyz(0.0, 0.6, 0.0),
+                        ..default()
+                    },
+                    CampfireLight,
+                ));
+            })
+            .id();
+        commands.entity(chunk_entity).add_child(campfire);
+    }
+}
+
+fn flicker_campfires(time: Res<Time>, mut light_query: Query<(&mut PointLight, &GlobalTransform), With<CampfireLight>>) {
+    let t = time.elapsed_seconds();
+    for (mut light, transform) in light_query.iter_mut() {
+        // Offset by position so neighbouring fires do not pulse in step
+        let phase = transform.translation().x * 0.37 + transform.translation().z * 0.71;
+        let flicker = 0.85 + 0.1 * (t * 9.0 + phase).sin() + 0.05 * (t * 23.0 + phase * 2.0).sin();
+        light.intensity = 60_000.0 * flicker;
+    }
+}
+
+fn hazard_damage(
+    time: Res<Time>,
+    zone_query: Query<(&DamageZone, &GlobalTransform)>,
+    player_query: Query<&Transform, With<Player>>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    let Ok(body) = player_query.get_single() else {
+        return;
+    };
+    for (zone, transform) in zone_query.iter() {
+        if body.translation.distance(transform.translation()) < zone.radius {
+            damage_events.send(DamageEvent {
+                amount: zone.damage_per_second * time.delta_seconds(),
+                source: DamageSource::Hazard,
+            });
+        }
+    }
+}
+
+// Apply queued damage and shake the camera in proportion to the hit
+fn apply_damage(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageEvent>,
+    noclip: Res<Noclip>,
+    settings: Res<HealthSettings>,
+    mut respawn_point: ResMut<RespawnPoint>,
+    mut player_query: Query<&mut Health, With<Player>>,
+    camera_query: Query<Entity, With<PlayerCamera>>,
+) {
+    let Ok(mut health) = player_query.get_single_mut() else {
+        damage_events.clear();
+        return;
+    };
+    for event in damage_events.read() {
+        if noclip.enabled || health.is_dead() {
+            continue;
+        }
+        health.current = (health.current - event.amount).max(0.0);
+        health.since_damage = 0.0;
+        if event.source == DamageSource::Fall {
+            info!("Fall damage: {:.0}", event.amount);
+        }
+
+        if let Ok(camera) = camera_query.get_single() {
+            let duration = 0.25 + (event.amount * 0.01).min(0.35);
+            commands.entity(camera).insert(CameraShake {
+                intensity: (0.03 + event.amount * 0.004).min(0.3),
+                duration,
+                timer: duration,
+            });
+        }
+
+        if health.is_dead() {
+            info!("Player died ({:?})", event.source);
+            respawn_point.countdown = settings.respawn_delay;
+        }
+    }
+}
+
+fn regenerate_health(time: Res<Time>, settings: Res<HealthSettings>, mut player_query: Query<&mut Health>) {
+    let dt = time.delta_seconds();
+    for mut health in player_query.iter_mut() {
+        health.since_damage += dt;
+        if !health.is_dead() && health.since_damage > settings.regen_delay {
+            health.current = (health.current + settings.regen_rate * dt).min(health.max);
+        }
+    }
+}
+
+// Standing on dry, walkable ground for a while without getting hurt marks a safe spot
+fn track_safe_position(
+    settings: Res<HealthSettings>,
+    mut respawn_point: ResMut<RespawnPoint>,
+    player_query: Query<(&Transform, &Player, &Health)>,
+) {
+    let Ok((transform, player, health)) = player_query.get_single() else {
+        return;
+    };
+    if player.on_ground
+        && player.water == WaterState::Dry
+        && matches!(player.state, MovementState::Walking | MovementState::Crouching)
+        && health.since_damage > settings.safe_delay
+        && !health.is_dead()
+    {
+        respawn_point.safe_position = transform.translation;
+    }
+}
+
+fn light_campfires(
+    settings: Res<HealthSettings>,
+    mut respawn_point: ResMut<RespawnPoint>,
+    player_query: Query<&Transform, With<Player>>,
+    campfire_query: Query<&GlobalTransform, With<Campfire>>,
+) {
+    let Ok(body) = player_query.get_single() else {
+        return;
+    };
+    for transform in campfire_query.iter() {
+        let position = transform.translation();
+        if body.translation.distance(position) < settings.campfire_radius
+            && respawn_point.campfire.is_none_or(|lit| lit.distance(position) > 0.1)
+        {
+            respawn_point.campfire = Some(position);
+            info!("Campfire lit: respawn point set");
+        }
+    }
+}
+
+fn respawn(
+    time: Res<Time>,
+    terrain: TerrainQuery,
+    mut respawn_point: ResMut<RespawnPoint>,
+    mut player_query: Query<(&mut Transform, &mut Player, &mut Stamina, &mut Health)>,
+) {
+    let Ok((mut transform, mut player, mut stamina, mut health)) = player_query.get_single_mut() else {
+        return;
+    };
+    if !health.is_dead() {
+        return;
+    }
+    respawn_point.countdown -= time.delta_seconds();
+    if respawn_point.countdown > 0.0 {
+        return;
+    }
+
+    // Stand beside the fire rather than in it
+    let target = match respawn_point.campfire {
+        Some(campfire) => {
+            let spot = campfire + Vec3::new(1.5, 0.0, 0.0);
+            Vec3::new(spot.x, terrain.height(spot.x, spot.z), spot.z)
+        }
+        None => respawn_point.safe_position,
+    };
+    transform.translation = target;
+    player.velocity = Vec3::ZERO;
+    player.on_ground = true;
+    player.air_time = 0.0;
+    player.jump_buffer = 0.0;
+    player.sprinting = false;
+    player.state = MovementState::Walking;
+    *stamina = Stamina::new(stamina.max);
+    *health = Health::new(health.max);
+    info!("Respawned at {:?}", target);
+}
+
+fn setup_death_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                background_color: BackgroundColor(Color::srgba(0.25, 0.02, 0.02, 0.7)),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            DeathScreen,
+        ))
+        .with_children(|screen| {
+            screen.spawn(TextBundle::from_section(
+                "YOU DIED",
+                TextStyle {
+                    font_size: 64.0,
+                    color: Color::srgb(0.95, 0.85, 0.8),
+                    ..default()
+                },
+            ));
+            screen.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 22.0,
+                        color: Color::srgb(0.9, 0.8, 0.75),
+                        ..default()
+                    },
+                ),
+                DeathCountdownText,
+            ));
+        });
+}
+
+fn update_death_screen(
+    respawn_point: Res<RespawnPoint>,
+    player_query: Query<&Health>,
+    mut screen_query: Query<&mut Visibility, With<DeathScreen>>,
+    mut text_query: Query<&mut Text, With<DeathCountdownText>>,
+) {
+    let dead = player_query.get_single().is_ok_and(Health::is_dead);
+    for mut visibility in screen_query.iter_mut() {
+        *visibility = if dead { Visibility::Visible } else { Visibility::Hidden };
+    }
+    if !dead {
+        return;
+    }
+    let place = if respawn_point.campfire.is_some() { "campfire" } else { "last safe spot" };
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!(
+            "Respawning at the {} in {:.0}...",
+            place,
+            respawn_point.countdown.max(0.0).ceil()
+        );
+    }
+}
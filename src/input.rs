@@ -0,0 +1,823 @@
+use bevy::app::AppExit;
+use bevy::input::mouse::MouseMotion;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(InputMap::load_or_create(BINDINGS_PATH))
+            .init_resource::<ActionState>()
+            .init_resource::<TextEntry>()
+            .add_systems(PreUpdate, update_action_state.after(InputSystem))
+            .add_systems(Last, save_bindings_on_exit);
+    }
+}
+
+const BINDINGS_PATH: &str = "config/input.cfg";
+
+// Everything the player can do, independent of which device triggers it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    Jump, // Also flies up in noclip
+    Sprint,
+    Crouch, // Slides when pressed while sprinting
+    Descend, // Flies down in noclip
+    Interact,
+    Pause,
+    CameraMode,
+    Noclip,
+    GrassQuality,
+    PlaceKeyframe,  // Adds the current view to the camera path
+    RemoveKeyframe,
+    PlayPath,
+    PathTimeOfDay, // Toggles whether the path drives the time of day
+    AddBookmark,
+    Bookmarks, // Shows the bookmark list
+    Aurora,
+    CycleWeather, // Debug: switches straight to the next weather
+    CycleSeason,  // Debug: jumps to the middle of the next season
+    PauseTime,    // Stops the clock; the world carries on
+    FastForward,  // Steps through the time speeds
+    HourForward,
+    HourBack,
+    SkipToDawn,
+    BookmarkSlot1, // Slots pick a bookmark on the open page of the bookmark list
+    BookmarkSlot2,
+    BookmarkSlot3,
+    BookmarkSlot4,
+    BookmarkSlot5,
+    BookmarkSlot6,
+    BookmarkSlot7,
+    BookmarkSlot8,
+    BookmarkSlot9,
+    BookmarkNextPage,
+}
+
+impl Action {
+    // Recordings store actions as bits in the order of this list, so new actions go at the
+    // end; replay files also name the order they were recorded in
+    pub const ALL: [Action; 37] = [
+        Action::MoveForward,
+        Action::MoveBackward,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Jump,
+        Action::Sprint,
+        Action::Crouch,
+        Action::Descend,
+        Action::Interact,
+        Action::Pause,
+        Action::CameraMode,
+        Action::Noclip,
+        Action::GrassQuality,
+        Action::PlaceKeyframe,
+        Action::RemoveKeyframe,
+        Action::PlayPath,
+        Action::PathTimeOfDay,
+        Action::AddBookmark,
+        Action::Bookmarks,
+        Action::Aurora,
+        Action::CycleWeather,
+        Action::CycleSeason,
+        Action::PauseTime,
+        Action::FastForward,
+        Action::HourForward,
+        Action::HourBack,
+        Action::SkipToDawn,
+        Action::BookmarkSlot1,
+        Action::BookmarkSlot2,
+        Action::BookmarkSlot3,
+        Action::BookmarkSlot4,
+        Action::BookmarkSlot5,
+        Action::BookmarkSlot6,
+        Action::BookmarkSlot7,
+        Action::BookmarkSlot8,
+        Action::BookmarkSlot9,
+        Action::BookmarkNextPage,
+    ];
+
+    pub const BOOKMARK_SLOTS: [Action; 9] = [
+        Action::BookmarkSlot1,
+        Action::BookmarkSlot2,
+        Action::BookmarkSlot3,
+        Action::BookmarkSlot4,
+        Action::BookmarkSlot5,
+        Action::BookmarkSlot6,
+        Action::BookmarkSlot7,
+        Action::BookmarkSlot8,
+        Action::BookmarkSlot9,
+    ];
+
+    // Key used in the bindings file
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveForward => "move_forward",
+            Action::MoveBackward => "move_backward",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Jump => "jump",
+            Action::Sprint => "sprint",
+            Action::Crouch => "crouch",
+            Action::Descend => "descend",
+            Action::Interact => "interact",
+            Action::Pause => "pause",
+            Action::CameraMode => "camera_mode",
+            Action::Noclip => "noclip",
+            Action::GrassQuality => "grass_quality",
+            Action::PlaceKeyframe => "place_keyframe",
+            Action::RemoveKeyframe => "remove_keyframe",
+            Action::PlayPath => "play_path",
+            Action::PathTimeOfDay => "path_time_of_day",
+            Action::AddBookmark => "add_bookmark",
+            Action::Bookmarks => "bookmarks",
+            Action::Aurora => "aurora",
+            Action::CycleWeather => "cycle_weather",
+            Action::CycleSeason => "cycle_season",
+            Action::PauseTime => "pause_time",
+            Action::FastForward => "fast_forward",
+            Action::HourForward => "hour_forward",
+            Action::HourBack => "hour_back",
+            Action::SkipToDawn => "skip_to_dawn",
+            Action::BookmarkSlot1 => "bookmark_1",
+            Action::BookmarkSlot2 => "bookmark_2",
+            Action::BookmarkSlot3 => "bookmark_3",
+            Action::BookmarkSlot4 => "bookmark_4",
+            Action::BookmarkSlot5 => "bookmark_5",
+            Action::BookmarkSlot6 => "bookmark_6",
+            Action::BookmarkSlot7 => "bookmark_7",
+            Action::BookmarkSlot8 => "bookmark_8",
+            Action::BookmarkSlot9 => "bookmark_9",
+            Action::BookmarkNextPage => "bookmark_next_page",
+        }
+    }
+
+    // Shown in the controls list
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveForward => "Forward",
+            Action::MoveBackward => "Backward",
+            Action::MoveLeft => "Left",
+            Action::MoveRight => "Right",
+            Action::Jump => "Jump",
+            Action::Sprint => "Sprint",
+            Action::Crouch => "Crouch / Slide",
+            Action::Descend => "Fly Down",
+            Action::Interact => "Interact",
+            Action::Pause => "Menu",
+            Action::CameraMode => "Camera Mode",
+            Action::Noclip => "Noclip",
+            Action::GrassQuality => "Grass Quality",
+            Action::PlaceKeyframe => "Add Keyframe",
+            Action::RemoveKeyframe => "Remove Keyframe",
+            Action::PlayPath => "Play Camera Path",
+            Action::PathTimeOfDay => "Path Time of Day",
+            Action::AddBookmark => "Add Bookmark",
+            Action::Bookmarks => "Bookmarks",
+            Action::Aurora => "Aurora",
+            Action::CycleWeather => "Next Weather",
+            Action::CycleSeason => "Next Season",
+            Action::PauseTime => "Pause Time",
+            Action::FastForward => "Fast Forward",
+            Action::HourForward => "Hour Later",
+            Action::HourBack => "Hour Earlier",
+            Action::SkipToDawn => "Skip to Dawn",
+            Action::BookmarkSlot1 => "Bookmark 1",
+            Action::BookmarkSlot2 => "Bookmark 2",
+            Action::BookmarkSlot3 => "Bookmark 3",
+            Action::BookmarkSlot4 => "Bookmark 4",
+            Action::BookmarkSlot5 => "Bookmark 5",
+            Action::BookmarkSlot6 => "Bookmark 6",
+            Action::BookmarkSlot7 => "Bookmark 7",
+            Action::BookmarkSlot8 => "Bookmark 8",
+            Action::BookmarkSlot9 => "Bookmark 9",
+            Action::BookmarkNextPage => "Next Bookmark Page",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType), // Matches that button on any connected gamepad
+}
+
+// Keys that can be written in the bindings file, by their Debug names
+const BINDABLE_KEYS: &[KeyCode] = &[
+    KeyCode::KeyA, KeyCode::KeyB, KeyCode::KeyC, KeyCode::KeyD, KeyCode::KeyE, KeyCode::KeyF,
+    KeyCode::KeyG, KeyCode::KeyH, KeyCode::KeyI, KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL,
+    KeyCode::KeyM, KeyCode::KeyN, KeyCode::KeyO, KeyCode::KeyP, KeyCode::KeyQ, KeyCode::KeyR,
+    KeyCode::KeyS, KeyCode::KeyT, KeyCode::KeyU, KeyCode::KeyV, KeyCode::KeyW, KeyCode::KeyX,
+    KeyCode::KeyY, KeyCode::KeyZ,
+    KeyCode::Digit0, KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4,
+    KeyCode::Digit5, KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+    KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6,
+    KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+    KeyCode::ArrowUp, KeyCode::ArrowDown, KeyCode::ArrowLeft, KeyCode::ArrowRight,
+    KeyCode::Space, KeyCode::Enter, KeyCode::Escape, KeyCode::Tab, KeyCode::Backspace,
+    KeyCode::ShiftLeft, KeyCode::ShiftRight, KeyCode::ControlLeft, KeyCode::ControlRight,
+    KeyCode::AltLeft, KeyCode::AltRight, KeyCode::CapsLock,
+    KeyCode::Minus, KeyCode::Equal, KeyCode::BracketLeft, KeyCode::BracketRight,
+    KeyCode::Semicolon, KeyCode::Quote, KeyCode::Comma, KeyCode::Period, KeyCode::Slash,
+    KeyCode::Backquote, KeyCode::Backslash,
+];
+
+const BINDABLE_MOUSE_BUTTONS: &[MouseButton] = &[
+    MouseButton::Left,
+    MouseButton::Right,
+    MouseButton::Middle,
+    MouseButton::Back,
+    MouseButton::Forward,
+];
+
+const BINDABLE_GAMEPAD_BUTTONS: &[GamepadButtonType] = &[
+    GamepadButtonType::South,
+    GamepadButtonType::East,
+    GamepadButtonType::North,
+    GamepadButtonType::West,
+    GamepadButtonType::LeftTrigger,
+    GamepadButtonType::LeftTrigger2,
+    GamepadButtonType::RightTrigger,
+    GamepadButtonType::RightTrigger2,
+    GamepadButtonType::Select,
+    GamepadButtonType::Start,
+    GamepadButtonType::LeftThumb,
+    GamepadButtonType::RightThumb,
+    GamepadButtonType::DPadUp,
+    GamepadButtonType::DPadDown,
+    GamepadButtonType::DPadLeft,
+    GamepadButtonType::DPadRight,
+];
+
+impl Binding {
+    // Written as "Key:KeyW", "Mouse:Left" or "Gamepad:South"
+    fn parse(text: &str) -> Option<Binding> {
+        let (device, name) = text.trim().split_once(':')?;
+        match device {
+            "Key" => BINDABLE_KEYS.iter().find(|key| format!("{key:?}") == name).map(|key| Binding::Key(*key)),
+            "Mouse" => BINDABLE_MOUSE_BUTTONS
+                .iter()
+                .find(|button| format!("{button:?}") == name)
+                .map(|button| Binding::Mouse(*button)),
+            "Gamepad" => BINDABLE_GAMEPAD_BUTTONS
+                .iter()
+                .find(|button| format!("{button:?}") == name)
+                .map(|button| Binding::Gamepad(*button)),
+            _ => None,
+        }
+    }
+
+    fn serialize(self) -> String {
+        match self {
+            Binding::Key(key) => format!("Key:{key:?}"),
+            Binding::Mouse(button) => format!("Mouse:{button:?}"),
+            Binding::Gamepad(button) => format!("Gamepad:{button:?}"),
+        }
+    }
+
+    // Short name for the controls list
+    pub fn label(self) -> String {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{key:?}");
+                match key {
+                    KeyCode::ShiftLeft | KeyCode::ShiftRight => "Shift".to_string(),
+                    KeyCode::ControlLeft | KeyCode::ControlRight => "Ctrl".to_string(),
+                    KeyCode::AltLeft | KeyCode::AltRight => "Alt".to_string(),
+                    KeyCode::Escape => "ESC".to_string(),
+                    KeyCode::Minus => "-".to_string(),
+                    KeyCode::Equal => "=".to_string(),
+                    KeyCode::BracketLeft => "[".to_string(),
+                    KeyCode::BracketRight => "]".to_string(),
+                    KeyCode::Semicolon => ";".to_string(),
+                    KeyCode::Quote => "'".to_string(),
+                    KeyCode::Comma => ",".to_string(),
+                    KeyCode::Period => ".".to_string(),
+                    KeyCode::Slash => "/".to_string(),
+                    KeyCode::Backquote => "`".to_string(),
+                    KeyCode::Backslash => "\\".to_string(),
+                    _ => name
+                        .strip_prefix("Key")
+                        .or_else(|| name.strip_prefix("Digit"))
+                        .or_else(|| name.strip_prefix("Arrow"))
+                        .unwrap_or(&name)
+                        .to_string(),
+                }
+            }
+            Binding::Mouse(button) => format!("Mouse {button:?}"),
+            Binding::Gamepad(button) => format!("Pad {button:?}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum InputDevice {
+    #[default]
+    KeyboardMouse,
+    Gamepad,
+}
+
+#[derive(Clone)]
+pub struct MouseSettings {
+    pub sensitivity: f32, // Radians per pixel of mouse motion
+    pub invert_y: bool,
+}
+
+impl Default for MouseSettings {
+    fn default() -> Self {
+        Self {
+            sensitivity: 0.002,
+            invert_y: false,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ControllerSettings {
+    pub look_speed: f32,     // Radians per second at full right-stick deflection
+    pub deadzone: f32,       // Radial deadzone applied to both sticks
+    pub response_curve: f32, // Exponent applied past the deadzone; above 1 gives finer aim near the centre
+    pub invert_y: bool,
+}
+
+impl Default for ControllerSettings {
+    fn default() -> Self {
+        Self {
+            look_speed: 2.5,
+            deadzone: 0.15,
+            response_curve: 1.6,
+            invert_y: false,
+        }
+    }
+}
+
+impl ControllerSettings {
+    // Rescale a raw stick so the deadzone edge maps to zero and apply the response curve
+    fn shape(&self, stick: Vec2) -> Vec2 {
+        let magnitude = stick.length().min(1.0);
+        if magnitude <= self.deadzone {
+            return Vec2::ZERO;
+        }
+        let scaled = ((magnitude - self.deadzone) / (1.0 - self.deadzone)).powf(self.response_curve);
+        stick / stick.length() * scaled
+    }
+}
+
+// Bindings plus per-device tuning, all stored in the same settings file
+#[derive(Resource, Clone)]
+pub struct InputMap {
+    pub bindings: HashMap<Action, Vec<Binding>>,
+    pub mouse: MouseSettings,
+    pub controller: ControllerSettings,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use Binding::{Gamepad, Key};
+        let bindings = HashMap::from([
+            (Action::MoveForward, vec![Key(KeyCode::KeyW), Key(KeyCode::ArrowUp)]),
+            (Action::MoveBackward, vec![Key(KeyCode::KeyS), Key(KeyCode::ArrowDown)]),
+            (Action::MoveLeft, vec![Key(KeyCode::KeyA), Key(KeyCode::ArrowLeft)]),
+            (Action::MoveRight, vec![Key(KeyCode::KeyD), Key(KeyCode::ArrowRight)]),
+            (Action::Jump, vec![Key(KeyCode::Space), Gamepad(GamepadButtonType::South)]),
+            (
+                Action::Sprint,
+                vec![Key(KeyCode::ShiftLeft), Key(KeyCode::ShiftRight), Gamepad(GamepadButtonType::LeftThumb)],
+            ),
+            (Action::Crouch, vec![Key(KeyCode::KeyC), Gamepad(GamepadButtonType::RightThumb)]),
+            (Action::Descend, vec![Key(KeyCode::ControlLeft), Gamepad(GamepadButtonType::East)]),
+            (Action::Interact, vec![Key(KeyCode::KeyE), Gamepad(GamepadButtonType::West)]),
+            (Action::Pause, vec![Key(KeyCode::Escape), Gamepad(GamepadButtonType::Start)]),
+            (Action::CameraMode, vec![Key(KeyCode::KeyV), Gamepad(GamepadButtonType::North)]),
+            (Action::Noclip, vec![Key(KeyCode::KeyN)]),
+            (Action::GrassQuality, vec![Key(KeyCode::KeyG)]),
+            (Action::PlaceKeyframe, vec![Key(KeyCode::KeyK)]),
+            (Action::RemoveKeyframe, vec![Key(KeyCode::Backspace)]),
+            (Action::PlayPath, vec![Key(KeyCode::KeyP)]),
+            (Action::PathTimeOfDay, vec![Key(KeyCode::KeyT)]),
+            (Action::AddBookmark, vec![Key(KeyCode::KeyM)]),
+            (Action::Bookmarks, vec![Key(KeyCode::KeyB)]),
+            (Action::Aurora, vec![Key(KeyCode::KeyU)]),
+            (Action::CycleWeather, vec![Key(KeyCode::F8)]),
+            (Action::CycleSeason, vec![Key(KeyCode::F7)]),
+            (Action::PauseTime, vec![Key(KeyCode::Comma)]),
+            (Action::FastForward, vec![Key(KeyCode::Period)]),
+            (Action::HourForward, vec![Key(KeyCode::BracketRight)]),
+            (Action::HourBack, vec![Key(KeyCode::BracketLeft)]),
+            (Action::SkipToDawn, vec![Key(KeyCode::Slash)]),
+            (Action::BookmarkSlot1, vec![Key(KeyCode::Digit1)]),
+            (Action::BookmarkSlot2, vec![Key(KeyCode::Digit2)]),
+            (Action::BookmarkSlot3, vec![Key(KeyCode::Digit3)]),
+            (Action::BookmarkSlot4, vec![Key(KeyCode::Digit4)]),
+            (Action::BookmarkSlot5, vec![Key(KeyCode::Digit5)]),
+            (Action::BookmarkSlot6, vec![Key(KeyCode::Digit6)]),
+            (Action::BookmarkSlot7, vec![Key(KeyCode::Digit7)]),
+            (Action::BookmarkSlot8, vec![Key(KeyCode::Digit8)]),
+            (Action::BookmarkSlot9, vec![Key(KeyCode::Digit9)]),
+            (Action::BookmarkNextPage, vec![Key(KeyCode::Digit0)]),
+        ]);
+        Self {
+            bindings,
+            mouse: MouseSettings::default(),
+            controller: ControllerSettings::default(),
+        }
+    }
+}
+
+impl InputMap {
+    pub fn bindings_for(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    // Actions missing from the file keep their default bindings
+    fn parse(text: &str) -> Self {
+        let mut map = InputMap::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((name, bindings)) = line.split_once('=') else {
+                warn!("{}:{}: expected `action = bindings`", BINDINGS_PATH, number + 1);
+                continue;
+            };
+            if name.contains('.') {
+                if !map.parse_device_setting(name.trim(), bindings.trim()) {
+                    warn!("{}:{}: invalid device setting '{}'", BINDINGS_PATH, number + 1, line);
+                }
+                continue;
+            }
+            let Some(action) = Action::from_name(name.trim()) else {
+                warn!("{}:{}: unknown action '{}'", BINDINGS_PATH, number + 1, name.trim());
+                continue;
+            };
+            let mut parsed = Vec::new();
+            let mut written = 0;
+            for binding in bindings.split(',').filter(|b| !b.trim().is_empty()) {
+                written += 1;
+                match Binding::parse(binding) {
+                    Some(binding) => parsed.push(binding),
+                    None => warn!("{}:{}: unknown binding '{}'", BINDINGS_PATH, number + 1, binding.trim()),
+                }
+            }
+            // An empty line unbinds the action, but one where nothing parsed keeps the default
+            if written > 0 && parsed.is_empty() {
+                continue;
+            }
+            map.bindings.insert(action, parsed);
+        }
+        map
+    }
+
+    // Lines like `mouse.sensitivity = 0.002`; returns false if the key or value is invalid
+    fn parse_device_setting(&mut self, key: &str, value: &str) -> bool {
+        fn set<T: std::str::FromStr>(target: &mut T, value: &str) -> bool {
+            value.parse().map(|parsed| *target = parsed).is_ok()
+        }
+        match key {
+            "mouse.sensitivity" => set(&mut self.mouse.sensitivity, value),
+            "mouse.invert_y" => set(&mut self.mouse.invert_y, value),
+            "gamepad.look_speed" => set(&mut self.controller.look_speed, value),
+            "gamepad.deadzone" => set(&mut self.controller.deadzone, value),
+            "gamepad.response_curve" => set(&mut self.controller.response_curve, value),
+            "gamepad.invert_y" => set(&mut self.controller.invert_y, value),
+            _ => false,
+        }
+    }
+
+    fn serialize(&self) -> String {
+        let mut text = String::from("# action = comma separated bindings (Key:<KeyCode>, Mouse:<Button>, Gamepad:<Button>)\n");
+        for action in Action::ALL {
+            let bindings: Vec<String> = self.bindings_for(action).iter().map(|b| b.serialize()).collect();
+            text.push_str(&format!("{} = {}\n", action.name(), bindings.join(", ")));
+        }
+        text.push_str("\n# Per-device settings\n");
+        text.push_str(&format!("mouse.sensitivity = {}\n", self.mouse.sensitivity));
+        text.push_str(&format!("mouse.invert_y = {}\n", self.mouse.invert_y));
+        text.push_str(&format!("gamepad.look_speed = {}\n", self.controller.look_speed));
+        text.push_str(&format!("gamepad.deadzone = {}\n", self.controller.deadzone));
+        text.push_str(&format!("gamepad.response_curve = {}\n", self.controller.response_curve));
+        text.push_str(&format!("gamepad.invert_y = {}\n", self.controller.invert_y));
+        text
+    }
+
+    fn load_or_create(path: &str) -> Self {
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                info!("Loaded input bindings from {}", path);
+                InputMap::parse(&text)
+            }
+            Err(_) => {
+                let map = InputMap::default();
+                map.save(path);
+                map
+            }
+        }
+    }
+
+    pub fn save(&self, path: &str) {
+        if let Some(parent) = Path::new(path).parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Err(error) = fs::write(path, self.serialize()) {
+            warn!("Could not save input bindings to {}: {}", path, error);
+        }
+    }
+}
+
+// Set while a text field has keyboard focus; every action reads as released so typing
+// does not also move the player
+#[derive(Resource, Default)]
+pub struct TextEntry {
+    pub active: bool,
+}
+
+// Per-frame action state, rebuilt from the raw device input before gameplay runs
+#[derive(Resource, Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    just_released: HashSet<Action>,
+    held: HashSet<Action>, // Raw device state, tracked even while text entry blocks actions
+    stick: Vec2,        // Shaped left stick: x right, y forward
+    look: Vec2,         // Radians this frame: x turns right, y looks up
+    pub device: InputDevice, // Whichever device was used last
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    #[allow(dead_code)] // Nothing reacts to releases yet
+    pub fn just_released(&self, action: Action) -> bool {
+        self.just_released.contains(&action)
+    }
+
+    // Camera rotation requested this frame by the mouse or right stick
+    pub fn look(&self) -> Vec2 {
+        self.look
+    }
+
+    // Movement input, x right and y forward. Keys give full length, the stick is analogue.
+    pub fn movement(&self) -> Vec2 {
+        if self.stick != Vec2::ZERO {
+            return self.stick.clamp_length_max(1.0);
+        }
+        let mut movement = Vec2::ZERO;
+        if self.pressed(Action::MoveForward) {
+            movement.y += 1.0;
+        }
+        if self.pressed(Action::MoveBackward) {
+            movement.y -= 1.0;
+        }
+        if self.pressed(Action::MoveRight) {
+            movement.x += 1.0;
+        }
+        if self.pressed(Action::MoveLeft) {
+            movement.x -= 1.0;
+        }
+        movement.normalize_or_zero()
+    }
+
+    // Snapshot of everything gameplay reads, for recording
+    pub fn frame(&self) -> ActionFrame {
+        ActionFrame {
+            pressed: action_bits(&self.pressed),
+            just_pressed: action_bits(&self.just_pressed),
+            just_released: action_bits(&self.just_released),
+            stick: self.stick,
+            look: self.look,
+        }
+    }
+
+    // Replace this frame's input with a recorded one
+    pub fn apply_frame(&mut self, frame: &ActionFrame) {
+        self.pressed = action_set(frame.pressed);
+        self.just_pressed = action_set(frame.just_pressed);
+        self.just_released = action_set(frame.just_released);
+        self.stick = frame.stick;
+        self.look = frame.look;
+    }
+}
+
+// One frame of resolved input. Actions are bit sets indexed by their position in
+// Action::ALL, so recordings do not depend on the bindings in use.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ActionFrame {
+    pub pressed: u64,
+    pub just_pressed: u64,
+    pub just_released: u64,
+    pub stick: Vec2,
+    pub look: Vec2,
+}
+
+const _: () = assert!(Action::ALL.len() <= 64, "ActionFrame has a bit per action");
+
+impl ActionFrame {
+    // The same input from a recording whose bits follow `order`, moved to the positions the
+    // actions have in Action::ALL now
+    pub fn reordered(self, order: &[Action]) -> Self {
+        let remap = |bits: u64| {
+            let actions = order
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| bits & (1 << i) != 0)
+                .map(|(_, action)| *action)
+                .collect();
+            action_bits(&actions)
+        };
+        Self {
+            pressed: remap(self.pressed),
+            just_pressed: remap(self.just_pressed),
+            just_released: remap(self.just_released),
+            ..self
+        }
+    }
+}
+
+fn action_bits(actions: &HashSet<Action>) -> u64 {
+    Action::ALL
+        .iter()
+        .enumerate()
+        .filter(|(_, action)| actions.contains(action))
+        .fold(0, |bits, (i, _)| bits | 1 << i)
+}
+
+fn action_set(bits: u64) -> HashSet<Action> {
+    Action::ALL
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| bits & (1 << i) != 0)
+        .map(|(_, action)| *action)
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn update_action_state(
+    map: Res<InputMap>,
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    gamepads: Res<Gamepads>,
+    text_entry: Res<TextEntry>,
+    mut state: ResMut<ActionState>,
+) {
+    let was_held = std::mem::take(&mut state.held);
+    for action in Action::ALL {
+        let down = map.bindings_for(action).iter().any(|binding| match *binding {
+            Binding::Key(key) => keys.pressed(key),
+            Binding::Mouse(button) => mouse_buttons.pressed(button),
+            Binding::Gamepad(button) => gamepads
+                .iter()
+                .any(|gamepad| gamepad_buttons.pressed(GamepadButton::new(gamepad, button))),
+        });
+        if down {
+            state.held.insert(action);
+        }
+    }
+    // Keys pressed while typing do not fire once typing ends, even if still held
+    if text_entry.active {
+        state.pressed.clear();
+        state.just_pressed.clear();
+        state.just_released.clear();
+    } else {
+        state.pressed = state.held.clone();
+        state.just_pressed = state.held.difference(&was_held).copied().collect();
+        state.just_released = was_held.difference(&state.held).copied().collect();
+    }
+
+    // Sticks from every connected gamepad; the one pushed furthest wins
+    let stick = |gamepad: Gamepad, x: GamepadAxisType, y: GamepadAxisType| {
+        Vec2::new(
+            gamepad_axes.get(GamepadAxis::new(gamepad, x)).unwrap_or(0.0),
+            gamepad_axes.get(GamepadAxis::new(gamepad, y)).unwrap_or(0.0),
+        )
+    };
+    let strongest = |sticks: Vec<Vec2>| sticks.into_iter().fold(Vec2::ZERO, |a, b| if b.length() > a.length() { b } else { a });
+    let left = strongest(gamepads.iter().map(|g| stick(g, GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY)).collect());
+    let right = strongest(gamepads.iter().map(|g| stick(g, GamepadAxisType::RightStickX, GamepadAxisType::RightStickY)).collect());
+    state.stick = map.controller.shape(left);
+    let right = map.controller.shape(right);
+
+    let mouse_delta: Vec2 = mouse_motion.read().map(|motion| motion.delta).sum();
+    let mut look = Vec2::new(mouse_delta.x, -mouse_delta.y) * map.mouse.sensitivity;
+    if map.mouse.invert_y {
+        look.y = -look.y;
+    }
+    let mut stick_look = right * map.controller.look_speed * time.delta_seconds();
+    if map.controller.invert_y {
+        stick_look.y = -stick_look.y;
+    }
+    state.look = look + stick_look;
+
+    // Follow whichever device was touched last
+    let gamepad_used = state.stick != Vec2::ZERO
+        || right != Vec2::ZERO
+        || gamepads.iter().any(|gamepad| {
+            BINDABLE_GAMEPAD_BUTTONS
+                .iter()
+                .any(|button| gamepad_buttons.just_pressed(GamepadButton::new(gamepad, *button)))
+        });
+    let keyboard_mouse_used = keys.get_just_pressed().next().is_some()
+        || mouse_buttons.get_just_pressed().next().is_some()
+        || mouse_delta != Vec2::ZERO;
+    if gamepad_used && state.device != InputDevice::Gamepad {
+        state.device = InputDevice::Gamepad;
+        info!("Input device: gamepad");
+    } else if keyboard_mouse_used && !gamepad_used && state.device != InputDevice::KeyboardMouse {
+        state.device = InputDevice::KeyboardMouse;
+        info!("Input device: keyboard and mouse");
+    }
+}
+
+fn save_bindings_on_exit(map: Res<InputMap>, mut exit_events: EventReader<AppExit>) {
+    if exit_events.read().next().is_some() {
+        map.save(BINDINGS_PATH);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_bindings_survive_a_round_trip() {
+        let defaults = InputMap::default();
+        let parsed = InputMap::parse(&defaults.serialize());
+        for action in Action::ALL {
+            assert_eq!(parsed.bindings_for(action), defaults.bindings_for(action), "{}", action.name());
+        }
+        assert_eq!(parsed.mouse.sensitivity, defaults.mouse.sensitivity);
+        assert_eq!(parsed.controller.invert_y, defaults.controller.invert_y);
+    }
+
+    #[test]
+    fn bad_lines_are_skipped_and_the_rest_kept() {
+        let text = "\
+            jump = Key:KeyJ, Key:NotAKey
+            crouch = Key:NotAKey
+            fly_to_the_moon = Key:KeyF
+            this line has no equals sign
+            interact = Mouse:Right
+            mouse.sensitivity = fast
+        ";
+        let map = InputMap::parse(text);
+        let defaults = InputMap::default();
+        assert_eq!(map.bindings_for(Action::Jump), &[Binding::Key(KeyCode::KeyJ)]);
+        assert_eq!(map.bindings_for(Action::Crouch), defaults.bindings_for(Action::Crouch));
+        assert_eq!(map.bindings_for(Action::Interact), &[Binding::Mouse(MouseButton::Right)]);
+        assert_eq!(map.mouse.sensitivity, defaults.mouse.sensitivity);
+    }
+
+    #[test]
+    fn reordered_frames_move_bits_to_the_current_order() {
+        let order = [Action::Jump, Action::Crouch, Action::MoveForward];
+        let frame = ActionFrame {
+            pressed: 0b101,
+            just_pressed: 0b010,
+            just_released: 0,
+            stick: Vec2::new(0.5, 1.0),
+            look: Vec2::ZERO,
+        };
+        let reordered = frame.reordered(&order);
+        assert_eq!(reordered.pressed, action_bits(&HashSet::from([Action::Jump, Action::MoveForward])));
+        assert_eq!(reordered.just_pressed, action_bits(&HashSet::from([Action::Crouch])));
+        assert_eq!(reordered.just_released, 0);
+        assert_eq!(reordered.stick, frame.stick);
+
+        // Recorded in the current order, nothing moves
+        assert_eq!(frame.reordered(&Action::ALL), frame);
+    }
+
+    #[test]
+    fn missing_actions_keep_their_defaults() {
+        let map = InputMap::parse("jump = Key:KeyJ\nnoclip =\n");
+        let defaults = InputMap::default();
+        assert_eq!(map.bindings_for(Action::Jump), &[Binding::Key(KeyCode::KeyJ)]);
+        assert!(map.bindings_for(Action::Noclip).is_empty());
+        for action in Action::ALL.into_iter().filter(|a| !matches!(a, Action::Jump | Action::Noclip)) {
+            assert_eq!(map.bindings_for(action), defaults.bindings_for(action), "{}", action.name());
+        }
+    }
+}
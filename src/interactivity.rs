@@ -1,6 +1,11 @@
 use bevy::prelude::*;
 use noise::{NoiseFn, Perlin};
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::placement::{self, PlacementRules};
+use crate::seasons::Seasons;
+use crate::terrain::TerrainQuery;
 
 pub struct InteractivityPlugin;
 
@@ -45,6 +50,9 @@ pub struct Mushroom {
     pub glow_intensity: f32,
 }
 
+// Cap glow at an intensity of 1; each mushroom's own intensity scales it
+pub const MUSHROOM_GLOW: Color = Color::srgb(0.8, 0.2, 0.8);
+
 #[derive(Component)]
 pub struct Collectible;
 
@@ -58,20 +66,34 @@ fn spawn_initial_mushrooms(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mushroom_noise: Res<MushroomNoise>,
+    terrain: TerrainQuery,
+    seasons: Res<Seasons>,
 ) {
-    spawn_mushrooms_in_area(&mut commands, &mut meshes, &mut materials, &mushroom_noise, -50.0, 50.0, -50.0, 50.0);
+    spawn_mushrooms_in_area(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &mushroom_noise,
+        &terrain,
+        seasons.mushroom_abundance(),
+        Vec2::splat(-50.0),
+        Vec2::splat(50.0),
+    );
 }
 
+#[allow(clippy::too_many_arguments)]
 fn spawn_mushrooms_around_camera(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mushroom_noise: Res<MushroomNoise>,
-    camera_query: Query<&Transform, (With<Camera3d>, Without<Mushroom>)>,
+    terrain: TerrainQuery,
+    seasons: Res<Seasons>,
+    camera_query: Query<&GlobalTransform, With<Camera3d>>,
     mushroom_query: Query<&Transform, With<Mushroom>>,
 ) {
     if let Ok(camera_transform) = camera_query.get_single() {
-        let camera_pos = camera_transform.translation;
+        let camera_pos = camera_transform.translation();
         let spawn_distance = 40.0;
         
         let mut has_nearby_mushrooms = false;
@@ -89,93 +111,103 @@ fn spawn_mushrooms_around_camera(
                 &mut meshes,
                 &mut materials,
                 &mushroom_noise,
-                camera_pos.x - spawn_distance,
-                camera_pos.x + spawn_distance,
-                camera_pos.z - spawn_distance,
-                camera_pos.z + spawn_distance,
+                &terrain,
+                seasons.mushroom_abundance(),
+                camera_pos.xz() - Vec2::splat(spawn_distance),
+                camera_pos.xz() + Vec2::splat(spawn_distance),
             );
         }
     }
 }
 
+const MUSHROOM_LAYER: u64 = 300;
+
+#[allow(clippy::too_many_arguments)]
 fn spawn_mushrooms_in_area(
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
     mushroom_noise: &MushroomNoise,
-    min_x: f32,
-    max_x: f32,
-    min_z: f32,
-    max_z: f32,
+    terrain: &TerrainQuery,
+    abundance: f32, // Seasonal multiplier on how many come up
+    min: Vec2,
+    max: Vec2,
 ) {
-    let mut rng = rand::thread_rng();
-    let rarity = 0.05; // 5% chance per grid cell
-
-    for x in (min_x as i32)..(max_x as i32) {
-        for z in (min_z as i32)..(max_z as i32) {
-            let world_x = x as f32;
-            let world_z = z as f32;
-
-            let noise_value = mushroom_noise.noise.get([world_x as f64 * 0.15, world_z as f64 * 0.15]) as f32;
-            let should_spawn = noise_value > (1.0 - rarity) && rng.gen_range(0.0..1.0) < rarity;
-
-            if should_spawn {
-                let terrain_height = mushroom_noise.noise.get([world_x as f64 * 0.1, world_z as f64 * 0.1]) as f32 * 5.0;
-                let glow = 0.5 + rng.gen_range(0.0..1.0) * 0.5;
-
-                // Mushroom cap
-                commands.spawn((
-                    PbrBundle {
-                        mesh: meshes.add(Sphere {
-                            radius: 0.3,
-                        }),
-                        material: materials.add(StandardMaterial {
-                            base_color: Color::srgb(0.8, 0.2, 0.8),
-                            emissive: Color::srgb(0.8 * glow, 0.2 * glow, 0.8 * glow).into(),
-                            ..default()
-                        }),
-                        transform: Transform::from_xyz(world_x, terrain_height + 0.5, world_z),
+    // Seeded by the corner of the area, in whole metres, so the same area always grows
+    // the same mushrooms and replays see the same ones
+    let corner = (min.x.floor() as i32, min.y.floor() as i32);
+    let mut rng = StdRng::seed_from_u64(placement::chunk_seed(terrain.config.seed, corner, MUSHROOM_LAYER));
+    let rarity = 0.1 * abundance; // Chance to keep a candidate point inside a mushroom patch
+    let rules = PlacementRules {
+        min_spacing: 4.0,
+        max_slope: 25.0,
+        ..default()
+    };
+
+    for point in placement::scatter(terrain, min, max, &rules, &mut rng) {
+        let world_x = point.x;
+        let world_z = point.z;
+
+        let noise_value = mushroom_noise.noise.get([world_x as f64 * 0.15, world_z as f64 * 0.15]) as f32;
+        let should_spawn = noise_value > 0.2 && rng.gen_range(0.0..1.0) < rarity;
+
+        if should_spawn {
+            let terrain_height = point.y;
+            let glow = 0.5 + rng.gen_range(0.0..1.0) * 0.5;
+
+            // Mushroom cap
+            commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(Sphere {
+                        radius: 0.3,
+                    }),
+                    material: materials.add(StandardMaterial {
+                        base_color: Color::srgb(0.8, 0.2, 0.8),
+                        emissive: MUSHROOM_GLOW.to_linear() * glow,
                         ..default()
-                    },
-                    Mushroom {
-                        glow_intensity: glow,
-                    },
-                    Collectible,
-                ));
-
-                // Mushroom stem
-                commands.spawn(PbrBundle {
-                    mesh: meshes.add(Cylinder {
-                        radius: 0.05,
-                        half_height: 0.3,
                     }),
-                    material: materials.add(Color::srgb(0.9, 0.9, 0.9)),
-                    transform: Transform::from_xyz(world_x, terrain_height + 0.2, world_z),
+                    transform: Transform::from_xyz(world_x, terrain_height + 0.5, world_z),
                     ..default()
-                });
-            }
+                },
+                Mushroom {
+                    glow_intensity: glow,
+                },
+                Collectible,
+            ));
+
+            // Mushroom stem
+            commands.spawn(PbrBundle {
+                mesh: meshes.add(Cylinder {
+                    radius: 0.05,
+                    half_height: 0.3,
+                }),
+                material: materials.add(Color::srgb(0.9, 0.9, 0.9)),
+                transform: Transform::from_xyz(world_x, terrain_height + 0.2, world_z),
+                ..default()
+            });
         }
     }
 }
 
 fn check_mushroom_collection(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    _player_query: Query<&Transform, (With<super::player::Player>, Without<Mushroom>)>,
-    camera_query: Query<&Transform, (With<Camera3d>, With<super::player::Player>)>,
+    actions: Res<super::input::ActionState>,
+    camera_query: Query<&GlobalTransform, With<super::player::PlayerCamera>>,
+    body_query: Query<(&GlobalTransform, &super::player::Player)>,
     mushroom_query: Query<(Entity, &Transform, &Mushroom), With<Collectible>>,
     mut commands: Commands,
     mut ev_mushroom_collected: EventWriter<MushroomCollected>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::KeyE) {
-        if let Ok(camera_transform) = camera_query.get_single() {
-            let camera_pos = camera_transform.translation;
+    if actions.just_pressed(super::input::Action::Interact) {
+        if let (Ok(camera_transform), Ok((body_transform, player))) = (camera_query.get_single(), body_query.get_single()) {
+            // Reach from the player's eyes, so collecting works the same in every camera mode
+            let eye_pos = body_transform.translation() + Vec3::Y * player.eye_height;
             let camera_forward = Vec3::from(camera_transform.forward());
             let collection_range = 3.0;
             let max_angle = 0.5; // ~30 degrees cone
 
             // Raycast-style collection: check mushrooms in front of camera
             for (entity, mushroom_transform, _mushroom) in mushroom_query.iter() {
-                let to_mushroom = mushroom_transform.translation - camera_pos;
+                let to_mushroom = mushroom_transform.translation - eye_pos;
                 let distance = to_mushroom.length();
                 
                 if distance < collection_range {
@@ -203,7 +235,7 @@ fn handle_collection_effects(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    mut camera_query: Query<Entity, (With<Camera3d>, With<super::player::Player>)>,
+    mut camera_query: Query<Entity, With<super::player::PlayerCamera>>,
 ) {
     for event in ev_mushroom_collected.read() {
         mushroom_count.collected += 1;
@@ -0,0 +1,274 @@
+use bevy::prelude::*;
+use std::fs;
+use std::ops::{Add, Mul, Sub};
+use std::time::SystemTime;
+
+pub struct LightingPlugin;
+
+impl Plugin for LightingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LightingProfile::load_or_default(PROFILE_PATH))
+            .insert_resource(ProfileWatcher {
+                modified: modified_time(PROFILE_PATH),
+                timer: Timer::from_seconds(1.0, TimerMode::Repeating),
+            })
+            .add_systems(PreUpdate, reload_profile);
+    }
+}
+
+const PROFILE_PATH: &str = "assets/lighting/profile.cfg";
+
+// Built into the game so it still has lighting if the file is missing or a curve is left out
+const DEFAULT_PROFILE: &str = include_str!("../assets/lighting/profile.cfg");
+
+// The profile is written for a day whose sun rises at 5:00 and sets at 19:00. On other
+// days the clock is stretched to fit, so sunset light always comes at sunset.
+pub const PROFILE_SUNRISE: f32 = 5.0;
+pub const PROFILE_SUNSET: f32 = 19.0;
+
+// Keyed values over the 24 hour clock. Between keys the value eases from one to the next,
+// and the last key of the day blends round into the first.
+#[derive(Clone, Debug)]
+pub struct Curve<T> {
+    keys: Vec<(f32, T)>, // (hour, value), sorted by hour
+}
+
+impl<T> Curve<T>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f32, Output = T>,
+{
+    pub fn sample(&self, hour: f32) -> T {
+        let hour = hour.rem_euclid(24.0);
+        let count = self.keys.len();
+        let next = self.keys.iter().position(|(key_hour, _)| *key_hour > hour).unwrap_or(count);
+        // Keys before the first and after the last wrap round midnight
+        let (last, first) = (self.keys[count - 1], self.keys[0]);
+        let from = if next == 0 { (last.0 - 24.0, last.1) } else { self.keys[next - 1] };
+        let to = if next == count { (first.0 + 24.0, first.1) } else { self.keys[next] };
+        let span = to.0 - from.0;
+        if span <= 0.0 {
+            return from.1;
+        }
+        let t = ((hour - from.0) / span).clamp(0.0, 1.0);
+        from.1 + (to.1 - from.1) * (t * t * (3.0 - 2.0 * t))
+    }
+}
+
+// Colour and intensity gradients over the day, edited by hand in PROFILE_PATH and
+// reloaded while the game runs
+#[derive(Resource, Clone, Debug)]
+pub struct LightingProfile {
+    pub sun_color: Curve<Vec3>,
+    pub sun_illuminance: Curve<f32>, // Lux
+    pub ambient_color: Curve<Vec3>,
+    pub ambient_brightness: Curve<f32>,
+    pub sky_tint: Curve<Vec3>, // Multiplies the scattered sky colour
+    pub fog_tint: Curve<Vec3>, // Multiplies the sky's horizon colour to give the fog colour
+    pub fog_start: Curve<f32>, // Metres
+    pub fog_end: Curve<f32>,
+}
+
+impl Default for LightingProfile {
+    fn default() -> Self {
+        let (fields, _) = ProfileFields::parse(DEFAULT_PROFILE);
+        fields.complete().expect("built-in lighting profile defines every curve")
+    }
+}
+
+// Curves as read from a file, before falling back to the defaults for missing ones
+#[derive(Default)]
+struct ProfileFields {
+    sun_color: Option<Curve<Vec3>>,
+    sun_illuminance: Option<Curve<f32>>,
+    ambient_color: Option<Curve<Vec3>>,
+    ambient_brightness: Option<Curve<f32>>,
+    sky_tint: Option<Curve<Vec3>>,
+    fog_tint: Option<Curve<Vec3>>,
+    fog_start: Option<Curve<f32>>,
+    fog_end: Option<Curve<f32>>,
+}
+
+impl ProfileFields {
+    // Returns the curves that parsed, and a message for each line that did not
+    fn parse(text: &str) -> (Self, Vec<String>) {
+        let mut fields = ProfileFields::default();
+        let mut errors = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                errors.push(format!("line {}: expected `curve = hour:value ...`", number + 1));
+                continue;
+            };
+            let parsed = match key.trim() {
+                "sun_color" => parse_curve(value, parse_color).map(|c| fields.sun_color = Some(c)),
+                "sun_illuminance" => parse_curve(value, parse_number).map(|c| fields.sun_illuminance = Some(c)),
+                "ambient_color" => parse_curve(value, parse_color).map(|c| fields.ambient_color = Some(c)),
+                "ambient_brightness" => parse_curve(value, parse_number).map(|c| fields.ambient_brightness = Some(c)),
+                "sky_tint" => parse_curve(value, parse_color).map(|c| fields.sky_tint = Some(c)),
+                "fog_tint" => parse_curve(value, parse_color).map(|c| fields.fog_tint = Some(c)),
+                "fog_start" => parse_curve(value, parse_number).map(|c| fields.fog_start = Some(c)),
+                "fog_end" => parse_curve(value, parse_number).map(|c| fields.fog_end = Some(c)),
+                _ => None,
+            };
+            if parsed.is_none() {
+                errors.push(format!("line {}: invalid curve '{}'", number + 1, line));
+            }
+        }
+        (fields, errors)
+    }
+
+    fn complete(self) -> Option<LightingProfile> {
+        Some(LightingProfile {
+            sun_color: self.sun_color?,
+            sun_illuminance: self.sun_illuminance?,
+            ambient_color: self.ambient_color?,
+            ambient_brightness: self.ambient_brightness?,
+            sky_tint: self.sky_tint?,
+            fog_tint: self.fog_tint?,
+            fog_start: self.fog_start?,
+            fog_end: self.fog_end?,
+        })
+    }
+
+    fn or_default(self) -> LightingProfile {
+        let default = LightingProfile::default();
+        LightingProfile {
+            sun_color: self.sun_color.unwrap_or(default.sun_color),
+            sun_illuminance: self.sun_illuminance.unwrap_or(default.sun_illuminance),
+            ambient_color: self.ambient_color.unwrap_or(default.ambient_color),
+            ambient_brightness: self.ambient_brightness.unwrap_or(default.ambient_brightness),
+            sky_tint: self.sky_tint.unwrap_or(default.sky_tint),
+            fog_tint: self.fog_tint.unwrap_or(default.fog_tint),
+            fog_start: self.fog_start.unwrap_or(default.fog_start),
+            fog_end: self.fog_end.unwrap_or(default.fog_end),
+        }
+    }
+}
+
+// `hour:value hour:value ...`, in any order
+fn parse_curve<T>(value: &str, parse_value: fn(&str) -> Option<T>) -> Option<Curve<T>> {
+    let mut keys = Vec::new();
+    for key in value.split_whitespace() {
+        let (hour, value) = key.split_once(':')?;
+        let hour: f32 = hour.parse().ok()?;
+        if !(0.0..24.0).contains(&hour) {
+            return None;
+        }
+        keys.push((hour, parse_value(value)?));
+    }
+    if keys.is_empty() {
+        return None;
+    }
+    keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Some(Curve { keys })
+}
+
+fn parse_number(value: &str) -> Option<f32> {
+    value.parse().ok()
+}
+
+// `r,g,b`
+fn parse_color(value: &str) -> Option<Vec3> {
+    let mut parts = value.split(',').map(|part| part.parse::<f32>().ok());
+    let color = Vec3::new(parts.next()??, parts.next()??, parts.next()??);
+    parts.next().is_none().then_some(color)
+}
+
+impl LightingProfile {
+    fn load(path: &str) -> Option<Self> {
+        let text = fs::read_to_string(path).ok()?;
+        let (fields, errors) = ProfileFields::parse(&text);
+        for error in errors {
+            warn!("{}: {}", path, error);
+        }
+        Some(fields.or_default())
+    }
+
+    fn load_or_default(path: &str) -> Self {
+        match LightingProfile::load(path) {
+            Some(profile) => {
+                info!("Loaded lighting profile from {}", path);
+                profile
+            }
+            None => {
+                warn!("Could not read lighting profile {}, using the built-in one", path);
+                LightingProfile::default()
+            }
+        }
+    }
+}
+
+#[derive(Resource)]
+struct ProfileWatcher {
+    modified: Option<SystemTime>,
+    timer: Timer,
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+// Check the file once a second and swap in the new curves when it has been saved
+fn reload_profile(time: Res<Time>, mut watcher: ResMut<ProfileWatcher>, mut profile: ResMut<LightingProfile>) {
+    if !watcher.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let modified = modified_time(PROFILE_PATH);
+    if modified.is_none() || modified == watcher.modified {
+        return;
+    }
+    watcher.modified = modified;
+    if let Some(reloaded) = LightingProfile::load(PROFILE_PATH) {
+        *profile = reloaded;
+        info!("Reloaded lighting profile from {}", PROFILE_PATH);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curves_parse_and_bad_lines_are_reported() {
+        let text = "\
+            # a comment
+            sun_illuminance = 18:0 6:100 12:1000
+            sun_color = 12:1,0.9
+            fog_start = 25:10
+            moon_glow = 12:1
+            no equals sign here
+        ";
+        let (fields, errors) = ProfileFields::parse(text);
+        let illuminance = fields.sun_illuminance.as_ref().expect("sun_illuminance parses");
+        assert_eq!(illuminance.sample(12.0), 1000.0);
+        assert!(fields.sun_color.is_none());
+        assert!(fields.fog_start.is_none());
+        assert_eq!(errors.len(), 4);
+
+        // Curves that did not parse come from the built-in profile
+        let profile = fields.or_default();
+        let default = LightingProfile::default();
+        assert_eq!(profile.sun_illuminance.sample(6.0), 100.0);
+        assert_eq!(profile.fog_start.sample(3.0), default.fog_start.sample(3.0));
+    }
+
+    #[test]
+    fn samples_wrap_round_midnight() {
+        let curve = Curve { keys: vec![(2.0, 10.0), (22.0, 30.0)] };
+        assert_eq!(curve.sample(2.0), 10.0);
+        assert_eq!(curve.sample(22.0), 30.0);
+        // Midnight is halfway between 22:00 and 2:00, on either side of the wrap
+        assert!((curve.sample(0.0) - 20.0).abs() < 1e-4);
+        assert!((curve.sample(24.0) - 20.0).abs() < 1e-4);
+        assert!((curve.sample(-1.0) - curve.sample(23.0)).abs() < 1e-4);
+        let before = curve.sample(23.9);
+        let after = curve.sample(0.1);
+        assert!(before > 20.0 && after < 20.0);
+
+        let single = Curve { keys: vec![(12.0, 5.0)] };
+        assert_eq!(single.sample(3.0), 5.0);
+    }
+}
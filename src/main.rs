@@ -1,19 +1,68 @@
 mod terrain;
 mod trees;
+mod forest_growth;
+mod placement;
+mod wind;
+mod grass;
+mod props;
+mod water;
+mod collision;
 mod player;
+mod abilities;
+mod health;
+mod camera;
+mod cinematic;
+mod bookmarks;
+mod noclip;
 mod day_night;
+mod seasons;
+mod lighting;
+mod weather;
+mod precipitation;
+mod clouds;
+mod sky;
 mod interactivity;
 mod fog;
 mod ui;
 mod visuals;
 mod controls;
 mod settings;
+mod input;
+mod replay;
 
+use bevy::app::ScheduleRunnerPlugin;
 use bevy::prelude::*;
+use bevy::render::settings::WgpuSettings;
+use bevy::render::RenderPlugin;
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
 
-fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
+fn main() -> AppExit {
+    let options = replay::ReplayOptions::from_args();
+    let mut app = App::new();
+
+    if options.headless {
+        // No window, no GPU: the simulation runs as fast as it can, for replays in CI
+        app.add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: None,
+                    exit_condition: ExitCondition::DontExit,
+                    close_when_requested: false,
+                })
+                .set(RenderPlugin {
+                    render_creation: WgpuSettings {
+                        backends: None,
+                        ..default()
+                    }
+                    .into(),
+                    ..default()
+                })
+                .disable::<WinitPlugin>(),
+        )
+        .add_plugins(ScheduleRunnerPlugin::run_loop(std::time::Duration::ZERO));
+    } else {
+        app.add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: "Procedural Forest Explorer".into(),
                 resolution: (1280.0, 720.0).into(),
@@ -25,23 +74,53 @@ fn main() {
                 ..default()
             }),
             ..default()
-        }))
+        }));
+    }
+
+    app
+        // World generation
         .add_plugins((
             terrain::TerrainPlugin,
             trees::TreesPlugin,
+            forest_growth::ForestGrowthPlugin,
+            wind::WindPlugin,
+            grass::GrassPlugin,
+            props::PropsPlugin,
+            water::WaterPlugin,
+            collision::CollisionPlugin,
+        ))
+        // Player and camera
+        .add_plugins((
             player::PlayerPlugin,
+            abilities::AbilitiesPlugin,
+            health::HealthPlugin,
+            camera::CameraPlugin,
+            cinematic::CinematicPlugin,
+            bookmarks::BookmarksPlugin,
+            noclip::NoclipPlugin,
+        ))
+        // Atmosphere and interface
+        .add_plugins((
             day_night::DayNightPlugin,
+            seasons::SeasonsPlugin,
+            lighting::LightingPlugin,
+            weather::WeatherPlugin,
+            precipitation::PrecipitationPlugin,
+            clouds::CloudsPlugin,
+            sky::SkyPlugin,
             interactivity::InteractivityPlugin,
             fog::FogPlugin,
             ui::UIPlugin,
             visuals::VisualEnhancementsPlugin,
             controls::ControlImprovementsPlugin,
             settings::SettingsPlugin,
+            input::InputPlugin,
             bevy::diagnostic::FrameTimeDiagnosticsPlugin,
         ))
+        .add_plugins(replay::ReplayPlugin { options })
         .insert_resource(ClearColor(Color::srgb(0.5, 0.7, 1.0)))
         .add_systems(Update, update_particles)
-        .run();
+        .run()
 }
 
 fn update_particles(
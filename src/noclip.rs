@@ -0,0 +1,125 @@
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+
+use crate::input::{Action, ActionState};
+use crate::player::{MovementState, Player, PlayerCamera};
+use crate::terrain::TerrainQuery;
+
+pub struct NoclipPlugin;
+
+impl Plugin for NoclipPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Noclip>()
+            .add_systems(Update, (toggle_noclip, adjust_noclip_speed, fly).chain());
+    }
+}
+
+// Free-fly scouting mode. While enabled the walking controller is paused and the
+// body moves wherever the camera looks, ignoring gravity, terrain and colliders.
+#[derive(Resource)]
+pub struct Noclip {
+    pub enabled: bool,
+    pub speed: f32,
+    pub min_speed: f32,
+    pub max_speed: f32,
+    pub boost: f32, // Speed multiplier while sprint is held
+}
+
+impl Default for Noclip {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            speed: 15.0,
+            min_speed: 1.0,
+            max_speed: 200.0,
+            boost: 3.0,
+        }
+    }
+}
+
+fn toggle_noclip(
+    actions: Res<ActionState>,
+    mut noclip: ResMut<Noclip>,
+    terrain: TerrainQuery,
+    camera_query: Query<&GlobalTransform, With<PlayerCamera>>,
+    mut body_query: Query<(&mut Transform, &mut Player)>,
+) {
+    if !actions.just_pressed(Action::Noclip) {
+        return;
+    }
+    noclip.enabled = !noclip.enabled;
+    info!("Noclip: {}", if noclip.enabled { "on" } else { "off" });
+
+    let Ok((mut transform, mut player)) = body_query.get_single_mut() else {
+        return;
+    };
+    player.velocity = Vec3::ZERO;
+    player.jump_buffer = 0.0;
+    player.sprinting = false;
+    // A mantle or slide started before flying must not resume where it left off
+    player.state = MovementState::Walking;
+
+    // Hand back to the walking controller standing on the ground under the camera
+    if !noclip.enabled
+        && let Ok(camera_transform) = camera_query.get_single()
+    {
+        let camera_position = camera_transform.translation();
+        transform.translation = Vec3::new(
+            camera_position.x,
+            terrain.height(camera_position.x, camera_position.z),
+            camera_position.z,
+        );
+        player.on_ground = true;
+        player.air_time = 0.0;
+    }
+}
+
+// Mouse wheel scales the flying speed geometrically
+fn adjust_noclip_speed(mut noclip: ResMut<Noclip>, mut wheel_events: EventReader<MouseWheel>) {
+    let scroll: f32 = wheel_events
+        .read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 40.0,
+        })
+        .sum();
+    if !noclip.enabled || scroll == 0.0 {
+        return;
+    }
+    noclip.speed = (noclip.speed * 1.2_f32.powf(scroll)).clamp(noclip.min_speed, noclip.max_speed);
+    info!("Noclip speed: {:.1}", noclip.speed);
+}
+
+fn fly(
+    actions: Res<ActionState>,
+    time: Res<Time>,
+    noclip: Res<Noclip>,
+    camera_query: Query<&GlobalTransform, With<PlayerCamera>>,
+    mut body_query: Query<&mut Transform, With<Player>>,
+) {
+    if !noclip.enabled {
+        return;
+    }
+    let (Ok(camera_transform), Ok(mut transform)) = (camera_query.get_single(), body_query.get_single_mut()) else {
+        return;
+    };
+
+    // Forward follows the full view direction, up and down are always world-vertical
+    let forward = Vec3::from(camera_transform.forward());
+    let right = Vec3::from(camera_transform.right());
+    let input = actions.movement();
+    let mut direction = forward * input.y + right * input.x;
+    if actions.pressed(Action::Jump) {
+        direction += Vec3::Y;
+    }
+    if actions.pressed(Action::Descend) {
+        direction -= Vec3::Y;
+    }
+
+    let boost = if actions.pressed(Action::Sprint) {
+        noclip.boost
+    } else {
+        1.0
+    };
+    transform.translation += direction.normalize_or_zero() * noclip.speed * boost * time.delta_seconds();
+}
@@ -0,0 +1,141 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::terrain::TerrainQuery;
+
+// Where a kind of vegetation or prop is allowed to appear
+#[derive(Clone, Debug)]
+pub struct PlacementRules {
+    pub min_spacing: f32,   // Metres between any two placed points
+    pub max_slope: f32,     // Degrees from horizontal
+    pub min_altitude: f32,
+    pub max_altitude: f32,
+    pub allow_underwater: bool,
+}
+
+impl Default for PlacementRules {
+    fn default() -> Self {
+        Self {
+            min_spacing: 2.0,
+            max_slope: 35.0,
+            min_altitude: f32::MIN,
+            max_altitude: f32::MAX,
+            allow_underwater: false,
+        }
+    }
+}
+
+impl PlacementRules {
+    // Check slope, altitude and water at a point and return the ground height if it passes
+    pub fn ground_height(&self, terrain: &TerrainQuery, x: f32, z: f32) -> Option<f32> {
+        let height = terrain.height(x, z);
+        if height < self.min_altitude || height > self.max_altitude {
+            return None;
+        }
+        if !self.allow_underwater && height < terrain.water_level() {
+            return None;
+        }
+        if terrain.slope_degrees(x, z) > self.max_slope {
+            return None;
+        }
+        Some(height)
+    }
+}
+
+// Bridson's Poisson-disk sampling over a rectangle: points are spread evenly with no
+// two closer than `spacing`, without the lattice look of sampling grid cells
+pub fn poisson_disk(min: Vec2, max: Vec2, spacing: f32, rng: &mut impl Rng) -> Vec<Vec2> {
+    const ATTEMPTS: usize = 30;
+
+    let size = max - min;
+    if spacing <= 0.0 || size.x <= 0.0 || size.y <= 0.0 {
+        return Vec::new();
+    }
+
+    let cell_size = spacing / std::f32::consts::SQRT_2;
+    let columns = (size.x / cell_size).ceil() as usize;
+    let rows = (size.y / cell_size).ceil() as usize;
+    let mut grid: Vec<Option<usize>> = vec![None; columns * rows];
+    let cell_of = |p: Vec2| -> (usize, usize) {
+        let local = (p - min) / cell_size;
+        (
+            (local.x as usize).min(columns - 1),
+            (local.y as usize).min(rows - 1),
+        )
+    };
+
+    let mut points = Vec::new();
+    let mut active = Vec::new();
+
+    let first = Vec2::new(rng.gen_range(min.x..max.x), rng.gen_range(min.y..max.y));
+    let (cx, cy) = cell_of(first);
+    grid[cy * columns + cx] = Some(0);
+    points.push(first);
+    active.push(0);
+
+    while !active.is_empty() {
+        let active_index = rng.gen_range(0..active.len());
+        let origin = points[active[active_index]];
+        let mut found = false;
+
+        for _ in 0..ATTEMPTS {
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            let distance = rng.gen_range(spacing..spacing * 2.0);
+            let candidate = origin + Vec2::new(angle.cos(), angle.sin()) * distance;
+            if candidate.x < min.x || candidate.y < min.y || candidate.x >= max.x || candidate.y >= max.y {
+                continue;
+            }
+
+            let (cx, cy) = cell_of(candidate);
+            let mut too_close = false;
+            for ny in cy.saturating_sub(2)..(cy + 3).min(rows) {
+                for nx in cx.saturating_sub(2)..(cx + 3).min(columns) {
+                    if let Some(index) = grid[ny * columns + nx]
+                        && points[index].distance(candidate) < spacing
+                    {
+                        too_close = true;
+                    }
+                }
+            }
+
+            if !too_close {
+                grid[cy * columns + cx] = Some(points.len());
+                active.push(points.len());
+                points.push(candidate);
+                found = true;
+                break;
+            }
+        }
+
+        if !found {
+            active.swap_remove(active_index);
+        }
+    }
+
+    points
+}
+
+// Poisson-disk scatter filtered by the placement rules. Returned points sit on the ground.
+pub fn scatter(
+    terrain: &TerrainQuery,
+    min: Vec2,
+    max: Vec2,
+    rules: &PlacementRules,
+    rng: &mut impl Rng,
+) -> Vec<Vec3> {
+    poisson_disk(min, max, rules.min_spacing, rng)
+        .into_iter()
+        .filter_map(|p| rules.ground_height(terrain, p.x, p.y).map(|height| Vec3::new(p.x, height, p.y)))
+        .collect()
+}
+
+// Deterministic seed for a chunk and placement layer in a world, so regenerated content
+// matches and each world seed gets its own layout
+pub fn chunk_seed(world_seed: u32, chunk: (i32, i32), layer: u64) -> u64 {
+    let x = chunk.0 as i64 as u64;
+    let z = chunk.1 as i64 as u64;
+    x.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ z.wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+        ^ layer.wrapping_mul(0x1656_67B1_9E37_79F9)
+        ^ (world_seed as u64).wrapping_mul(0x27D4_EB2F_1656_67C5)
+}
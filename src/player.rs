@@ -1,6 +1,12 @@
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
-use crate::controls;
+
+use crate::camera::CameraMode;
+use crate::collision::SpatialIndex;
+use crate::health::{DamageEvent, DamageSource, Health};
+use crate::input::{Action, ActionState};
+use crate::noclip::Noclip;
+use crate::terrain::TerrainQuery;
 
 pub struct PlayerPlugin;
 
@@ -8,7 +14,7 @@ impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<PlayerSettings>()
             .add_systems(Startup, setup_player)
-            .add_systems(Update, (player_movement, mouse_look))
+            .add_systems(Update, (mouse_look, player_movement).chain())
             .add_systems(Update, handle_escape_key);
     }
 }
@@ -16,145 +22,460 @@ impl Plugin for PlayerPlugin {
 #[derive(Resource)]
 pub struct PlayerSettings {
     pub move_speed: f32,
-    pub mouse_sensitivity: f32,
     pub jump_force: f32,
+    pub eye_height: f32,       // Camera height above the feet
+    pub collision_radius: f32, // Radius of the player's capsule
+    pub gravity: f32,
+    pub ground_acceleration: f32, // How quickly velocity approaches the input target, per second
+    pub air_acceleration: f32,
+    pub ground_friction: f32, // Exponential velocity decay per second with no input
+    pub air_friction: f32,
+    pub max_slope: f32,      // Steepest walkable slope in degrees; steeper ground slides
+    pub step_height: f32,    // Obstacles up to this height are stepped onto
+    pub ground_snap: f32,    // Stay glued to the ground over small dips when walking downhill
+    pub coyote_time: f32,    // Seconds after leaving the ground that a jump still counts
+    pub jump_buffer: f32,    // Seconds a jump press is remembered before landing
+    pub stamina_max: f32,
+    pub sprint_drain: f32,   // Stamina per second while sprinting
+    pub jump_cost: f32,      // Stamina per jump
+    pub stamina_regen: f32,  // Stamina per second once regeneration starts
+    pub regen_delay: f32,    // Seconds after the last drain before regeneration starts
+    pub sprint_resume: f32,  // Fraction of stamina needed to sprint again after running dry
+    pub wade_depth: f32,     // Water deeper than this at the feet slows the player down
+    pub swim_depth: f32,     // Water deeper than this lifts the player off the bottom
+    pub wade_speed_multiplier: f32,
+    pub wade_jump_multiplier: f32,
+    pub swim_speed: f32,
+    pub swim_acceleration: f32,
+    pub swim_vertical_speed: f32, // Upward or downward acceleration while Jump or Descend is held
+    pub float_depth: f32,    // Feet depth at which buoyancy balances gravity; keeps the eyes above water
+    pub water_drag: f32,     // Exponential vertical velocity decay per second in deep water
+    pub crouch_eye_height: f32,
+    pub crouch_speed_multiplier: f32,
+    pub slide_min_speed: f32, // Crouching while sprinting at least this fast starts a slide
+    pub slide_boost: f32,     // Speed multiplier applied when a slide starts
+    pub slide_friction: f32,  // Exponential speed decay per second while sliding
+    pub slide_end_speed: f32,
+    pub slide_max_time: f32,
+    pub mantle_max_height: f32, // Highest ledge above the feet that can be climbed onto
+    pub mantle_time: f32,
+    pub fall_damage_speed: f32, // Landings slower than this are free
+    pub fall_damage_scale: f32, // Damage per m/s of impact speed above the threshold
 }
 
 impl Default for PlayerSettings {
     fn default() -> Self {
         Self {
             move_speed: 5.0,
-            mouse_sensitivity: 0.001,
             jump_force: 5.0,
+            eye_height: 2.5,
+            collision_radius: 0.35,
+            gravity: 9.8,
+            ground_acceleration: 12.0,
+            air_acceleration: 2.0,
+            ground_friction: 8.0,
+            air_friction: 0.2,
+            max_slope: 40.0,
+            step_height: 0.45,
+            ground_snap: 0.3,
+            coyote_time: 0.12,
+            jump_buffer: 0.15,
+            stamina_max: 100.0,
+            sprint_drain: 20.0,
+            jump_cost: 12.0,
+            stamina_regen: 25.0,
+            regen_delay: 1.0,
+            sprint_resume: 0.3,
+            wade_depth: 0.3,
+            swim_depth: 1.6,
+            wade_speed_multiplier: 0.6,
+            wade_jump_multiplier: 0.6,
+            swim_speed: 2.5,
+            swim_acceleration: 3.0,
+            swim_vertical_speed: 14.0,
+            float_depth: 2.2,
+            water_drag: 2.5,
+            crouch_eye_height: 1.4,
+            crouch_speed_multiplier: 0.45,
+            slide_min_speed: 7.0,
+            slide_boost: 1.15,
+            slide_friction: 0.8,
+            slide_end_speed: 2.5,
+            slide_max_time: 1.2,
+            mantle_max_height: 1.9,
+            mantle_time: 0.45,
+            fall_damage_speed: 12.0,
+            fall_damage_scale: 9.0,
         }
     }
 }
 
+// The player's body: owns the physics state and yaw. Its transform sits at the feet.
 #[derive(Component)]
 pub struct Player {
     pub velocity: Vec3,
     pub on_ground: bool,
+    pub air_time: f32,    // Seconds since the player last stood on walkable ground
+    pub jump_buffer: f32, // Time left on a buffered jump press
+    pub sprinting: bool,  // Actually sprinting this frame, not just holding the key
+    pub water: WaterState,
+    pub state: MovementState,
+    pub eye_height: f32, // Current eye height, eased between standing and crouching
+}
+
+// Controller state machine; transitions live in abilities.rs
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum MovementState {
+    #[default]
+    Walking,
+    Crouching,
+    Sliding { elapsed: f32 },
+    Mantling { from: Vec3, to: Vec3, elapsed: f32 }, // Controller is paused while climbing
+}
+
+impl MovementState {
+    pub fn is_low(self) -> bool {
+        matches!(self, MovementState::Crouching | MovementState::Sliding { .. })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum WaterState {
+    #[default]
+    Dry,
+    Wading,   // Feet under water but still standing on the bottom
+    Swimming, // Deep enough to float; gravity is replaced by buoyancy
+}
+
+#[derive(Component)]
+pub struct Stamina {
+    pub current: f32,
+    pub max: f32,
+    pub regen_cooldown: f32, // Time left before regeneration starts
+    pub exhausted: bool,     // Ran dry; sprint is blocked until it refills past the resume point
+}
+
+impl Stamina {
+    pub fn new(max: f32) -> Self {
+        Self {
+            current: max,
+            max,
+            regen_cooldown: 0.0,
+            exhausted: false,
+        }
+    }
+
+    pub fn fraction(&self) -> f32 {
+        self.current / self.max
+    }
+
+    pub fn spend(&mut self, amount: f32, settings: &PlayerSettings) {
+        self.current = (self.current - amount).max(0.0);
+        self.regen_cooldown = settings.regen_delay;
+        if self.current <= 0.0 {
+            self.exhausted = true;
+        }
+    }
+
+    fn regenerate(&mut self, dt: f32, settings: &PlayerSettings) {
+        if self.regen_cooldown > 0.0 {
+            self.regen_cooldown -= dt;
+            return;
+        }
+        self.current = (self.current + settings.stamina_regen * dt).min(self.max);
+        if self.exhausted && self.fraction() >= settings.sprint_resume {
+            self.exhausted = false;
+        }
+    }
+}
+
+// Camera rig parented to the body. Pitch, head bob and shake are kept as separate
+// values and the rig's local transform is rebuilt from them every frame.
+#[derive(Component, Default)]
+pub struct PlayerCamera {
+    pub pitch: f32,
+    pub bob_phase: f32,
+    pub bob_offset: Vec3,
+    pub shake_offset: Vec3,
+    pub follow_distance: f32, // Current third-person distance after collision
+    pub orbit_focus: Vec3,
+    pub orbit_yaw: f32,
+    pub orbit_pitch: f32,
+    pub orbit_distance: f32,
 }
 
-fn setup_player(mut commands: Commands) {
-    // Spawn camera as player - start higher above ground
-    commands.spawn((
-        Camera3dBundle {
-            transform: Transform::from_xyz(0.0, 5.0, 0.0).looking_at(Vec3::new(0.0, 4.0, 5.0), Vec3::Y),
-            ..default()
-        },
-        Player {
-            velocity: Vec3::ZERO,
-            on_ground: true,
-        },
-        controls::Velocity {
-            linear: Vec3::ZERO,
-            damping: 0.9,
-        },
-    ));
+fn setup_player(mut commands: Commands, settings: Res<PlayerSettings>) {
+    // Start a little above the ground and drop onto it
+    commands
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_xyz(0.0, 2.5, 0.0).looking_to(Vec3::Z, Vec3::Y)),
+            Player {
+                velocity: Vec3::ZERO,
+                on_ground: false,
+                air_time: 0.0,
+                jump_buffer: 0.0,
+                sprinting: false,
+                water: WaterState::Dry,
+                state: MovementState::Walking,
+                eye_height: settings.eye_height,
+            },
+            Stamina::new(settings.stamina_max),
+        ))
+        .with_children(|body| {
+            body.spawn((
+                Camera3dBundle {
+                    transform: Transform::from_xyz(0.0, settings.eye_height, 0.0),
+                    ..default()
+                },
+                PlayerCamera {
+                    pitch: -0.2,
+                    ..default()
+                },
+            ));
+        });
 }
 
-fn player_movement(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+// Kinematic character controller: input, friction, gravity, jumping, integration,
+// collision and ground handling all happen here in one step
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn player_movement(
+    actions: Res<ActionState>,
     time: Res<Time>,
-    mut player_query: Query<(&mut Transform, &mut controls::Velocity), With<Player>>,
+    mut player_query: Query<(&mut Transform, &mut Player, &mut Stamina, Option<&Health>)>,
     settings: Res<PlayerSettings>,
     game_settings: Res<crate::settings::GameSettings>,
+    terrain: TerrainQuery,
+    index: Res<SpatialIndex>,
+    camera_mode: Res<CameraMode>,
+    noclip: Res<Noclip>,
+    mut damage_events: EventWriter<DamageEvent>,
 ) {
-    if let Ok((mut transform, mut velocity)) = player_query.get_single_mut() {
-        let mut movement_direction = Vec3::ZERO;
+    if noclip.enabled {
+        return;
+    }
+    let Ok((mut transform, mut player, mut stamina, health)) = player_query.get_single_mut() else {
+        return;
+    };
+    if matches!(player.state, MovementState::Mantling { .. }) || health.is_some_and(Health::is_dead) {
+        return;
+    }
+    let dt = time.delta_seconds();
+    if dt <= 0.0 {
+        return;
+    }
 
-        // Forward/backward
-        if keyboard_input.pressed(KeyCode::KeyW) {
-            movement_direction += Vec3::from(transform.forward());
-        }
-        if keyboard_input.pressed(KeyCode::KeyS) {
-            movement_direction -= Vec3::from(transform.forward());
+    // Movement input on the horizontal plane, independent of camera pitch
+    let forward = transform.forward().with_y(0.0).normalize_or_zero();
+    let right = transform.right().with_y(0.0).normalize_or_zero();
+    let input = actions.movement();
+    let wish = forward * input.y + right * input.x;
+    // The body stays put while the orbit camera is looking around
+    let wish = if *camera_mode == CameraMode::Orbit { Vec3::ZERO } else { wish.clamp_length_max(1.0) };
+
+    // How deep the feet are decides between walking, wading and swimming
+    let depth = terrain.water_level() - transform.translation.y;
+    player.water = if depth >= settings.swim_depth {
+        WaterState::Swimming
+    } else if depth > settings.wade_depth {
+        WaterState::Wading
+    } else {
+        WaterState::Dry
+    };
+
+    // Sprinting needs stamina, movement input, dry ground under the feet
+    player.sprinting = actions.pressed(Action::Sprint)
+        && !stamina.exhausted
+        && wish != Vec3::ZERO
+        && player.on_ground
+        && player.water == WaterState::Dry
+        && player.state == MovementState::Walking;
+    if player.sprinting {
+        stamina.spend(settings.sprint_drain * dt, &settings);
+    } else {
+        stamina.regenerate(dt, &settings);
+    }
+    let target = match player.water {
+        WaterState::Swimming => wish * settings.swim_speed,
+        WaterState::Wading => wish * settings.move_speed * settings.wade_speed_multiplier,
+        WaterState::Dry if player.state.is_low() => wish * settings.move_speed * settings.crouch_speed_multiplier,
+        WaterState::Dry if player.sprinting => wish * settings.move_speed * game_settings.sprint_speed_multiplier,
+        WaterState::Dry => wish * settings.move_speed,
+    };
+
+    // Accelerate towards the input, or let friction bleed speed off. Both are
+    // exponential in time so the result does not depend on the frame rate.
+    let (acceleration, friction) = if player.water == WaterState::Swimming {
+        (settings.swim_acceleration, settings.water_drag)
+    } else if player.on_ground {
+        (settings.ground_acceleration, settings.ground_friction)
+    } else {
+        (settings.air_acceleration, settings.air_friction)
+    };
+    let mut horizontal = player.velocity.xz();
+    if let MovementState::Sliding { .. } = player.state {
+        // Momentum carries the slide; slopes speed it up or slow it down
+        let normal = terrain.normal(transform.translation.x, transform.translation.z);
+        horizontal *= (-settings.slide_friction * dt).exp();
+        horizontal += normal.xz() * settings.gravity * dt;
+    } else if wish != Vec3::ZERO {
+        horizontal = horizontal.lerp(target.xz(), 1.0 - (-acceleration * dt).exp());
+    } else {
+        horizontal *= (-friction * dt).exp();
+        if horizontal.length() < 0.01 {
+            horizontal = Vec2::ZERO;
         }
+    }
+    player.velocity.x = horizontal.x;
+    player.velocity.z = horizontal.y;
 
-        // Left/right
-        if keyboard_input.pressed(KeyCode::KeyA) {
-            movement_direction -= Vec3::from(transform.right());
+    // Jumping with coyote time and buffering
+    if actions.just_pressed(Action::Jump) {
+        player.jump_buffer = settings.jump_buffer;
+    } else {
+        player.jump_buffer = (player.jump_buffer - dt).max(0.0);
+    }
+    let mut jumped = false;
+    let can_jump = player.water != WaterState::Swimming && (player.on_ground || player.air_time <= settings.coyote_time);
+    if player.jump_buffer > 0.0 && can_jump {
+        // Water around the legs holds the player back
+        let multiplier = if player.water == WaterState::Wading { settings.wade_jump_multiplier } else { 1.0 };
+        player.velocity.y = settings.jump_force * multiplier;
+        player.jump_buffer = 0.0;
+        player.air_time = settings.coyote_time + 1.0; // No second jump from the same ledge
+        player.on_ground = false;
+        stamina.spend(settings.jump_cost, &settings);
+        jumped = true;
+    }
+
+    if player.water == WaterState::Swimming {
+        // Buoyancy grows with depth and balances gravity at the float depth, so the
+        // player bobs with their head above water; Jump and Descend swim up and down
+        let submersion = (depth / settings.float_depth).min(1.2);
+        let mut vertical = settings.gravity * (submersion - 1.0);
+        if actions.pressed(Action::Jump) {
+            vertical += settings.swim_vertical_speed;
         }
-        if keyboard_input.pressed(KeyCode::KeyD) {
-            movement_direction += Vec3::from(transform.right());
+        if actions.pressed(Action::Descend) {
+            vertical -= settings.swim_vertical_speed;
         }
+        player.velocity.y = (player.velocity.y + vertical * dt) * (-settings.water_drag * dt).exp();
+        player.on_ground = false;
+    } else if !player.on_ground {
+        player.velocity.y -= settings.gravity * dt;
+    }
+
+    // Integrate, then resolve against trees and props
+    let was_on_ground = player.on_ground;
+    let start = transform.translation;
+    let mut position = start + player.velocity * dt;
+    // Taken before contacts and the ground clamp cancel the fall
+    let impact_speed = -player.velocity.y;
+    let (mut correction, mut normals) = resolve(&index, &settings, position, player.eye_height);
 
-        // Normalize direction
-        if movement_direction.length() > 0.0 {
-            movement_direction = movement_direction.normalize();
-            
-            // Check for sprint
-            let is_sprinting = keyboard_input.pressed(KeyCode::ShiftLeft) || keyboard_input.pressed(KeyCode::ShiftRight);
-            let speed_multiplier = if is_sprinting { game_settings.sprint_speed_multiplier } else { 1.0 };
-            let target_speed = settings.move_speed * speed_multiplier;
-            
-            // Calculate target velocity
-            let target_velocity = movement_direction * target_speed;
-            
-            // Smoothly accelerate towards target velocity
-            let acceleration = 20.0;
-            velocity.linear.x = velocity.linear.x.lerp(target_velocity.x, acceleration * time.delta_seconds());
-            velocity.linear.z = velocity.linear.z.lerp(target_velocity.z, acceleration * time.delta_seconds());
-        } else {
-            // Decelerate when not moving
-            velocity.linear.x *= velocity.damping;
-            velocity.linear.z *= velocity.damping;
+    // Step-up: if blocked while walking, retry the move lifted by the step height
+    let blocked = correction.xz().length() > 1e-3;
+    if was_on_ground && blocked && !jumped {
+        let lifted = position + Vec3::Y * settings.step_height;
+        let (lifted_correction, lifted_normals) = resolve(&index, &settings, lifted, player.eye_height);
+        if lifted_correction.xz().length() < correction.xz().length() * 0.5 {
+            position = lifted;
+            correction = lifted_correction;
+            normals = lifted_normals;
         }
+    }
+    position += correction;
 
-        // Ground following - query terrain height using noise (same as terrain generation)
-        use noise::{NoiseFn, Perlin};
-        let noise = Perlin::new(12345); // Same seed as terrain
-        let terrain_height = noise.get([transform.translation.x as f64 * 0.1, transform.translation.z as f64 * 0.1]) as f32 * 5.0;
-        let min_height = terrain_height + 2.5; // Keep player 2.5 units above terrain
-        
-        // Apply gravity if above ground
-        if transform.translation.y > min_height {
-            velocity.linear.y -= 9.8 * time.delta_seconds();
+    // Contacts facing up count as ground; everything else slides the velocity along it
+    let walkable = settings.max_slope.to_radians().cos();
+    let mut grounded = false;
+    for normal in normals {
+        if normal.y >= walkable {
+            grounded = true;
+        }
+        let into = player.velocity.dot(normal);
+        if into < 0.0 {
+            player.velocity -= normal * into;
         }
-        
-        if transform.translation.y < min_height {
-            transform.translation.y = min_height;
-            velocity.linear.y = 0.0; // Stop falling when on ground
+    }
+
+    // Terrain contact
+    let ground = terrain.height(position.x, position.z);
+    let feet = position.y;
+    let snapping = was_on_ground && !jumped && feet - ground <= settings.ground_snap + settings.step_height * 0.5;
+    if feet <= ground || (snapping && !grounded) {
+        let normal = terrain.normal(position.x, position.z);
+        if normal.y >= walkable {
+            position.y = ground;
+            grounded = true;
+        } else if feet <= ground {
+            // Too steep to stand on: keep out of the ground and slide downhill
+            position.y = ground;
+            let into = player.velocity.dot(normal);
+            if into < 0.0 {
+                player.velocity -= normal * into;
+            }
+            let downhill = Vec3::new(normal.x, 0.0, normal.z).normalize_or_zero();
+            player.velocity += downhill * settings.gravity * (1.0 - normal.y) * dt;
+        }
+    }
+
+    // A jump always leaves the ground, even if a contact was found this frame
+    let grounded = grounded && !jumped;
+    if grounded {
+        if !was_on_ground && impact_speed > settings.fall_damage_speed {
+            damage_events.send(DamageEvent {
+                amount: (impact_speed - settings.fall_damage_speed) * settings.fall_damage_scale,
+                source: DamageSource::Fall,
+            });
         }
+        player.velocity.y = 0.0;
+        player.air_time = 0.0;
+    } else {
+        player.air_time += dt;
     }
+    player.on_ground = grounded;
+    transform.translation = position;
 }
 
+// Push the player's capsule, standing at the given feet position, out of nearby colliders
+pub(crate) fn resolve(index: &SpatialIndex, settings: &PlayerSettings, feet: Vec3, eye_height: f32) -> (Vec3, Vec<Vec3>) {
+    let bottom = feet + Vec3::Y * settings.collision_radius;
+    let top = feet + Vec3::Y * (eye_height + 0.1);
+    index.resolve_capsule(bottom, top, settings.collision_radius)
+}
+
+// Yaw turns the body, pitch only tilts the camera rig. The orbit camera has its own angles.
+// Mouse and right stick arrive already combined and scaled through the action state.
 fn mouse_look(
     mut player_query: Query<&mut Transform, With<Player>>,
-    mut mouse_motion_events: EventReader<bevy::input::mouse::MouseMotion>,
-    _settings: Res<PlayerSettings>,
-    game_settings: Res<crate::settings::GameSettings>,
+    mut camera_query: Query<&mut PlayerCamera>,
+    actions: Res<ActionState>,
+    camera_mode: Res<CameraMode>,
 ) {
-    let mut delta = Vec2::ZERO;
-    for event in mouse_motion_events.read() {
-        delta += event.delta;
-    }
-
-        if delta.length() > 0.0 {
-        if let Ok(mut transform) = player_query.get_single_mut() {
-            // Use game settings sensitivity if available, otherwise fall back to player settings
-            let sensitivity = game_settings.mouse_sensitivity;
-            
-            // Horizontal rotation (yaw)
-            let yaw = -delta.x * sensitivity;
-            transform.rotate_y(yaw);
-
-            // Vertical rotation (pitch) - limited to prevent flipping (-89 to 89 degrees)
-            let pitch = -delta.y * sensitivity;
-            let (yaw_angle, current_pitch, _) = transform.rotation.to_euler(EulerRot::YXZ);
-            let new_pitch = (current_pitch + pitch).clamp(-1.4, 1.4);
-            
-            transform.rotation = Quat::from_euler(EulerRot::YXZ, yaw_angle, new_pitch, 0.0);
-        }
+    let look = actions.look();
+    if look == Vec2::ZERO {
+        return;
+    }
+    let Ok(mut camera) = camera_query.get_single_mut() else {
+        return;
+    };
+    if *camera_mode == CameraMode::Orbit {
+        camera.orbit_yaw -= look.x;
+        camera.orbit_pitch = (camera.orbit_pitch + look.y).clamp(-1.4, 1.4);
+        return;
+    }
+    if let Ok(mut transform) = player_query.get_single_mut() {
+        transform.rotate_y(-look.x);
     }
+    // Limited to prevent flipping
+    camera.pitch = (camera.pitch + look.y).clamp(-1.4, 1.4);
 }
 
 fn handle_escape_key(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
     mut windows: Query<&mut Window, With<PrimaryWindow>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Escape) {
+    if actions.just_pressed(Action::Pause) {
         if let Ok(mut window) = windows.get_single_mut() {
             let is_locked = matches!(window.cursor.grab_mode, bevy::window::CursorGrabMode::Locked);
             window.cursor.visible = is_locked;
@@ -0,0 +1,486 @@
+use bevy::core_pipeline::core_3d::Transparent3d;
+use bevy::ecs::system::lifetimeless::SRes;
+use bevy::ecs::system::SystemParamItem;
+use bevy::pbr::{
+    MeshPipeline, MeshPipelineKey, NotShadowCaster, NotShadowReceiver, RenderMeshInstances, SetMeshBindGroup,
+    SetMeshViewBindGroup,
+};
+use bevy::prelude::*;
+use bevy::render::mesh::{GpuBufferInfo, GpuMesh, MeshVertexBufferLayoutRef};
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_phase::{
+    AddRenderCommand, DrawFunctions, PhaseItem, PhaseItemExtraIndex, RenderCommand, RenderCommandResult,
+    SetItemPipeline, TrackedRenderPass, ViewSortedRenderPhases,
+};
+use bevy::render::render_resource::{
+    AsBindGroup, BindGroup, BindGroupLayout, Buffer, BufferInitDescriptor, BufferUsages, PipelineCache,
+    RenderPipelineDescriptor, SpecializedMeshPipeline, SpecializedMeshPipelineError, SpecializedMeshPipelines,
+    VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode,
+};
+use bevy::render::renderer::RenderDevice;
+use bevy::render::texture::{FallbackImage, GpuImage};
+use bevy::render::view::{ExtractedView, NoFrustumCulling, VisibleEntities, WithMesh};
+use bevy::render::{Extract, ExtractSchedule, Render, RenderApp, RenderSet};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::player::PlayerCamera;
+use crate::terrain::TerrainConfig;
+use crate::weather::{self, Weather, WeatherSettings};
+use crate::wind::Wind;
+
+pub struct PrecipitationPlugin;
+
+impl Plugin for PrecipitationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_precipitation)
+            .add_systems(Update, update_precipitation.after(weather::update_weather));
+
+        // Headless runs have no renderer
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<PrecipitationInstanceBuffers>()
+            .init_resource::<SpecializedMeshPipelines<PrecipitationPipeline>>()
+            .add_render_command::<Transparent3d, DrawPrecipitation>()
+            .add_systems(ExtractSchedule, extract_precipitation)
+            .add_systems(
+                Render,
+                (
+                    queue_precipitation.in_set(RenderSet::QueueMeshes),
+                    prepare_precipitation_buffers.in_set(RenderSet::PrepareResources),
+                    prepare_precipitation_bind_group.in_set(RenderSet::PrepareBindGroups),
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.init_resource::<PrecipitationPipeline>();
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum PrecipitationKind {
+    Rain,
+    Snow,
+}
+
+// Uniforms shared by rain and snow, written once a frame. Particles only store where they
+// started; the vertex shader moves them by how far the shower has fallen and wraps them
+// round inside a box that follows the camera.
+#[derive(Resource, AsBindGroup, Clone)]
+pub struct PrecipitationMaterial {
+    #[uniform(0)]
+    pub camera: Vec4, // xyz camera position, w snow sway clock
+    #[uniform(0)]
+    pub size: Vec4, // xyz size of the box of particles
+    #[uniform(0)]
+    pub rain_drift: Vec4, // xyz distance rain has fallen and blown, wrapped to the box
+    #[uniform(0)]
+    pub rain_velocity: Vec4, // Streaks lean along it
+    #[uniform(0)]
+    pub snow_drift: Vec4,
+    #[uniform(0)]
+    pub rain_color: Vec4, // Linear
+    #[uniform(0)]
+    pub snow_color: Vec4,
+}
+
+// Every raindrop or every snowflake, drawn as instances of one mesh
+#[derive(Component, Clone)]
+pub struct Precipitation {
+    kind: PrecipitationKind,
+    particles: Arc<Vec<Vec4>>, // xyz start in the box, w sway phase
+    shown: u32,                // Particles are shown in order, so intensity picks how many
+}
+
+fn setup_precipitation(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    settings: Res<WeatherSettings>,
+    config: Res<TerrainConfig>,
+) {
+    commands.insert_resource(PrecipitationMaterial {
+        camera: Vec4::ZERO,
+        size: Vec4::ZERO,
+        rain_drift: Vec4::ZERO,
+        rain_velocity: Vec4::NEG_Y,
+        snow_drift: Vec4::ZERO,
+        rain_color: Color::srgba(0.7, 0.75, 0.85, 0.35).to_linear().to_vec4(),
+        snow_color: Color::srgba(1.0, 1.0, 1.0, 0.9).to_linear().to_vec4(),
+    });
+
+    let mut rng = StdRng::seed_from_u64(config.seed as u64);
+    let (radius, height) = (settings.precipitation_radius, settings.precipitation_height);
+    let sets = [
+        (PrecipitationKind::Rain, settings.raindrops, meshes.add(Cuboid::new(0.012, 0.45, 0.012))),
+        (PrecipitationKind::Snow, settings.snowflakes, meshes.add(Sphere::new(0.03).mesh().ico(0).unwrap())),
+    ];
+    for (kind, count, mesh) in sets {
+        let particles = (0..count)
+            .map(|_| {
+                Vec4::new(
+                    rng.gen_range(-radius..radius),
+                    rng.gen_range(-height * 0.5..height * 0.5),
+                    rng.gen_range(-radius..radius),
+                    rng.gen_range(0.0..std::f32::consts::TAU),
+                )
+            })
+            .collect();
+        commands.spawn((
+            mesh,
+            SpatialBundle {
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            Precipitation {
+                kind,
+                particles: Arc::new(particles),
+                shown: 0,
+            },
+            // The particles are placed by the shader, so the mesh's own bounds mean nothing
+            NoFrustumCulling,
+            NotShadowCaster,
+            NotShadowReceiver,
+        ));
+    }
+}
+
+// Move the shower with gravity and wind and pick how many particles fall. Components are
+// only written when they change, so clear weather leaves the particles alone.
+fn update_precipitation(
+    time: Res<Time>,
+    settings: Res<WeatherSettings>,
+    weather: Res<Weather>,
+    wind: Res<Wind>,
+    mut material: ResMut<PrecipitationMaterial>,
+    camera_query: Query<&GlobalTransform, With<PlayerCamera>>,
+    mut particle_query: Query<(&mut Precipitation, &mut Transform, &mut Visibility)>,
+) {
+    let Ok(camera) = camera_query.get_single() else {
+        return;
+    };
+    let effects = weather.effects();
+    let center = camera.translation();
+    let dt = time.delta_seconds();
+    let size = Vec3::new(
+        settings.precipitation_radius * 2.0,
+        settings.precipitation_height,
+        settings.precipitation_radius * 2.0,
+    );
+    let push = Vec3::new(wind.direction.x, 0.0, wind.direction.y) * wind.strength;
+    let rain_velocity = Vec3::new(0.0, -14.0, 0.0) + push * 5.0;
+    let snow_velocity = Vec3::new(0.0, -1.2, 0.0) + push * 1.5;
+    let shown = |count: usize, intensity: f32| (count as f32 * intensity.clamp(0.0, 1.0)) as u32;
+
+    for (mut precipitation, mut transform, mut visibility) in particle_query.iter_mut() {
+        let count = precipitation.particles.len();
+        let (shown, velocity, drift) = match precipitation.kind {
+            PrecipitationKind::Rain => (shown(count, effects.rain), rain_velocity, &mut material.rain_drift),
+            PrecipitationKind::Snow => (shown(count, effects.snow), snow_velocity, &mut material.snow_drift),
+        };
+        // Wrapped to the box, which the shader does anyway, so it never loses precision
+        *drift = (drift.truncate() + velocity * dt).rem_euclid(size).extend(0.0);
+        if precipitation.shown != shown {
+            precipitation.shown = shown;
+        }
+        visibility.set_if_neq(if shown > 0 { Visibility::Inherited } else { Visibility::Hidden });
+        // Kept on the camera so it sorts in front of the water
+        if shown > 0 {
+            transform.translation = center;
+        }
+    }
+
+    material.camera = center.extend(time.elapsed_seconds_wrapped());
+    material.size = size.extend(0.0);
+    material.rain_velocity = rain_velocity.extend(0.0);
+}
+
+// Render world: rain and snow are one instanced draw each in the transparent pass, with
+// the particles in a vertex buffer stepped per instance
+
+fn extract_precipitation(
+    mut commands: Commands,
+    material: Extract<Option<Res<PrecipitationMaterial>>>,
+    particle_query: Extract<Query<(Entity, &Precipitation)>>,
+) {
+    if let Some(material) = material.as_ref() {
+        commands.insert_resource(PrecipitationMaterial::clone(material));
+    }
+    let sets: Vec<_> = particle_query
+        .iter()
+        .map(|(entity, precipitation)| (entity, precipitation.clone()))
+        .collect();
+    commands.insert_or_spawn_batch(sets);
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct PrecipitationPipelineKey {
+    mesh: MeshPipelineKey,
+    kind: PrecipitationKind,
+}
+
+#[derive(Resource)]
+struct PrecipitationPipeline {
+    shader: Handle<Shader>,
+    mesh_pipeline: MeshPipeline,
+    material_layout: BindGroupLayout,
+}
+
+impl FromWorld for PrecipitationPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let material_layout = PrecipitationMaterial::bind_group_layout(world.resource::<RenderDevice>());
+        Self {
+            shader: world.load_asset("shaders/precipitation.wgsl"),
+            mesh_pipeline: world.resource::<MeshPipeline>().clone(),
+            material_layout,
+        }
+    }
+}
+
+impl SpecializedMeshPipeline for PrecipitationPipeline {
+    type Key = PrecipitationPipelineKey;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayoutRef,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let mut descriptor = self.mesh_pipeline.specialize(key.mesh, layout)?;
+        descriptor.label = Some("precipitation_pipeline".into());
+        descriptor.vertex.shader = self.shader.clone();
+        descriptor.vertex.buffers.push(VertexBufferLayout {
+            array_stride: VertexFormat::Float32x4.size(),
+            step_mode: VertexStepMode::Instance,
+            attributes: vec![
+                // Starting point in the box and sway phase
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 0,
+                    shader_location: 3,
+                },
+            ],
+        });
+        descriptor.layout.push(self.material_layout.clone());
+        if key.kind == PrecipitationKind::Snow {
+            descriptor.vertex.shader_defs.push("SNOW".into());
+        }
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            fragment.shader = self.shader.clone();
+            if key.kind == PrecipitationKind::Snow {
+                fragment.shader_defs.push("SNOW".into());
+            }
+        }
+        Ok(descriptor)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn queue_precipitation(
+    draw_functions: Res<DrawFunctions<Transparent3d>>,
+    precipitation_pipeline: Res<PrecipitationPipeline>,
+    msaa: Res<Msaa>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<PrecipitationPipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    meshes: Res<RenderAssets<GpuMesh>>,
+    mesh_instances: Res<RenderMeshInstances>,
+    bind_group: Option<Res<PrecipitationBindGroup>>,
+    particle_query: Query<&Precipitation>,
+    mut transparent_phases: ResMut<ViewSortedRenderPhases<Transparent3d>>,
+    views: Query<(Entity, &ExtractedView, &VisibleEntities)>,
+) {
+    // Nothing to draw with until the uniforms have made it to the GPU
+    if bind_group.is_none() {
+        return;
+    }
+    let draw_precipitation = draw_functions.read().id::<DrawPrecipitation>();
+    let msaa_key = MeshPipelineKey::from_msaa_samples(msaa.samples()) | MeshPipelineKey::BLEND_ALPHA;
+
+    for (view_entity, view, visible_entities) in &views {
+        let Some(transparent_phase) = transparent_phases.get_mut(&view_entity) else {
+            continue;
+        };
+        let view_key = msaa_key | MeshPipelineKey::from_hdr(view.hdr);
+        let rangefinder = view.rangefinder3d();
+
+        for &entity in visible_entities.iter::<WithMesh>() {
+            let Ok(precipitation) = particle_query.get(entity) else {
+                continue;
+            };
+            let Some(mesh_instance) = mesh_instances.render_mesh_queue_data(entity) else {
+                continue;
+            };
+            let Some(mesh) = meshes.get(mesh_instance.mesh_asset_id) else {
+                continue;
+            };
+            let key = PrecipitationPipelineKey {
+                mesh: view_key | MeshPipelineKey::from_primitive_topology(mesh.primitive_topology()),
+                kind: precipitation.kind,
+            };
+            let pipeline = match pipelines.specialize(&pipeline_cache, &precipitation_pipeline, key, &mesh.layout) {
+                Ok(pipeline) => pipeline,
+                Err(err) => {
+                    error!("Precipitation pipeline: {err}");
+                    continue;
+                }
+            };
+            transparent_phase.add(Transparent3d {
+                entity,
+                draw_function: draw_precipitation,
+                pipeline,
+                distance: rangefinder.distance_translation(&mesh_instance.translation),
+                batch_range: 0..1,
+                extra_index: PhaseItemExtraIndex::NONE,
+            });
+        }
+    }
+}
+
+struct PrecipitationInstanceBuffer {
+    particles: Arc<Vec<Vec4>>, // What the buffer was filled from
+    buffer: Buffer,
+    shown: u32,
+}
+
+// Instance buffers by kind, filled once; only the number of instances drawn changes
+#[derive(Resource, Default)]
+struct PrecipitationInstanceBuffers(HashMap<Entity, PrecipitationInstanceBuffer>);
+
+fn prepare_precipitation_buffers(
+    mut buffers: ResMut<PrecipitationInstanceBuffers>,
+    render_device: Res<RenderDevice>,
+    particle_query: Query<(Entity, &Precipitation)>,
+) {
+    buffers.0.retain(|entity, _| particle_query.contains(*entity));
+
+    for (entity, precipitation) in &particle_query {
+        if let Some(existing) = buffers.0.get_mut(&entity)
+            && Arc::ptr_eq(&existing.particles, &precipitation.particles)
+        {
+            existing.shown = precipitation.shown;
+            continue;
+        }
+        let contents: Vec<u8> = precipitation
+            .particles
+            .iter()
+            .flat_map(|particle| particle.to_array())
+            .flat_map(f32::to_le_bytes)
+            .collect();
+        let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("precipitation_particles"),
+            contents: &contents,
+            usage: BufferUsages::VERTEX,
+        });
+        buffers.0.insert(
+            entity,
+            PrecipitationInstanceBuffer {
+                particles: precipitation.particles.clone(),
+                buffer,
+                shown: precipitation.shown,
+            },
+        );
+    }
+}
+
+#[derive(Resource)]
+struct PrecipitationBindGroup(BindGroup);
+
+fn prepare_precipitation_bind_group(
+    mut commands: Commands,
+    precipitation_pipeline: Res<PrecipitationPipeline>,
+    material: Option<Res<PrecipitationMaterial>>,
+    render_device: Res<RenderDevice>,
+    images: Res<RenderAssets<GpuImage>>,
+    fallback_image: Res<FallbackImage>,
+) {
+    let Some(material) = material else {
+        return;
+    };
+    if let Ok(prepared) = material.as_bind_group(
+        &precipitation_pipeline.material_layout,
+        &render_device,
+        &images,
+        &fallback_image,
+    ) {
+        commands.insert_resource(PrecipitationBindGroup(prepared.bind_group));
+    }
+}
+
+type DrawPrecipitation = (
+    SetItemPipeline,
+    SetMeshViewBindGroup<0>,
+    SetMeshBindGroup<1>,
+    SetPrecipitationBindGroup<2>,
+    DrawParticles,
+);
+
+struct SetPrecipitationBindGroup<const I: usize>;
+
+impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetPrecipitationBindGroup<I> {
+    type Param = SRes<PrecipitationBindGroup>;
+    type ViewQuery = ();
+    type ItemQuery = ();
+
+    fn render<'w>(
+        _item: &P,
+        _view: (),
+        _entity: Option<()>,
+        bind_group: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        pass.set_bind_group(I, &bind_group.into_inner().0, &[]);
+        RenderCommandResult::Success
+    }
+}
+
+struct DrawParticles;
+
+impl<P: PhaseItem> RenderCommand<P> for DrawParticles {
+    type Param = (
+        SRes<RenderAssets<GpuMesh>>,
+        SRes<RenderMeshInstances>,
+        SRes<PrecipitationInstanceBuffers>,
+    );
+    type ViewQuery = ();
+    type ItemQuery = ();
+
+    fn render<'w>(
+        item: &P,
+        _view: (),
+        _entity: Option<()>,
+        (meshes, mesh_instances, buffers): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(mesh_instance) = mesh_instances.render_mesh_queue_data(item.entity()) else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(gpu_mesh) = meshes.into_inner().get(mesh_instance.mesh_asset_id) else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(particles) = buffers.into_inner().0.get(&item.entity()) else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
+        pass.set_vertex_buffer(1, particles.buffer.slice(..));
+        match &gpu_mesh.buffer_info {
+            GpuBufferInfo::Indexed {
+                buffer,
+                index_format,
+                count,
+            } => {
+                pass.set_index_buffer(buffer.slice(..), 0, *index_format);
+                pass.draw_indexed(0..*count, 0, 0..particles.shown);
+            }
+            GpuBufferInfo::NonIndexed => {
+                pass.draw(0..gpu_mesh.vertex_count, 0..particles.shown);
+            }
+        }
+        RenderCommandResult::Success
+    }
+}
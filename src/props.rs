@@ -0,0 +1,400 @@
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+use noise::{NoiseFn, Perlin};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::collision::Collider;
+use crate::placement::{self, PlacementRules};
+use crate::terrain::{Biome, TerrainChunk, TerrainQuery};
+
+pub struct PropsPlugin;
+
+impl Plugin for PropsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScatterLayers>()
+            .add_systems(Startup, build_prop_variants)
+            .add_systems(Update, spawn_chunk_props);
+    }
+}
+
+// Where the meshes of a layer come from
+pub enum PropMesh {
+    Generated(fn(&mut StdRng) -> Mesh), // Called once per variant with a seeded rng
+    #[allow(dead_code)] // None of the built-in layers use an asset yet
+    Asset(&'static str), // Loaded through the asset server, e.g. "models/stump.glb#Mesh0/Primitive0"
+}
+
+// Collision shape fitted to each generated variant's bounds
+#[derive(Clone, Copy, PartialEq)]
+pub enum PropCollider {
+    Sphere,
+    Box,
+}
+
+// One kind of forest-floor prop, instantiated per terrain chunk
+pub struct ScatterLayer {
+    pub name: &'static str,
+    pub mesh: PropMesh,
+    pub variants: usize, // Distinct meshes and materials shared by all instances
+    pub base_color: Color,
+    pub color_jitter: f32,
+    pub roughness: f32,
+    pub double_sided: bool,
+    pub density_noise: Perlin,
+    pub density_scale: f64,
+    pub density_threshold: f32, // Points are kept where the density noise is above this
+    pub placement: PlacementRules,
+    pub biomes: Vec<Biome>,
+    pub scale: (f32, f32),
+    pub tilt_jitter: f32, // Max random tilt in radians; yaw is always fully random
+    pub sink: f32,        // How far the prop is pushed into the ground, relative to its scale
+    pub collider: Option<PropCollider>, // None lets the player walk through
+}
+
+#[derive(Resource)]
+pub struct ScatterLayers {
+    pub layers: Vec<ScatterLayer>,
+    pub meshes: Vec<Vec<Handle<Mesh>>>, // Per layer, filled at startup
+    pub materials: Vec<Vec<Handle<StandardMaterial>>>,
+    pub colliders: Vec<Vec<Option<Collider>>>,
+}
+
+impl Default for ScatterLayers {
+    fn default() -> Self {
+        Self {
+            layers: vec![
+                ScatterLayer {
+                    name: "rocks",
+                    mesh: PropMesh::Generated(rock_mesh),
+                    variants: 6,
+                    base_color: Color::srgb(0.48, 0.47, 0.45),
+                    color_jitter: 0.06,
+                    roughness: 0.9,
+                    double_sided: false,
+                    density_noise: Perlin::new(1001),
+                    density_scale: 0.05,
+                    density_threshold: -0.1,
+                    placement: PlacementRules {
+                        min_spacing: 5.0,
+                        max_slope: 60.0,
+                        ..default()
+                    },
+                    biomes: vec![Biome::Meadow, Biome::Forest, Biome::Rock],
+                    scale: (0.3, 1.1),
+                    tilt_jitter: 0.4,
+                    sink: 0.2,
+                    collider: Some(PropCollider::Sphere),
+                },
+                ScatterLayer {
+                    name: "bushes",
+                    mesh: PropMesh::Generated(bush_mesh),
+                    variants: 4,
+                    base_color: Color::srgb(0.14, 0.38, 0.12),
+                    color_jitter: 0.05,
+                    roughness: 0.8,
+                    double_sided: false,
+                    density_noise: Perlin::new(1002),
+                    density_scale: 0.08,
+                    density_threshold: 0.1,
+                    placement: PlacementRules {
+                        min_spacing: 3.5,
+                        max_slope: 30.0,
+                        ..default()
+                    },
+                    biomes: vec![Biome::Meadow, Biome::Forest],
+                    scale: (0.6, 1.3),
+                    tilt_jitter: 0.1,
+                    sink: 0.05,
+                    collider: None,
+                },
+                ScatterLayer {
+                    name: "ferns",
+                    mesh: PropMesh::Generated(fern_mesh),
+                    variants: 4,
+                    base_color: Color::srgb(0.2, 0.45, 0.15),
+                    color_jitter: 0.05,
+                    roughness: 0.7,
+                    double_sided: true,
+                    density_noise: Perlin::new(1003),
+                    density_scale: 0.1,
+                    density_threshold: 0.15,
+                    placement: PlacementRules {
+                        min_spacing: 2.5,
+                        max_slope: 35.0,
+                        ..default()
+                    },
+                    biomes: vec![Biome::Forest],
+                    scale: (0.5, 1.1),
+                    tilt_jitter: 0.15,
+                    sink: 0.0,
+                    collider: None,
+                },
+                ScatterLayer {
+                    name: "fallen logs",
+                    mesh: PropMesh::Generated(log_mesh),
+                    variants: 3,
+                    base_color: Color::srgb(0.3, 0.22, 0.14),
+                    color_jitter: 0.04,
+                    roughness: 0.9,
+                    double_sided: false,
+                    density_noise: Perlin::new(1004),
+                    density_scale: 0.04,
+                    density_threshold: 0.3,
+                    placement: PlacementRules {
+                        min_spacing: 10.0,
+                        max_slope: 20.0,
+                        ..default()
+                    },
+                    biomes: vec![Biome::Forest, Biome::Meadow],
+                    scale: (0.8, 1.4),
+                    tilt_jitter: 0.05,
+                    sink: 0.1,
+                    collider: Some(PropCollider::Box),
+                },
+            ],
+            meshes: Vec::new(),
+            materials: Vec::new(),
+            colliders: Vec::new(),
+        }
+    }
+}
+
+// A spawned prop instance, parented to its terrain chunk
+#[derive(Component)]
+pub struct Prop;
+
+const PROPS_LAYER_BASE: u64 = 100;
+
+// Generate the mesh and material variants each layer draws from
+fn build_prop_variants(
+    mut layers: ResMut<ScatterLayers>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+) {
+    let mut all_meshes = Vec::new();
+    let mut all_materials = Vec::new();
+    let mut all_colliders = Vec::new();
+
+    for (index, layer) in layers.layers.iter().enumerate() {
+        let mut rng = StdRng::seed_from_u64(index as u64 + 1);
+        let mut layer_meshes = Vec::new();
+        let mut layer_materials = Vec::new();
+        let mut layer_colliders = Vec::new();
+
+        for _ in 0..layer.variants.max(1) {
+            match layer.mesh {
+                PropMesh::Generated(generate) => {
+                    let mesh = generate(&mut rng);
+                    layer_colliders.push(layer.collider.and_then(|shape| fit_collider(&mesh, shape)));
+                    layer_meshes.push(meshes.add(mesh));
+                }
+                // Asset meshes are not loaded yet, so their bounds are unknown here
+                PropMesh::Asset(path) => {
+                    layer_colliders.push(None);
+                    layer_meshes.push(asset_server.load(path));
+                }
+            }
+
+            let base = layer.base_color.to_srgba();
+            let jitter = layer.color_jitter;
+            layer_materials.push(materials.add(StandardMaterial {
+                base_color: Color::srgb(
+                    (base.red + rng.gen_range(-jitter..jitter)).clamp(0.0, 1.0),
+                    (base.green + rng.gen_range(-jitter..jitter)).clamp(0.0, 1.0),
+                    (base.blue + rng.gen_range(-jitter..jitter)).clamp(0.0, 1.0),
+                ),
+                perceptual_roughness: layer.roughness,
+                reflectance: 0.03,
+                double_sided: layer.double_sided,
+                cull_mode: if layer.double_sided { None } else { Some(bevy::render::render_resource::Face::Back) },
+                ..default()
+            }));
+        }
+
+        info!("Prepared {} variants for scatter layer '{}'", layer_meshes.len(), layer.name);
+        all_meshes.push(layer_meshes);
+        all_materials.push(layer_materials);
+        all_colliders.push(layer_colliders);
+    }
+
+    layers.meshes = all_meshes;
+    layers.materials = all_materials;
+    layers.colliders = all_colliders;
+}
+
+fn fit_collider(mesh: &Mesh, shape: PropCollider) -> Option<Collider> {
+    let aabb = mesh.compute_aabb()?;
+    let center = Vec3::from(aabb.center);
+    let half_extents = Vec3::from(aabb.half_extents);
+    Some(match shape {
+        // Rocks are squashed, so average the extents rather than enclosing them
+        PropCollider::Sphere => Collider::Sphere {
+            center,
+            radius: (half_extents.x + half_extents.y + half_extents.z) / 3.0,
+        },
+        PropCollider::Box => Collider::Box { center, half_extents },
+    })
+}
+
+fn spawn_chunk_props(
+    mut commands: Commands,
+    layers: Res<ScatterLayers>,
+    terrain: TerrainQuery,
+    new_chunks: Query<(Entity, &TerrainChunk), Added<TerrainChunk>>,
+) {
+    if layers.meshes.is_empty() {
+        return;
+    }
+
+    for (chunk_entity, chunk) in new_chunks.iter() {
+        let key = (chunk.chunk_x, chunk.chunk_z);
+        let min = Vec2::new(key.0 as f32, key.1 as f32) * terrain.config.chunk_size;
+        let max = min + Vec2::splat(terrain.config.chunk_size);
+
+        for (index, layer) in layers.layers.iter().enumerate() {
+            let mut rng = StdRng::seed_from_u64(placement::chunk_seed(terrain.config.seed, key, PROPS_LAYER_BASE + index as u64));
+
+            for point in placement::scatter(&terrain, min, max, &layer.placement, &mut rng) {
+                let density = layer
+                    .density_noise
+                    .get([point.x as f64 * layer.density_scale, point.z as f64 * layer.density_scale])
+                    as f32;
+                if density < layer.density_threshold || !layer.biomes.contains(&terrain.biome(point.x, point.z)) {
+                    continue;
+                }
+
+                let variant = rng.gen_range(0..layers.meshes[index].len());
+                let scale = rng.gen_range(layer.scale.0..layer.scale.1);
+                let rotation = Quat::from_rotation_y(rng.gen_range(0.0..std::f32::consts::TAU))
+                    * Quat::from_rotation_x(rng.gen_range(-1.0..1.0) * layer.tilt_jitter)
+                    * Quat::from_rotation_z(rng.gen_range(-1.0..1.0) * layer.tilt_jitter);
+
+                let mut prop = commands.spawn((
+                    PbrBundle {
+                        mesh: layers.meshes[index][variant].clone(),
+                        material: layers.materials[index][variant].clone(),
+                        transform: Transform::from_translation(point - Vec3::Y * layer.sink * scale)
+                            .with_rotation(rotation)
+                            .with_scale(Vec3::splat(scale)),
+                        ..default()
+                    },
+                    Prop,
+                ));
+                if let Some(collider) = layers.colliders[index][variant] {
+                    prop.insert(collider);
+                }
+                let prop = prop.id();
+                commands.entity(chunk_entity).add_child(prop);
+            }
+        }
+    }
+}
+
+// Icosphere pushed in and out by noise, squashed and flat-shaded for a faceted look
+fn rock_mesh(rng: &mut StdRng) -> Mesh {
+    let noise = Perlin::new(rng.r#gen());
+    let squash = rng.gen_range(0.5..0.8);
+    let mut mesh = Sphere::new(0.5).mesh().ico(2).expect("valid icosphere subdivisions");
+
+    if let Some(bevy::render::mesh::VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+    {
+        for position in positions.iter_mut() {
+            let p = Vec3::from_array(*position);
+            let offset = noise.get([p.x as f64 * 2.5, p.y as f64 * 2.5, p.z as f64 * 2.5]) as f32;
+            let displaced = p * (1.0 + offset * 0.35);
+            *position = [displaced.x, displaced.y * squash + 0.5 * squash, displaced.z];
+        }
+    }
+
+    mesh.duplicate_vertices();
+    mesh.compute_flat_normals();
+    mesh
+}
+
+// Cluster of lumpy spheres sitting on the ground
+fn bush_mesh(rng: &mut StdRng) -> Mesh {
+    let noise = Perlin::new(rng.r#gen());
+    let mut bush: Option<Mesh> = None;
+
+    for _ in 0..rng.gen_range(4..7) {
+        let radius = rng.gen_range(0.3..0.55);
+        let centre = Vec3::new(rng.gen_range(-0.4..0.4), radius * rng.gen_range(0.8..1.4), rng.gen_range(-0.4..0.4));
+        let mut lobe = Sphere::new(radius).mesh().ico(1).expect("valid icosphere subdivisions");
+        if let Some(bevy::render::mesh::VertexAttributeValues::Float32x3(positions)) =
+            lobe.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+        {
+            for position in positions.iter_mut() {
+                let p = Vec3::from_array(*position);
+                let offset = noise.get([(p.x + centre.x) as f64 * 4.0, (p.y + centre.y) as f64 * 4.0, (p.z + centre.z) as f64 * 4.0]) as f32;
+                *position = (p * (1.0 + offset * 0.25) + centre).to_array();
+            }
+        }
+        lobe.compute_smooth_normals();
+
+        match bush.as_mut() {
+            Some(mesh) => mesh.merge(&lobe),
+            None => bush = Some(lobe),
+        }
+    }
+
+    bush.expect("bush has at least one lobe")
+}
+
+// Arching fronds radiating from the centre
+fn fern_mesh(rng: &mut StdRng) -> Mesh {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    let fronds = rng.gen_range(6..10);
+    let segments = 5;
+    for frond in 0..fronds {
+        let angle = frond as f32 / fronds as f32 * std::f32::consts::TAU + rng.gen_range(-0.2..0.2);
+        let outward = Vec3::new(angle.cos(), 0.0, angle.sin());
+        let side = Vec3::new(-angle.sin(), 0.0, angle.cos());
+        let length = rng.gen_range(0.6..0.9);
+        let rise = rng.gen_range(0.35..0.55);
+
+        let base = positions.len() as u32;
+        for segment in 0..=segments {
+            let t = segment as f32 / segments as f32;
+            // Rise then droop towards the tip
+            let centre = outward * length * t + Vec3::Y * rise * (t * (1.6 - t * 1.1));
+            let width = 0.12 * (1.0 - t) + 0.01;
+            let normal = (Vec3::Y - outward * (t - 0.4)).normalize();
+            for (offset, u) in [(-width, 0.0), (width, 1.0)] {
+                positions.push((centre + side * offset).to_array());
+                normals.push(normal.to_array());
+                uvs.push([u, t]);
+            }
+        }
+        for segment in 0..segments {
+            let i = base + segment * 2;
+            indices.extend_from_slice(&[i, i + 2, i + 1, i + 1, i + 2, i + 3]);
+        }
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::RENDER_WORLD);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.insert_indices(Indices::U32(indices));
+    mesh
+}
+
+// Cylinder lying along X and resting on the ground
+fn log_mesh(rng: &mut StdRng) -> Mesh {
+    let radius = rng.gen_range(0.15..0.3);
+    let length = rng.gen_range(2.0..4.0);
+    Cylinder::new(radius, length)
+        .mesh()
+        .resolution(10)
+        .build()
+        .rotated_by(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2))
+        .translated_by(Vec3::Y * radius)
+}
@@ -0,0 +1,327 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::input::{self, Action, ActionFrame, ActionState};
+use crate::player::Player;
+
+// Records every frame of resolved input with its delta time, and plays recordings back
+// frame by frame on the recorded timestep so the player ends up in the same place.
+// Started from the command line:
+//   --record <file>   record this session
+//   --replay <file>   play a recording back, then check the final transform and exit
+//   --headless        run without a window or renderer (for CI)
+pub struct ReplayPlugin {
+    pub options: ReplayOptions,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(path) = &self.options.replay {
+            let replay = Replay::load(path).unwrap_or_else(|error| panic!("Could not load replay {}: {}", path.display(), error));
+            info!("Replaying {} frames from {}", replay.frames.len(), path.display());
+            // Time advances by the recorded delta instead of the wall clock
+            let first = replay.frames.first().map_or(Duration::ZERO, |frame| frame.delta);
+            app.insert_resource(TimeUpdateStrategy::ManualDuration(first))
+                .insert_resource(replay)
+                .add_systems(PreUpdate, play_back.after(input::update_action_state));
+            if self.options.record.is_some() {
+                warn!("Ignoring --record while replaying");
+            }
+        } else if let Some(path) = &self.options.record {
+            match Recorder::create(path) {
+                Ok(recorder) => {
+                    info!("Recording input to {}", path.display());
+                    app.insert_resource(recorder)
+                        .add_systems(PreUpdate, record_frame.after(input::update_action_state))
+                        .add_systems(Last, finish_recording);
+                }
+                Err(error) => warn!("Could not record to {}: {}", path.display(), error),
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ReplayOptions {
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub headless: bool,
+}
+
+impl ReplayOptions {
+    pub fn from_args() -> Self {
+        let mut options = ReplayOptions::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--record" => options.record = args.next().map(PathBuf::from),
+                "--replay" => options.replay = args.next().map(PathBuf::from),
+                "--headless" => options.headless = true,
+                other => eprintln!("Ignoring unknown argument: {}", other),
+            }
+        }
+        options
+    }
+}
+
+const HEADER: &str = "# Forest Explorer input replay v2\n# delta_nanos pressed just_pressed just_released stick_x stick_y look_x look_y\n";
+
+// Allowed drift between the recorded and replayed final position
+const TOLERANCE: f32 = 1e-3;
+
+struct ReplayFrame {
+    delta: Duration,
+    actions: ActionFrame,
+}
+
+// Final body position and yaw, written when a recording ends
+#[derive(Clone, Copy)]
+struct FinalPose {
+    translation: Vec3,
+    yaw: f32,
+}
+
+impl FinalPose {
+    fn of(transform: &Transform) -> Self {
+        let (yaw, _, _) = transform.rotation.to_euler(EulerRot::YXZ);
+        Self {
+            translation: transform.translation,
+            yaw,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct Recorder {
+    writer: BufWriter<File>,
+    frames: u64,
+}
+
+impl Recorder {
+    fn create(path: &Path) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(HEADER.as_bytes())?;
+        // Name the bit order so the file still plays back after actions are added or moved
+        let names: Vec<&str> = Action::ALL.iter().map(|action| action.name()).collect();
+        writeln!(writer, "actions {}", names.join(" "))?;
+        Ok(Self { writer, frames: 0 })
+    }
+}
+
+#[derive(Resource)]
+struct Replay {
+    frames: Vec<ReplayFrame>,
+    expected: Option<FinalPose>,
+    cursor: usize,
+    finished: bool,
+}
+
+impl Replay {
+    fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        Replay::parse(&text)
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut frames = Vec::new();
+        let mut expected = None;
+        // v1 files have no actions line; their bits follow Action::ALL as it was then
+        let mut order = Action::ALL.to_vec();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let parsed = match fields[0] {
+                "end" => parse_pose(&fields[1..]).map(|pose| expected = Some(pose)),
+                "actions" => fields[1..]
+                    .iter()
+                    .map(|name| Action::from_name(name))
+                    .collect::<Option<Vec<_>>>()
+                    .filter(|actions| actions.len() <= 64)
+                    .map(|actions| order = actions),
+                _ => parse_frame(&fields).map(|frame| frames.push(frame)),
+            };
+            if parsed.is_none() {
+                return Err(format!("bad line {}: {}", number + 1, line));
+            }
+        }
+        for frame in &mut frames {
+            frame.actions = frame.actions.reordered(&order);
+        }
+        Ok(Self {
+            frames,
+            expected,
+            cursor: 0,
+            finished: false,
+        })
+    }
+}
+
+fn parse_frame(fields: &[&str]) -> Option<ReplayFrame> {
+    let [delta, pressed, just_pressed, just_released, stick_x, stick_y, look_x, look_y] = fields else {
+        return None;
+    };
+    Some(ReplayFrame {
+        delta: Duration::from_nanos(delta.parse().ok()?),
+        actions: ActionFrame {
+            pressed: pressed.parse().ok()?,
+            just_pressed: just_pressed.parse().ok()?,
+            just_released: just_released.parse().ok()?,
+            stick: Vec2::new(stick_x.parse().ok()?, stick_y.parse().ok()?),
+            look: Vec2::new(look_x.parse().ok()?, look_y.parse().ok()?),
+        },
+    })
+}
+
+fn parse_pose(fields: &[&str]) -> Option<FinalPose> {
+    let [x, y, z, yaw] = fields else {
+        return None;
+    };
+    Some(FinalPose {
+        translation: Vec3::new(x.parse().ok()?, y.parse().ok()?, z.parse().ok()?),
+        yaw: yaw.parse().ok()?,
+    })
+}
+
+// Floats are written with Rust's shortest round-trip formatting, so they parse back exactly
+fn record_frame(time: Res<Time>, actions: Res<ActionState>, mut recorder: ResMut<Recorder>) {
+    let frame = actions.frame();
+    let line = format!(
+        "{} {} {} {} {} {} {} {}\n",
+        time.delta().as_nanos(),
+        frame.pressed,
+        frame.just_pressed,
+        frame.just_released,
+        frame.stick.x,
+        frame.stick.y,
+        frame.look.x,
+        frame.look.y,
+    );
+    if let Err(error) = recorder.writer.write_all(line.as_bytes()) {
+        warn!("Could not write replay frame: {}", error);
+    }
+    recorder.frames += 1;
+}
+
+fn finish_recording(
+    mut recorder: ResMut<Recorder>,
+    mut exit_events: EventReader<AppExit>,
+    body_query: Query<&Transform, With<Player>>,
+) {
+    if exit_events.read().next().is_none() {
+        return;
+    }
+    if let Ok(body) = body_query.get_single() {
+        let pose = FinalPose::of(body);
+        let line = format!("end {} {} {} {}\n", pose.translation.x, pose.translation.y, pose.translation.z, pose.yaw);
+        let _ = recorder.writer.write_all(line.as_bytes());
+    }
+    match recorder.writer.flush() {
+        Ok(()) => info!("Recorded {} frames", recorder.frames),
+        Err(error) => warn!("Could not finish replay file: {}", error),
+    }
+}
+
+// Overwrite this frame's input with the recorded one and queue the next frame's delta.
+// Once the recording runs out, compare the final pose and exit.
+fn play_back(
+    mut replay: ResMut<Replay>,
+    mut actions: ResMut<ActionState>,
+    mut strategy: ResMut<TimeUpdateStrategy>,
+    body_query: Query<&Transform, With<Player>>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    if replay.finished {
+        return;
+    }
+    let cursor = replay.cursor;
+    if let Some(frame) = replay.frames.get(cursor) {
+        actions.apply_frame(&frame.actions);
+        let next = replay.frames.get(cursor + 1).map_or(frame.delta, |next| next.delta);
+        *strategy = TimeUpdateStrategy::ManualDuration(next);
+        replay.cursor += 1;
+        return;
+    }
+
+    replay.finished = true;
+    let Ok(body) = body_query.get_single() else {
+        error!("Replay finished without a player");
+        exit_events.send(AppExit::error());
+        return;
+    };
+    let pose = FinalPose::of(body);
+    info!("Replay finished at {:?}, yaw {:.4}", pose.translation, pose.yaw);
+    let Some(expected) = replay.expected else {
+        exit_events.send(AppExit::Success);
+        return;
+    };
+    let drift = pose.translation.distance(expected.translation);
+    let yaw_drift = (pose.yaw - expected.yaw).abs();
+    if drift <= TOLERANCE && yaw_drift <= TOLERANCE {
+        info!("Replay matches the recorded final pose");
+        exit_events.send(AppExit::Success);
+    } else {
+        error!(
+            "Replay diverged: expected {:?}, yaw {:.4} ({:.4} m and {:.4} rad off)",
+            expected.translation, expected.yaw, drift, yaw_drift
+        );
+        exit_events.send(AppExit::error());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bit(action: Action) -> u64 {
+        1 << Action::ALL.iter().position(|a| *a == action).unwrap()
+    }
+
+    #[test]
+    fn frames_follow_the_named_action_order() {
+        let text = "\
+            # Forest Explorer input replay v2
+            actions sprint jump
+            16666667 3 2 0 0 1 0.5 -0.25
+            16666667 1 0 2 0 0 0 0
+            end 1.5 2 -3 0.25
+        ";
+        let replay = Replay::parse(text).expect("replay parses");
+        assert_eq!(replay.frames.len(), 2);
+        let first = replay.frames[0].actions;
+        assert_eq!(replay.frames[0].delta, Duration::from_nanos(16666667));
+        assert_eq!(first.pressed, bit(Action::Sprint) | bit(Action::Jump));
+        assert_eq!(first.just_pressed, bit(Action::Jump));
+        assert_eq!(first.stick, Vec2::new(0.0, 1.0));
+        assert_eq!(first.look, Vec2::new(0.5, -0.25));
+        assert_eq!(replay.frames[1].actions.just_released, bit(Action::Jump));
+        let expected = replay.expected.expect("final pose is read");
+        assert_eq!(expected.translation, Vec3::new(1.5, 2.0, -3.0));
+        assert_eq!(expected.yaw, 0.25);
+    }
+
+    #[test]
+    fn v1_frames_use_the_original_order() {
+        let replay = Replay::parse("# Forest Explorer input replay v1\n1000 1 0 0 0 0 0 0\n").expect("replay parses");
+        assert_eq!(replay.frames[0].actions.pressed, bit(Action::ALL[0]));
+        assert!(replay.expected.is_none());
+    }
+
+    #[test]
+    fn bad_lines_are_rejected() {
+        assert!(Replay::parse("actions jump fly\n").is_err());
+        assert!(Replay::parse("1000 1 0 0 0 0 0\n").is_err());
+        assert!(Replay::parse("1000 1 0 0 0 0 0 x\n").is_err());
+        assert!(Replay::parse("end 1 2 3\n").is_err());
+    }
+}
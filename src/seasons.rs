@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+
+use crate::day_night::{self, TimeOfDay};
+use crate::input::{Action, ActionState};
+
+pub struct SeasonsPlugin;
+
+impl Plugin for SeasonsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Seasons>().add_systems(
+            Update,
+            (cycle_season, advance_calendar, update_seasons)
+                .chain()
+                .after(day_night::update_day_night_cycle),
+        );
+    }
+}
+
+const DAYS_PER_YEAR: f32 = 365.0;
+const SPRING_EQUINOX: f32 = 79.0; // 20th of March, as a day of the year
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Season {
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+impl Season {
+    pub const ALL: [Season; 4] = [Season::Spring, Season::Summer, Season::Autumn, Season::Winter];
+
+    pub fn next(self) -> Self {
+        Season::ALL[(self as usize + 1) % 4]
+    }
+}
+
+// Season clock. Each game day moves the calendar on by a chunk of the year, so a full
+// year of seasons passes in a few days of play. The position in the year is kept as a
+// phase from 0 to 4: spring, summer, autumn and winter each take one unit, starting at the
+// local spring equinox, so the southern hemisphere's seasons are the other way round.
+#[derive(Resource)]
+pub struct Seasons {
+    pub days_per_season: u32,
+    pub phase: f32,
+    last_day: u32,
+}
+
+impl FromWorld for Seasons {
+    fn from_world(world: &mut World) -> Self {
+        let phase = world.get_resource::<TimeOfDay>().map_or(0.5, phase_of);
+        Self {
+            days_per_season: 7,
+            phase,
+            last_day: 0,
+        }
+    }
+}
+
+// Phase of the year for the calendar date and hemisphere
+fn phase_of(time_of_day: &TimeOfDay) -> f32 {
+    let mut day = time_of_day.day_of_year as f32 + time_of_day.time / 24.0 - SPRING_EQUINOX;
+    if time_of_day.latitude < 0.0 {
+        day -= DAYS_PER_YEAR / 2.0;
+    }
+    day.rem_euclid(DAYS_PER_YEAR) / DAYS_PER_YEAR * 4.0
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+impl Seasons {
+    pub fn season(&self) -> Season {
+        Season::ALL[(self.phase.floor() as usize).min(3)]
+    }
+
+    // How far through the current season, 0 to 1
+    pub fn progress(&self) -> f32 {
+        self.phase.fract()
+    }
+
+    // Leaves on a broadleaf tree, 0 bare to 1 in full leaf. `offset` shifts one tree a
+    // little earlier or later so a forest does not turn all at once.
+    pub fn leaf_cover(&self, offset: f32) -> f32 {
+        let phase = self.phase + offset;
+        if self.phase < 2.0 {
+            smoothstep(0.0, 0.35, phase)
+        } else {
+            1.0 - smoothstep(2.6, 3.0, phase)
+        }
+    }
+
+    // How far leaves have turned from green towards their autumn colour
+    pub fn autumn_colour(&self, offset: f32) -> f32 {
+        if self.phase < 2.0 {
+            0.0
+        } else {
+            smoothstep(2.1, 2.7, self.phase + offset)
+        }
+    }
+
+    // Fresh light green of new leaves, fading into summer
+    pub fn spring_freshness(&self) -> f32 {
+        if self.phase < 2.0 {
+            1.0 - smoothstep(0.3, 1.0, self.phase)
+        } else {
+            0.0
+        }
+    }
+
+    // 0 with no snow to 1 at the height of winter; settles in late autumn and melts in
+    // early spring
+    pub fn snow_cover(&self) -> f32 {
+        if self.phase < 2.0 {
+            1.0 - smoothstep(0.0, 0.3, self.phase)
+        } else {
+            smoothstep(2.7, 3.1, self.phase)
+        }
+    }
+
+    // Multiplies how many mushrooms come up; peaks in mid autumn, scarce under snow
+    pub fn mushroom_abundance(&self) -> f32 {
+        let autumn = (-((self.phase - 2.4) / 0.5).powi(2)).exp();
+        (0.5 + 1.5 * autumn) * (1.0 - 0.8 * self.snow_cover())
+    }
+
+    // Jump the calendar to the middle of a season
+    pub fn set(&mut self, time_of_day: &mut TimeOfDay, season: Season) {
+        let mut day = SPRING_EQUINOX + (season as usize as f32 + 0.5) * DAYS_PER_YEAR / 4.0;
+        if time_of_day.latitude < 0.0 {
+            day += DAYS_PER_YEAR / 2.0;
+        }
+        time_of_day.day_of_year = day.rem_euclid(DAYS_PER_YEAR) as u32;
+        self.phase = phase_of(time_of_day);
+        info!("Season: {:?}", season);
+    }
+}
+
+// Debug command: skip to the middle of the next season
+fn cycle_season(actions: Res<ActionState>, mut seasons: ResMut<Seasons>, mut time_of_day: ResMut<TimeOfDay>) {
+    if actions.just_pressed(Action::CycleSeason) {
+        let next = seasons.season().next();
+        seasons.set(&mut time_of_day, next);
+    }
+}
+
+// Move the calendar on at each midnight, and back again when the clock is turned back
+// past one
+fn advance_calendar(mut seasons: ResMut<Seasons>, mut time_of_day: ResMut<TimeOfDay>) {
+    if time_of_day.day == seasons.last_day {
+        return;
+    }
+    let days = time_of_day.day as i64 - seasons.last_day as i64;
+    seasons.last_day = time_of_day.day;
+    let per_day = (DAYS_PER_YEAR / (4 * seasons.days_per_season.max(1)) as f32).round() as i64;
+    let before = seasons.season();
+    time_of_day.day_of_year =
+        (time_of_day.day_of_year as i64 + days * per_day).rem_euclid(DAYS_PER_YEAR as i64) as u32;
+    seasons.phase = phase_of(&time_of_day);
+    if seasons.season() != before {
+        info!("Season: {:?}", seasons.season());
+    }
+}
+
+fn update_seasons(time_of_day: Res<TimeOfDay>, mut seasons: ResMut<Seasons>) {
+    let phase = phase_of(&time_of_day);
+    if phase != seasons.phase {
+        seasons.phase = phase;
+    }
+}
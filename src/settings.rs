@@ -10,25 +10,63 @@ impl Plugin for SettingsPlugin {
 
 #[derive(Resource)]
 pub struct GameSettings {
-    pub mouse_sensitivity: f32,
     pub fog_density: f32,
     pub render_distance: i32,
     pub enable_shadows: bool,
     pub enable_fog: bool,
     pub fov: f32,
     pub sprint_speed_multiplier: f32,
+    pub grass_quality: GrassQuality,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GrassQuality {
+    Off,
+    Low,
+    Medium,
+    High,
+}
+
+impl GrassQuality {
+    pub fn next(self) -> Self {
+        match self {
+            GrassQuality::Off => GrassQuality::Low,
+            GrassQuality::Low => GrassQuality::Medium,
+            GrassQuality::Medium => GrassQuality::High,
+            GrassQuality::High => GrassQuality::Off,
+        }
+    }
+
+    // Fraction of the full blade count to spawn
+    pub fn density_scale(self) -> f32 {
+        match self {
+            GrassQuality::Off => 0.0,
+            GrassQuality::Low => 0.25,
+            GrassQuality::Medium => 0.5,
+            GrassQuality::High => 1.0,
+        }
+    }
+
+    // Chunk rings around the player that get grass
+    pub fn chunk_radius(self) -> i32 {
+        match self {
+            GrassQuality::Off => -1,
+            GrassQuality::Low => 1,
+            GrassQuality::Medium | GrassQuality::High => 2,
+        }
+    }
 }
 
 impl Default for GameSettings {
     fn default() -> Self {
         Self {
-            mouse_sensitivity: 0.002,
             fog_density: 1.0,
             render_distance: 3,
             enable_shadows: true,
             enable_fog: true,
             fov: 75.0,
             sprint_speed_multiplier: 2.0,
+            grass_quality: GrassQuality::Medium,
         }
     }
 }
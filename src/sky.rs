@@ -0,0 +1,300 @@
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey, NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use bevy::render::mesh::MeshVertexBufferLayoutRef;
+use bevy::render::render_resource::{
+    AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+};
+use bevy::render::view::NoFrustumCulling;
+use std::f32::consts::PI;
+
+use crate::clouds::{CloudLayer, CloudSettings};
+use crate::day_night::{self, TimeOfDay};
+use crate::input::{Action, ActionState};
+use crate::lighting::LightingProfile;
+use crate::player::PlayerCamera;
+use crate::weather::Weather;
+
+pub struct SkyPlugin;
+
+impl Plugin for SkyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<SkyMaterial> {
+            prepass_enabled: false,
+            shadows_enabled: false,
+            ..default()
+        })
+        .init_resource::<SkySettings>()
+        .init_resource::<SkyColor>()
+        .add_systems(Startup, setup_sky)
+        .add_systems(
+            Update,
+            (toggle_aurora, update_sky)
+                .chain()
+                .after(day_night::update_day_night_cycle),
+        )
+        .add_systems(Update, follow_camera);
+    }
+}
+
+#[derive(Resource)]
+pub struct SkySettings {
+    pub radius: f32, // Inside the camera's far plane
+    pub star_brightness: f32,
+    pub aurora: bool,
+    pub aurora_fade: f32, // Seconds for the aurora to fade in or out
+}
+
+impl Default for SkySettings {
+    fn default() -> Self {
+        Self {
+            radius: 900.0,
+            star_brightness: 1.0,
+            aurora: false,
+            aurora_fade: 4.0,
+        }
+    }
+}
+
+// Average colour of the sky just above the horizon, in linear RGB. Distant fog and the
+// clear colour use it so far-off terrain melts into the sky behind it.
+#[derive(Resource)]
+pub struct SkyColor {
+    pub horizon: Vec3,
+}
+
+impl Default for SkyColor {
+    fn default() -> Self {
+        Self {
+            horizon: Vec3::new(0.45, 0.6, 0.8),
+        }
+    }
+}
+
+impl SkyColor {
+    pub fn horizon_color(&self) -> Color {
+        Color::linear_rgb(self.horizon.x, self.horizon.y, self.horizon.z)
+    }
+}
+
+// Scattering constants, shared with shaders/sky.wgsl; keep the two in step
+const RAYLEIGH: Vec3 = Vec3::new(4.5e-6, 13.5e-6, 33.1e-6); // Per metre at sea level
+const MIE: f32 = 2e-6;
+const RAYLEIGH_HEIGHT: f32 = 8000.0; // Scale heights in metres
+const MIE_HEIGHT: f32 = 1200.0;
+const MIE_G: f32 = 0.76; // Forward scattering of haze
+const SUN_INTENSITY: f32 = 22.0;
+const MOON_INTENSITY: f32 = 0.02; // Full moon, relative to the sun
+const NIGHT_SKY: Vec3 = Vec3::new(0.002, 0.003, 0.008); // Airglow and starlight
+
+// Relative optical path length through the atmosphere (Kasten and Young)
+fn air_mass(cos_zenith: f32) -> f32 {
+    let cos_zenith = cos_zenith.max(0.0);
+    let zenith_degrees = cos_zenith.min(1.0).acos().to_degrees();
+    1.0 / (cos_zenith + 0.50572 * (96.07995 - zenith_degrees).powf(-1.6364))
+}
+
+// Single scattering of light from a body in `light` direction towards the viewer
+// looking along `view`; radiance before tone mapping
+fn scatter(view: Vec3, light: Vec3, intensity: f32) -> Vec3 {
+    let cos_angle = view.dot(light);
+    let rayleigh_phase = 3.0 / (16.0 * PI) * (1.0 + cos_angle * cos_angle);
+    let g2 = MIE_G * MIE_G;
+    let mie_phase = (1.0 - g2) / (4.0 * PI * (1.0 + g2 - 2.0 * MIE_G * cos_angle).powf(1.5));
+
+    let view_mass = air_mass(view.y);
+    let light_mass = air_mass(light.y);
+    let rayleigh_depth = RAYLEIGH * RAYLEIGH_HEIGHT;
+    let mie_depth = MIE * MIE_HEIGHT;
+    // Light reddens on its way in, then scatters along the view ray
+    let transmittance = (-(rayleigh_depth + Vec3::splat(mie_depth)) * light_mass).exp();
+    let inscatter = (Vec3::ONE - (-rayleigh_depth * view_mass).exp()) * rayleigh_phase
+        + Vec3::splat((1.0 - (-mie_depth * view_mass).exp()) * mie_phase);
+    // Fades out over the last few degrees as the body sinks below the horizon
+    let above_horizon = ((light.y + 0.1) / 0.1).clamp(0.0, 1.0);
+    intensity * above_horizon * transmittance * inscatter
+}
+
+fn sky_radiance(view: Vec3, time_of_day: &TimeOfDay, overcast: f32, flash: f32) -> Vec3 {
+    let moon = SUN_INTENSITY * MOON_INTENSITY * time_of_day.moon_illumination();
+    let clear = NIGHT_SKY
+        + scatter(view, time_of_day.sun_direction(), SUN_INTENSITY)
+        + scatter(view, time_of_day.moon_direction(), moon);
+    cloud_over(clear, overcast, flash)
+}
+
+// Heavy cloud turns the sky a flat grey of about the same brightness, lit up by lightning
+fn cloud_over(radiance: Vec3, overcast: f32, flash: f32) -> Vec3 {
+    let grey = Vec3::splat(radiance.dot(Vec3::new(0.2126, 0.7152, 0.0722)) * 0.7);
+    radiance.lerp(grey, overcast) + Vec3::new(0.6, 0.65, 0.8) * flash * overcast
+}
+
+// How much of the sky is greyed out for the weather's cloud cover; light cloud is left
+// to the cloud layer
+fn overcast(weather: &Weather) -> f32 {
+    let cover = weather.effects().cloud_cover;
+    let t = ((cover - 0.3) / 0.7).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Atmosphere, sun, moon, stars, aurora and clouds, all drawn by the fragment shader on a
+// sphere around the camera
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+pub struct SkyMaterial {
+    #[uniform(0)]
+    pub sun: Vec4, // xyz direction towards the sun, w intensity
+    #[uniform(0)]
+    pub moon: Vec4, // xyz direction towards the moon, w lit fraction
+    #[uniform(0)]
+    pub star_rotation: Vec4, // Quaternion from world to the rotating star sphere
+    #[uniform(0)]
+    pub params: Vec4, // x aurora strength, y star brightness, z clock, w moon intensity
+    #[uniform(0)]
+    pub tint: Vec4, // rgb multiplier from the lighting profile
+    #[uniform(0)]
+    pub weather: Vec4, // x overcast, y lightning flash
+    #[uniform(0)]
+    pub clouds: Vec4, // CloudLayer::params
+    #[uniform(0)]
+    pub cloud_shape: Vec4, // CloudLayer::shape
+    #[texture(1)]
+    #[sampler(2)]
+    pub cloud_map: Handle<Image>,
+}
+
+impl Material for SkyMaterial {
+    fn vertex_shader() -> ShaderRef {
+        "shaders/sky.wgsl".into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        "shaders/sky.wgsl".into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        // Seen from inside
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
+
+#[derive(Component)]
+pub struct SkyDome;
+
+fn setup_sky(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<SkyMaterial>>,
+    settings: Res<SkySettings>,
+    clouds: Res<CloudLayer>,
+) {
+    let material = materials.add(SkyMaterial {
+        sun: Vec4::new(0.0, 1.0, 0.0, SUN_INTENSITY),
+        moon: Vec4::new(0.0, -1.0, 0.0, 0.0),
+        star_rotation: Vec4::from(Quat::IDENTITY),
+        params: Vec4::new(0.0, settings.star_brightness, 0.0, MOON_INTENSITY),
+        tint: Vec4::ONE,
+        weather: Vec4::ZERO,
+        clouds: Vec4::ZERO,
+        cloud_shape: Vec4::ZERO,
+        cloud_map: clouds.map.clone(),
+    });
+    commands.spawn((
+        MaterialMeshBundle {
+            mesh: meshes.add(Sphere::new(settings.radius).mesh().uv(48, 24)),
+            material,
+            ..default()
+        },
+        SkyDome,
+        NotShadowCaster,
+        NotShadowReceiver,
+        // Always around the camera, so never outside the view
+        NoFrustumCulling,
+    ));
+}
+
+fn toggle_aurora(actions: Res<ActionState>, mut settings: ResMut<SkySettings>) {
+    if actions.just_pressed(Action::Aurora) {
+        settings.aurora = !settings.aurora;
+        info!("Aurora: {}", if settings.aurora { "on" } else { "off" });
+    }
+}
+
+// Feed the clock to the sky shader and work out the horizon colour for fog
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn update_sky(
+    time: Res<Time>,
+    time_of_day: Res<TimeOfDay>,
+    settings: Res<SkySettings>,
+    profile: Res<LightingProfile>,
+    weather: Res<Weather>,
+    clouds: Res<CloudLayer>,
+    cloud_settings: Res<CloudSettings>,
+    mut sky_color: ResMut<SkyColor>,
+    mut clear_color: ResMut<ClearColor>,
+    mut materials: ResMut<Assets<SkyMaterial>>,
+    dome_query: Query<&Handle<SkyMaterial>, With<SkyDome>>,
+) {
+    let sun = time_of_day.sun_direction();
+    let moon = time_of_day.moon_direction();
+    let tint = profile.sky_tint.sample(time_of_day.profile_hour());
+    let overcast = overcast(&weather);
+
+    // The stars turn about the celestial pole once a sidereal day: with the sun, plus a
+    // full extra turn over the year
+    let latitude = time_of_day.latitude.to_radians();
+    let pole = Vec3::new(0.0, latitude.sin(), -latitude.cos());
+    let turns = (time_of_day.time - 12.0) / 24.0
+        + (time_of_day.day_of_year as f32 + time_of_day.time / 24.0) / 365.0;
+    let world_from_stars =
+        Quat::from_axis_angle(pole, -turns * 2.0 * PI) * Quat::from_rotation_arc(Vec3::Y, pole);
+
+    for handle in dome_query.iter() {
+        if let Some(material) = materials.get_mut(handle) {
+            material.sun = sun.extend(SUN_INTENSITY);
+            material.moon = moon.extend(time_of_day.moon_illumination());
+            material.star_rotation = Vec4::from(world_from_stars.inverse());
+            let target = if settings.aurora { 1.0 } else { 0.0 };
+            let step = time.delta_seconds() / settings.aurora_fade.max(0.01);
+            material.params.x += (target - material.params.x).clamp(-step, step);
+            material.params.y = settings.star_brightness;
+            material.params.z = time.elapsed_seconds_wrapped();
+            material.tint = tint.extend(1.0);
+            material.weather = Vec4::new(overcast, weather.flash, 0.0, 0.0);
+            material.clouds = clouds.params();
+            material.cloud_shape = clouds.shape(&cloud_settings);
+        }
+    }
+
+    // Average a ring of directions a few degrees above the horizon
+    let elevation = 3f32.to_radians();
+    let mut horizon = Vec3::ZERO;
+    for i in 0..8 {
+        let azimuth = i as f32 / 8.0 * 2.0 * PI;
+        let view = Vec3::new(
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+            -elevation.cos() * azimuth.cos(),
+        );
+        horizon += Vec3::ONE - (-sky_radiance(view, &time_of_day, overcast, weather.flash)).exp();
+    }
+    sky_color.horizon = horizon / 8.0 * tint;
+    clear_color.0 = sky_color.horizon_color();
+}
+
+// Keep the dome centred on the camera so it is never reached
+fn follow_camera(
+    camera_query: Query<&GlobalTransform, With<PlayerCamera>>,
+    mut dome_query: Query<&mut Transform, With<SkyDome>>,
+) {
+    let Ok(camera) = camera_query.get_single() else {
+        return;
+    };
+    for mut transform in dome_query.iter_mut() {
+        transform.translation = camera.translation();
+    }
+}
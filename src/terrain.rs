@@ -1,14 +1,20 @@
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
+use bevy::render::mesh::VertexAttributeValues;
 use noise::{NoiseFn, Perlin};
 
+use crate::clouds::{CloudLayer, CloudShadowedMaterial, CloudShadows};
+use crate::seasons::Seasons;
+
 pub struct TerrainPlugin;
 
 impl Plugin for TerrainPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<TerrainConfig>()
-            .init_resource::<ChunkManager>()
+        let config = TerrainConfig::default();
+        app.insert_resource(ChunkManager::new(config.seed))
+            .insert_resource(config)
             .add_systems(Startup, setup_terrain)
-            .add_systems(Update, update_chunks);
+            .add_systems(Update, (update_chunks, update_snow_cover));
     }
 }
 
@@ -18,6 +24,8 @@ pub struct TerrainConfig {
     pub render_distance: i32,
     pub height_scale: f32,
     pub noise_scale: f64,
+    pub water_level: f32, // Ground below this height is under water
+    pub seed: u32,        // Height field seed; names the world for per-world saves
 }
 
 impl Default for TerrainConfig {
@@ -27,6 +35,8 @@ impl Default for TerrainConfig {
             render_distance: 3,
             height_scale: 5.0,
             noise_scale: 0.1,
+            water_level: -3.0,
+            seed: 12345,
         }
     }
 }
@@ -37,35 +47,114 @@ pub struct ChunkManager {
     pub noise: Perlin,
 }
 
-impl Default for ChunkManager {
-    fn default() -> Self {
+impl ChunkManager {
+    pub fn new(seed: u32) -> Self {
         Self {
             loaded_chunks: std::collections::HashSet::new(),
-            noise: Perlin::new(12345), // Fixed seed for reproducibility
+            noise: Perlin::new(seed),
         }
     }
+
+    // Terrain height at a world position, matching the generated chunk meshes
+    pub fn height_at(&self, config: &TerrainConfig, x: f32, z: f32) -> f32 {
+        self.noise.get([x as f64 * config.noise_scale, z as f64 * config.noise_scale]) as f32 * config.height_scale
+    }
+}
+
+// Read-only access to the terrain surface for systems that need to place or move things on it
+#[derive(SystemParam)]
+pub struct TerrainQuery<'w> {
+    pub config: Res<'w, TerrainConfig>,
+    pub chunks: Res<'w, ChunkManager>,
+}
+
+impl TerrainQuery<'_> {
+    pub fn height(&self, x: f32, z: f32) -> f32 {
+        self.chunks.height_at(&self.config, x, z)
+    }
+
+    // Surface normal from central differences of the height field
+    pub fn normal(&self, x: f32, z: f32) -> Vec3 {
+        let eps = 0.5;
+        let dx = self.height(x - eps, z) - self.height(x + eps, z);
+        let dz = self.height(x, z - eps) - self.height(x, z + eps);
+        Vec3::new(dx, 2.0 * eps, dz).normalize()
+    }
+
+    // Angle of the ground from horizontal, in degrees
+    pub fn slope_degrees(&self, x: f32, z: f32) -> f32 {
+        self.normal(x, z).y.clamp(-1.0, 1.0).acos().to_degrees()
+    }
+
+    pub fn water_level(&self) -> f32 {
+        self.config.water_level
+    }
+
+    pub fn biome(&self, x: f32, z: f32) -> Biome {
+        let height = self.height(x, z);
+        if height < self.water_level() {
+            return Biome::Water;
+        }
+        if self.slope_degrees(x, z) > 40.0 {
+            return Biome::Rock;
+        }
+        let height_factor = (height / self.config.height_scale).clamp(0.0, 1.0);
+        if height_factor > 0.4 {
+            Biome::Rock
+        } else if height_factor > 0.15 {
+            Biome::Forest
+        } else {
+            Biome::Meadow
+        }
+    }
+}
+
+// Broad ground type, matching the colour bands of the chunk materials
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Biome {
+    Water,
+    Meadow,
+    Forest,
+    Rock,
 }
 
 #[derive(Component)]
 pub struct TerrainChunk {
     pub chunk_x: i32,
     pub chunk_z: i32,
+    pub ground: Vec3, // Palette colour before snow, sRGB
 }
 
+// One material for every chunk; the ground colours are in the vertex colours
+#[derive(Resource)]
+pub struct TerrainMaterial(pub Handle<CloudShadowedMaterial>);
+
 fn setup_terrain(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut materials: ResMut<Assets<CloudShadowedMaterial>>,
     config: Res<TerrainConfig>,
+    clouds: Res<CloudLayer>,
     mut chunk_manager: ResMut<ChunkManager>,
 ) {
+    let material = materials.add(CloudShadowedMaterial {
+        base: StandardMaterial {
+            base_color: Color::WHITE,
+            metallic: 0.0,
+            perceptual_roughness: 0.95,
+            reflectance: 0.02,
+            ..default()
+        },
+        extension: CloudShadows::new(&clouds),
+    });
+
     // Generate initial chunks around origin
     for x in -config.render_distance..=config.render_distance {
         for z in -config.render_distance..=config.render_distance {
             spawn_chunk(
                 &mut commands,
                 &mut meshes,
-                &mut materials,
+                &material,
                 &config,
                 &mut chunk_manager,
                 x,
@@ -73,20 +162,22 @@ fn setup_terrain(
             );
         }
     }
+    commands.insert_resource(TerrainMaterial(material));
 }
 
 fn update_chunks(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    material: Res<TerrainMaterial>,
     config: Res<TerrainConfig>,
     mut chunk_manager: ResMut<ChunkManager>,
-    camera_query: Query<&Transform, (With<Camera3d>, Without<TerrainChunk>)>,
+    camera_query: Query<&GlobalTransform, With<Camera3d>>,
     chunk_query: Query<(Entity, &TerrainChunk)>,
 ) {
     if let Ok(camera_transform) = camera_query.get_single() {
-        let camera_chunk_x = (camera_transform.translation.x / config.chunk_size).floor() as i32;
-        let camera_chunk_z = (camera_transform.translation.z / config.chunk_size).floor() as i32;
+        let camera_position = camera_transform.translation();
+        let camera_chunk_x = (camera_position.x / config.chunk_size).floor() as i32;
+        let camera_chunk_z = (camera_position.z / config.chunk_size).floor() as i32;
 
         // Unload distant chunks
         let mut chunks_to_remove = Vec::new();
@@ -109,7 +200,7 @@ fn update_chunks(
                     spawn_chunk(
                         &mut commands,
                         &mut meshes,
-                        &mut materials,
+                        &material.0,
                         &config,
                         &mut chunk_manager,
                         x,
@@ -124,7 +215,7 @@ fn update_chunks(
 fn spawn_chunk(
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<StandardMaterial>>,
+    material: &Handle<CloudShadowedMaterial>,
     config: &TerrainConfig,
     chunk_manager: &mut ChunkManager,
     chunk_x: i32,
@@ -150,7 +241,7 @@ fn spawn_chunk(
             let world_z = chunk_world_z + local_z * config.chunk_size;
             
             // Sample noise for height
-            let height = chunk_manager.noise.get([world_x as f64 * config.noise_scale, world_z as f64 * config.noise_scale]) as f32 * config.height_scale;
+            let height = chunk_manager.height_at(config, world_x, world_z);
             
             positions.push([world_x, height, world_z]);
             normals.push([0.0, 1.0, 0.0]); // Simplified normals
@@ -204,12 +295,12 @@ fn spawn_chunk(
     let height_factor = (avg_height / config.height_scale).clamp(0.0, 1.0);
     
     // Gradient-based material color blending
-    let grass_light = Color::srgb(0.3, 0.65, 0.25);
+    let grass_light = Vec3::new(0.3, 0.65, 0.25);
     
-    let material_color = if height_factor > 0.4 {
+    let ground = if height_factor > 0.4 {
         // Rock - blend based on height
         let blend = ((height_factor - 0.4) / 0.6).min(1.0);
-        Color::srgb(
+        Vec3::new(
             0.5 + blend * 0.1,
             0.5 + blend * 0.05,
             0.5 + blend * 0.0,
@@ -217,7 +308,7 @@ fn spawn_chunk(
     } else if height_factor > 0.15 {
         // Dark grass to light grass transition
         let blend = ((height_factor - 0.15) / 0.25).min(1.0);
-        Color::srgb(
+        Vec3::new(
             0.15 + blend * 0.15,
             0.4 + blend * 0.25,
             0.15 + blend * 0.1,
@@ -227,6 +318,9 @@ fn spawn_chunk(
         grass_light
     };
 
+    // The palette colour goes in the vertex colours so snow can be painted over it
+    let colors = ground_colors(&positions, &calculated_normals, ground, f32::INFINITY);
+
     let mut mesh = Mesh::new(
         bevy::render::render_resource::PrimitiveTopology::TriangleList,
         bevy::render::render_asset::RenderAssetUsages::MAIN_WORLD | bevy::render::render_asset::RenderAssetUsages::RENDER_WORLD,
@@ -234,27 +328,79 @@ fn spawn_chunk(
     mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
     mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, calculated_normals);
     mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
     mesh.insert_indices(bevy::render::mesh::Indices::U32(indices));
 
     commands.spawn((
-        PbrBundle {
+        MaterialMeshBundle {
             mesh: meshes.add(mesh),
-            material: materials.add(StandardMaterial {
-                base_color: material_color,
-                metallic: 0.0,
-                perceptual_roughness: 0.95,
-                reflectance: 0.02,
-                ..default()
-            }),
+            material: material.clone(),
             transform: Transform::from_xyz(0.0, 0.0, 0.0),
             ..default()
         },
         TerrainChunk {
             chunk_x,
             chunk_z,
+            ground,
         },
     ));
 
     chunk_manager.loaded_chunks.insert((chunk_x, chunk_z));
 }
 
+const SNOW_COLOR: Vec3 = Vec3::new(0.92, 0.94, 0.98);
+
+// Vertex colours for a chunk: the ground colour, with snow above the snow line on all
+// but the steepest slopes
+fn ground_colors(positions: &[[f32; 3]], normals: &[[f32; 3]], ground: Vec3, snow_line: f32) -> Vec<[f32; 4]> {
+    positions
+        .iter()
+        .zip(normals)
+        .map(|(position, normal)| {
+            let depth = ((position[1] - snow_line) / 0.6 + 0.5).clamp(0.0, 1.0);
+            let flat = ((normal[1] - 0.6) / 0.3).clamp(0.0, 1.0);
+            let color = ground.lerp(SNOW_COLOR, depth * flat);
+            Color::srgb(color.x, color.y, color.z).to_linear().to_f32_array()
+        })
+        .collect()
+}
+
+// Snow creeps down from the peaks as winter sets in and retreats in spring. Chunk colours
+// are repainted when the snow line has moved, and for new chunks.
+fn update_snow_cover(
+    seasons: Res<Seasons>,
+    config: Res<TerrainConfig>,
+    mut last_snow_line: Local<Option<f32>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    chunk_query: Query<(Ref<TerrainChunk>, &Handle<Mesh>)>,
+) {
+    let cover = seasons.snow_cover();
+    // Above the highest ground until winter, then down to just above the meadows
+    let snow_line = if cover <= 0.0 {
+        f32::INFINITY
+    } else {
+        config.height_scale * (1.2 - cover)
+    };
+    let repaint_all = last_snow_line.is_none_or(|line| {
+        line != snow_line && (line.is_infinite() || snow_line.is_infinite() || (line - snow_line).abs() > 0.05)
+    });
+    if repaint_all {
+        *last_snow_line = Some(snow_line);
+    }
+
+    for (chunk, mesh_handle) in chunk_query.iter() {
+        if !repaint_all && !chunk.is_added() {
+            continue;
+        }
+        let Some(mesh) = meshes.get_mut(mesh_handle) else {
+            continue;
+        };
+        let (Some(VertexAttributeValues::Float32x3(positions)), Some(VertexAttributeValues::Float32x3(normals))) =
+            (mesh.attribute(Mesh::ATTRIBUTE_POSITION), mesh.attribute(Mesh::ATTRIBUTE_NORMAL))
+        else {
+            continue;
+        };
+        let colors = ground_colors(positions, normals, chunk.ground, snow_line);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    }
+}
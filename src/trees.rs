@@ -1,6 +1,15 @@
 use bevy::prelude::*;
 use noise::{NoiseFn, Perlin};
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{HashMap, HashSet};
+
+use crate::collision::Collider;
+use crate::forest_growth::{GrowthConfig, GrowthStage};
+use crate::placement::{self, PlacementRules};
+use crate::seasons::Seasons;
+use crate::terrain::{TerrainChunk, TerrainQuery};
+use crate::wind::Wind;
 
 pub struct TreesPlugin;
 
@@ -8,30 +17,75 @@ impl Plugin for TreesPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<TreeConfig>()
             .init_resource::<TreeNoise>()
-            .add_systems(Startup, spawn_initial_trees)
-            .add_systems(Update, (spawn_trees_around_camera, animate_wind));
+            .init_resource::<ForestState>()
+            .add_systems(
+                Update,
+                (generate_chunk_trees, rebuild_dirty_chunks, update_foliage, animate_wind).chain(),
+            );
     }
 }
 
 #[derive(Resource)]
 pub struct TreeConfig {
     pub density: f32,
-    pub spawn_distance: f32,
     pub min_height: f32,
     pub max_height: f32,
+    pub species: Vec<SpeciesConfig>,
 }
 
 impl Default for TreeConfig {
     fn default() -> Self {
         Self {
             density: 0.3,
-            spawn_distance: 50.0,
             min_height: 2.0,
             max_height: 4.0,
+            species: vec![
+                SpeciesConfig {
+                    species: TreeSpecies::Broadleaf,
+                    placement: PlacementRules {
+                        min_spacing: 3.0,
+                        max_slope: 30.0,
+                        max_altitude: 3.0,
+                        ..default()
+                    },
+                    coverage: 0.8,
+                },
+                SpeciesConfig {
+                    species: TreeSpecies::Pine,
+                    placement: PlacementRules {
+                        min_spacing: 3.5,
+                        max_slope: 38.0,
+                        min_altitude: 0.5,
+                        ..default()
+                    },
+                    coverage: 0.6,
+                },
+            ],
         }
     }
 }
 
+impl TreeConfig {
+    pub fn rules_for(&self, species: TreeSpecies) -> Option<&PlacementRules> {
+        self.species
+            .iter()
+            .find(|config| config.species == species)
+            .map(|config| &config.placement)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TreeSpecies {
+    Broadleaf,
+    Pine,
+}
+
+pub struct SpeciesConfig {
+    pub species: TreeSpecies,
+    pub placement: PlacementRules,
+    pub coverage: f32, // Fraction of candidate points kept inside forest patches
+}
+
 #[derive(Resource)]
 pub struct TreeNoise {
     pub noise: Perlin,
@@ -45,271 +99,500 @@ impl Default for TreeNoise {
     }
 }
 
+// Persistent description of a single tree. Records outlive the chunk entities so
+// the forest keeps growing while the player is away.
+#[derive(Clone, Debug)]
+pub struct TreeRecord {
+    pub species: TreeSpecies,
+    pub position: Vec3, // Base of the trunk on the terrain
+    pub height: f32,    // Full-grown height
+    pub age: f32,       // Days
+    pub lifespan: f32,  // Days before the tree dies into a snag
+    pub stage: GrowthStage,
+    pub days_dead: f32,
+    pub seed: u64, // Drives colour and canopy variation so respawned trees look the same; also identifies the tree
+}
+
+// Tree records for every chunk that has ever been loaded
+#[derive(Resource, Default)]
+pub struct ForestState {
+    pub chunks: HashMap<(i32, i32), Vec<TreeRecord>>,
+    pub dirty_chunks: HashSet<(i32, i32)>,
+}
+
+// Root entity of a spawned tree, parented to its terrain chunk
 #[derive(Component)]
-pub struct Tree;
+pub struct Tree {
+    pub chunk: (i32, i32),
+    pub seed: u64, // Seed of the record it was spawned from
+    pub stage: GrowthStage,
+}
 
+// One leafy part of a tree. Keeps its summer colour and size so the seasons can be
+// applied on top.
 #[derive(Component)]
-pub struct TreeCanopy;
+pub struct TreeCanopy {
+    pub color: Vec3, // sRGB
+    pub scale: Vec3,
+    pub deciduous: bool,
+    pub turn: f32, // -0.1..0.1; shifts when this tree turns and drops its leaves
+}
 
 #[derive(Component)]
 pub struct WindAffected {
     pub base_rotation: Quat,
 }
 
-fn spawn_initial_trees(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+// Create tree records the first time a chunk is loaded, and queue the chunk for spawning
+fn generate_chunk_trees(
     config: Res<TreeConfig>,
+    growth_config: Res<GrowthConfig>,
     tree_noise: Res<TreeNoise>,
+    terrain: TerrainQuery,
+    mut forest: ResMut<ForestState>,
+    new_chunks: Query<&TerrainChunk, Added<TerrainChunk>>,
 ) {
-    spawn_trees_in_area(
-        &mut commands,
-        &mut meshes,
-        &mut materials,
-        &config,
-        &tree_noise,
-        -50.0,
-        50.0,
-        -50.0,
-        50.0,
-    );
+    for chunk in new_chunks.iter() {
+        let key = (chunk.chunk_x, chunk.chunk_z);
+        let min = Vec2::new(key.0 as f32, key.1 as f32) * terrain.config.chunk_size;
+        let max = min + Vec2::splat(terrain.config.chunk_size);
+        forest.chunks.entry(key).or_insert_with(|| {
+            let mut rng = StdRng::seed_from_u64(placement::chunk_seed(terrain.config.seed, key, TREE_LAYER));
+            spawn_trees_in_area(&config, &growth_config, &tree_noise, &terrain, &mut rng, min, max)
+        });
+        forest.dirty_chunks.insert(key);
+    }
 }
 
-fn spawn_trees_around_camera(
+// Bring the tree entities of chunks whose records changed back in line with them. Trees
+// that grew only change scale; only trees that appeared, died or fell are spawned again
+// or removed.
+fn rebuild_dirty_chunks(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    config: Res<TreeConfig>,
-    tree_noise: Res<TreeNoise>,
-    camera_query: Query<&Transform, (With<Camera3d>, Without<Tree>)>,
-    tree_query: Query<&Transform, With<Tree>>,
+    mut forest: ResMut<ForestState>,
+    chunk_query: Query<(Entity, &TerrainChunk)>,
+    mut tree_query: Query<(Entity, &mut Tree, &mut Transform)>,
 ) {
-    if let Ok(camera_transform) = camera_query.get_single() {
-        let camera_pos = camera_transform.translation;
-        
-        // Check if we need to spawn trees in new areas
-        let mut has_nearby_trees = false;
-        for tree_transform in tree_query.iter() {
-            let distance = tree_transform.translation.distance(camera_pos);
-            if distance < config.spawn_distance {
-                has_nearby_trees = true;
-                break;
-            }
+    if forest.dirty_chunks.is_empty() {
+        return;
+    }
+
+    let dirty: HashSet<(i32, i32)> = std::mem::take(&mut forest.dirty_chunks);
+
+    // Spawned trees of the dirty chunks, by record seed
+    let mut spawned: HashMap<u64, Entity> = HashMap::new();
+    for (entity, tree, _) in tree_query.iter() {
+        if dirty.contains(&tree.chunk) {
+            spawned.insert(tree.seed, entity);
         }
+    }
 
-        if !has_nearby_trees {
-            let spawn_radius = config.spawn_distance;
-            spawn_trees_in_area(
-                &mut commands,
-                &mut meshes,
-                &mut materials,
-                &config,
-                &tree_noise,
-                camera_pos.x - spawn_radius,
-                camera_pos.x + spawn_radius,
-                camera_pos.z - spawn_radius,
-                camera_pos.z + spawn_radius,
-            );
+    for (chunk_entity, chunk) in chunk_query.iter() {
+        let key = (chunk.chunk_x, chunk.chunk_z);
+        if !dirty.contains(&key) {
+            continue;
+        }
+        let Some(records) = forest.chunks.get(&key) else {
+            continue;
+        };
+        for record in records {
+            if let Some(entity) = spawned.remove(&record.seed) {
+                let Ok((_, mut tree, mut transform)) = tree_query.get_mut(entity) else {
+                    continue;
+                };
+                if tree.stage == record.stage {
+                    continue;
+                }
+                // Saplings gain a collider and snags lose their leaves, so those are
+                // rebuilt; otherwise the tree just grows and the spatial index picks up
+                // the rescaled trunk from its changed GlobalTransform
+                let rebuild = tree.stage == GrowthStage::Sapling || record.stage == GrowthStage::Snag;
+                if !rebuild {
+                    tree.stage = record.stage;
+                    transform.scale = Vec3::splat(record.stage.scale());
+                    continue;
+                }
+                commands.entity(entity).despawn_recursive();
+            }
+            let tree_entity = spawn_tree(&mut commands, &mut meshes, &mut materials, record, key);
+            commands.entity(chunk_entity).add_child(tree_entity);
         }
     }
+
+    // Whatever is left has fallen or died out
+    for entity in spawned.into_values() {
+        commands.entity(entity).despawn_recursive();
+    }
 }
 
+pub const TREE_LAYER: u64 = 1;
+
 fn spawn_trees_in_area(
+    config: &TreeConfig,
+    growth_config: &GrowthConfig,
+    tree_noise: &TreeNoise,
+    terrain: &TerrainQuery,
+    rng: &mut impl Rng,
+    min: Vec2,
+    max: Vec2,
+) -> Vec<TreeRecord> {
+    let mut records: Vec<TreeRecord> = Vec::new();
+
+    for (layer, species_config) in config.species.iter().enumerate() {
+        for point in placement::scatter(terrain, min, max, &species_config.placement, rng) {
+            // Use noise to group trees into forest patches, offset per species
+            let noise_value = tree_noise
+                .noise
+                .get([point.x as f64 * 0.1 + layer as f64 * 100.0, point.z as f64 * 0.1]) as f32;
+            if noise_value < 0.5 - config.density || rng.gen_range(0.0..1.0) > species_config.coverage {
+                continue;
+            }
+
+            // Keep the spacing between species as well
+            let spacing = species_config.placement.min_spacing;
+            if records.iter().any(|r| r.position.xz().distance(point.xz()) < spacing) {
+                continue;
+            }
+
+            // Enhanced variety to tree sizes
+            let size_variation = rng.gen_range(0.7..1.4);
+            let height = rng.gen_range(config.min_height..config.max_height) * size_variation;
+
+            // Start with an established forest of mixed ages
+            let lifespan = growth_config.lifespan * rng.gen_range(0.8..1.2);
+            let age = rng.gen_range(0.0..lifespan);
+
+            records.push(TreeRecord {
+                species: species_config.species,
+                position: point,
+                height,
+                age,
+                lifespan,
+                stage: growth_config.stage_for_age(age),
+                days_dead: 0.0,
+                seed: rng.r#gen(),
+            });
+        }
+    }
+
+    records
+}
+
+// Spawn the meshes for one tree record and return the root entity
+fn spawn_tree(
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
-    config: &TreeConfig,
-    tree_noise: &TreeNoise,
-    min_x: f32,
-    max_x: f32,
-    min_z: f32,
-    max_z: f32,
-) {
-    let mut rng = rand::thread_rng();
-
-    for x in (min_x as i32)..(max_x as i32) {
-        for z in (min_z as i32)..(max_z as i32) {
-            let world_x = x as f32;
-            let world_z = z as f32;
-
-            // Use noise to determine if tree should spawn here
-            let noise_value = tree_noise.noise.get([world_x as f64 * 0.1, world_z as f64 * 0.1]) as f32;
-            let should_spawn = noise_value > (1.0 - config.density);
-
-            if should_spawn && rng.gen_range(0.0..1.0) < config.density {
-                // Sample terrain height using same noise as terrain generation (seed 12345, scale 0.1)
-                use noise::{NoiseFn, Perlin};
-                let terrain_noise = Perlin::new(12345); // Same seed as terrain
-                let terrain_height = terrain_noise.get([world_x as f64 * 0.1, world_z as f64 * 0.1]) as f32 * 5.0;
-                
-                // Enhanced variety to tree sizes
-                let size_variation = rng.gen_range(0.7..1.4);
-                let tree_height = rng.gen_range(config.min_height..config.max_height) * size_variation;
-                let trunk_radius = tree_height * 0.06;
-                let canopy_radius = tree_height * 0.45 + rng.gen_range(-0.2..0.3);
-
-                // More natural trunk color variation with richer browns
-                let trunk_base = rng.gen_range(0.22..0.32);
-                let trunk_color_r = (trunk_base + rng.gen_range(-0.06..0.06) as f32).clamp(0.15, 0.4);
-                let trunk_color_g = (trunk_base * 0.65 + rng.gen_range(-0.04..0.04) as f32).clamp(0.1, 0.3);
-                let trunk_color_b = (trunk_base * 0.35 + rng.gen_range(-0.03..0.03) as f32).clamp(0.05, 0.2);
-
-                // More vibrant and natural canopy colors with better green variation
-                let canopy_green = rng.gen_range(0.35..0.6);
-                let canopy_color_r = rng.gen_range(0.04..0.1);
-                let canopy_color_g = canopy_green;
-                let canopy_color_b = rng.gen_range(0.04..0.1);
-
-                // Spawn trunk with enhanced material and slight tapering
-                let trunk_taper = 0.85; // Slight taper for more natural look
-                commands.spawn((
+    record: &TreeRecord,
+    chunk: (i32, i32),
+) -> Entity {
+    let mut rng = StdRng::seed_from_u64(record.seed);
+
+    let tree_height = record.height;
+    let trunk_radius = tree_height * 0.06;
+    let canopy_radius = tree_height * 0.45 + rng.gen_range(-0.2..0.3);
+
+    // More natural trunk color variation with richer browns
+    let trunk_base = rng.gen_range(0.22..0.32);
+    let mut trunk_color_r = (trunk_base + rng.gen_range(-0.06..0.06) as f32).clamp(0.15, 0.4);
+    let mut trunk_color_g = (trunk_base * 0.65 + rng.gen_range(-0.04..0.04) as f32).clamp(0.1, 0.3);
+    let mut trunk_color_b = (trunk_base * 0.35 + rng.gen_range(-0.03..0.03) as f32).clamp(0.05, 0.2);
+
+    // Dead wood bleaches towards grey
+    if record.stage == GrowthStage::Snag {
+        let grey = (trunk_color_r + trunk_color_g + trunk_color_b) / 3.0 + 0.1;
+        trunk_color_r = (trunk_color_r + grey) * 0.5;
+        trunk_color_g = (trunk_color_g + grey) * 0.5;
+        trunk_color_b = (trunk_color_b + grey) * 0.5;
+    }
+
+    // More vibrant and natural canopy colors with better green variation
+    let canopy_green = rng.gen_range(0.35..0.6);
+    let canopy_color_r = rng.gen_range(0.04..0.1);
+    let canopy_color_g = canopy_green;
+    let canopy_color_b = rng.gen_range(0.04..0.1);
+
+    // Taken from the seed rather than the rng so the rest of the tree is unchanged
+    let turn = ((record.seed >> 16) % 1000) as f32 / 1000.0 * 0.2 - 0.1;
+
+    let root = commands
+        .spawn((
+            SpatialBundle::from_transform(
+                Transform::from_translation(record.position).with_scale(Vec3::splat(record.stage.scale())),
+            ),
+            Tree {
+                chunk,
+                seed: record.seed,
+                stage: record.stage,
+            },
+        ))
+        .id();
+
+    // Saplings are too thin to block the player
+    if record.stage != GrowthStage::Sapling {
+        commands.entity(root).insert(Collider::Capsule {
+            radius: trunk_radius,
+            height: tree_height,
+        });
+    }
+
+    // Spawn trunk with enhanced material and slight tapering
+    let trunk_taper = 0.85; // Slight taper for more natural look
+    let trunk = commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cylinder {
+                radius: trunk_radius,
+                half_height: tree_height / 2.0,
+            }),
+            material: materials.add(StandardMaterial {
+                base_color: Color::srgb(trunk_color_r, trunk_color_g, trunk_color_b),
+                metallic: 0.0,
+                perceptual_roughness: 0.88,
+                reflectance: 0.015,
+                ..default()
+            }),
+            transform: Transform::from_xyz(0.0, tree_height / 2.0, 0.0)
+                .with_scale(Vec3::new(1.0, 1.0, trunk_taper)),
+            ..default()
+        })
+        .id();
+    commands.entity(root).add_child(trunk);
+
+    // Snags keep only their trunk
+    if record.stage == GrowthStage::Snag {
+        return root;
+    }
+
+    // Pines get stacked cones that narrow towards the top
+    if record.species == TreeSpecies::Pine {
+        let tiers = 3;
+        for tier in 0..tiers {
+            let t = tier as f32 / tiers as f32;
+            let radius = canopy_radius * (1.0 - t * 0.6);
+            let height = tree_height * 0.45;
+            let color = Vec3::new(canopy_color_r * 0.6, canopy_color_g * (0.7 + t * 0.15), canopy_color_b * 0.9);
+            let cone = commands
+                .spawn((
                     PbrBundle {
-                        mesh: meshes.add(Cylinder {
-                            radius: trunk_radius,
-                            half_height: tree_height / 2.0,
-                        }),
+                        mesh: meshes.add(Cone { radius, height }),
                         material: materials.add(StandardMaterial {
-                            base_color: Color::srgb(trunk_color_r, trunk_color_g, trunk_color_b),
+                            base_color: Color::srgb(color.x, color.y, color.z),
                             metallic: 0.0,
-                            perceptual_roughness: 0.88,
-                            reflectance: 0.015,
+                            perceptual_roughness: 0.8,
+                            reflectance: 0.05,
                             ..default()
                         }),
-                        transform: Transform::from_xyz(world_x, terrain_height + tree_height / 2.0, world_z)
-                            .with_scale(Vec3::new(1.0, 1.0, trunk_taper)),
+                        transform: Transform::from_xyz(0.0, tree_height * (0.45 + t * 0.4), 0.0),
                         ..default()
                     },
-                    Tree,
-                ));
-
-                // Spawn multiple canopy layers for more natural, lush look
-                let canopy_y = terrain_height + tree_height;
-                let canopy_offset_x = rng.gen_range(-0.2..0.2);
-                let canopy_offset_z = rng.gen_range(-0.2..0.2);
-                
-                // Main canopy with better material
-                commands.spawn((
-                    PbrBundle {
-                        mesh: meshes.add(Sphere {
-                            radius: canopy_radius,
-                        }),
-                        material: materials.add(StandardMaterial {
-                            base_color: Color::srgb(canopy_color_r, canopy_color_g, canopy_color_b),
-                            metallic: 0.0,
-                            perceptual_roughness: 0.72,
-                            reflectance: 0.08,
-                            ..default()
-                        }),
-                        transform: Transform::from_xyz(
-                            world_x + canopy_offset_x, 
-                            canopy_y, 
-                            world_z + canopy_offset_z
-                        )
-                        .with_scale(Vec3::new(
-                            1.0 + rng.gen_range(-0.1..0.1),
-                            1.0 + rng.gen_range(-0.1..0.1),
-                            1.0 + rng.gen_range(-0.1..0.1),
-                        )),
-                        ..default()
+                    TreeCanopy {
+                        color,
+                        scale: Vec3::ONE,
+                        deciduous: false,
+                        turn,
                     },
-                    TreeCanopy,
                     WindAffected {
                         base_rotation: Quat::IDENTITY,
                     },
-                ));
-                
-                // Secondary smaller canopy layer for depth (40% chance for more variety)
-                if rng.gen_range(0.0..1.0) < 0.4 {
-                    let secondary_radius = canopy_radius * 0.55;
-                    let secondary_offset_x = rng.gen_range(-0.25..0.25);
-                    let secondary_offset_z = rng.gen_range(-0.25..0.25);
-                    let secondary_y = canopy_y + rng.gen_range(-0.4..0.6);
-                    
-                    commands.spawn((
-                        PbrBundle {
-                            mesh: meshes.add(Sphere {
-                                radius: secondary_radius,
-                            }),
-                            material: materials.add(StandardMaterial {
-                                base_color: Color::srgb(
-                                    (canopy_color_r * 0.85).clamp(0.0, 1.0),
-                                    (canopy_color_g * 1.15).clamp(0.0, 1.0),
-                                    (canopy_color_b * 0.85).clamp(0.0, 1.0),
-                                ),
-                                metallic: 0.0,
-                                perceptual_roughness: 0.68,
-                                reflectance: 0.08,
-                                ..default()
-                            }),
-                            transform: Transform::from_xyz(
-                                world_x + secondary_offset_x,
-                                secondary_y,
-                                world_z + secondary_offset_z
-                            )
-                            .with_scale(Vec3::new(
-                                1.0 + rng.gen_range(-0.15..0.15),
-                                1.0 + rng.gen_range(-0.15..0.15),
-                                1.0 + rng.gen_range(-0.15..0.15),
-                            )),
-                            ..default()
-                        },
-                        TreeCanopy,
-                        WindAffected {
-                            base_rotation: Quat::IDENTITY,
-                        },
-                    ));
-                }
-                
-                // Tertiary small canopy layer for extra depth (20% chance)
-                if rng.gen_range(0.0..1.0) < 0.2 {
-                    let tertiary_radius = canopy_radius * 0.35;
-                    let tertiary_offset_x = rng.gen_range(-0.3..0.3);
-                    let tertiary_offset_z = rng.gen_range(-0.3..0.3);
-                    let tertiary_y = canopy_y + rng.gen_range(-0.5..0.7);
-                    
-                    commands.spawn((
-                        PbrBundle {
-                            mesh: meshes.add(Sphere {
-                                radius: tertiary_radius,
-                            }),
-                            material: materials.add(StandardMaterial {
-                                base_color: Color::srgb(
-                                    (canopy_color_r * 0.75).clamp(0.0, 1.0),
-                                    (canopy_color_g * 1.2).clamp(0.0, 1.0),
-                                    (canopy_color_b * 0.75).clamp(0.0, 1.0),
-                                ),
-                                metallic: 0.0,
-                                perceptual_roughness: 0.65,
-                                reflectance: 0.08,
-                                ..default()
-                            }),
-                            transform: Transform::from_xyz(
-                                world_x + tertiary_offset_x,
-                                tertiary_y,
-                                world_z + tertiary_offset_z
-                            ),
-                            ..default()
-                        },
-                        TreeCanopy,
-                        WindAffected {
-                            base_rotation: Quat::IDENTITY,
-                        },
-                    ));
-                }
-            }
+                ))
+                .id();
+            commands.entity(root).add_child(cone);
         }
+        return root;
     }
+
+    // Spawn multiple canopy layers for more natural, lush look
+    let canopy_y = tree_height;
+    let canopy_offset_x = rng.gen_range(-0.2..0.2);
+    let canopy_offset_z = rng.gen_range(-0.2..0.2);
+
+    // Main canopy with better material
+    let main_color = Vec3::new(canopy_color_r, canopy_color_g, canopy_color_b);
+    let main_scale = Vec3::new(
+        1.0 + rng.gen_range(-0.1..0.1),
+        1.0 + rng.gen_range(-0.1..0.1),
+        1.0 + rng.gen_range(-0.1..0.1),
+    );
+    let main_canopy = commands
+        .spawn((
+            PbrBundle {
+                mesh: meshes.add(Sphere {
+                    radius: canopy_radius,
+                }),
+                material: materials.add(StandardMaterial {
+                    base_color: Color::srgb(main_color.x, main_color.y, main_color.z),
+                    metallic: 0.0,
+                    perceptual_roughness: 0.72,
+                    reflectance: 0.08,
+                    ..default()
+                }),
+                transform: Transform::from_xyz(canopy_offset_x, canopy_y, canopy_offset_z).with_scale(main_scale),
+                ..default()
+            },
+            TreeCanopy {
+                color: main_color,
+                scale: main_scale,
+                deciduous: true,
+                turn,
+            },
+            WindAffected {
+                base_rotation: Quat::IDENTITY,
+            },
+        ))
+        .id();
+    commands.entity(root).add_child(main_canopy);
+
+    // Secondary smaller canopy layer for depth (40% chance for more variety)
+    if rng.gen_range(0.0..1.0) < 0.4 {
+        let secondary_radius = canopy_radius * 0.55;
+        let secondary_offset_x = rng.gen_range(-0.25..0.25);
+        let secondary_offset_z = rng.gen_range(-0.25..0.25);
+        let secondary_y = canopy_y + rng.gen_range(-0.4..0.6);
+        let secondary_color = Vec3::new(
+            (canopy_color_r * 0.85).clamp(0.0, 1.0),
+            (canopy_color_g * 1.15).clamp(0.0, 1.0),
+            (canopy_color_b * 0.85).clamp(0.0, 1.0),
+        );
+        let secondary_scale = Vec3::new(
+            1.0 + rng.gen_range(-0.15..0.15),
+            1.0 + rng.gen_range(-0.15..0.15),
+            1.0 + rng.gen_range(-0.15..0.15),
+        );
+
+        let secondary = commands
+            .spawn((
+                PbrBundle {
+                    mesh: meshes.add(Sphere {
+                        radius: secondary_radius,
+                    }),
+                    material: materials.add(StandardMaterial {
+                        base_color: Color::srgb(secondary_color.x, secondary_color.y, secondary_color.z),
+                        metallic: 0.0,
+                        perceptual_roughness: 0.68,
+                        reflectance: 0.08,
+                        ..default()
+                    }),
+                    transform: Transform::from_xyz(secondary_offset_x, secondary_y, secondary_offset_z)
+                        .with_scale(secondary_scale),
+                    ..default()
+                },
+                TreeCanopy {
+                    color: secondary_color,
+                    scale: secondary_scale,
+                    deciduous: true,
+                    turn,
+                },
+                WindAffected {
+                    base_rotation: Quat::IDENTITY,
+                },
+            ))
+            .id();
+        commands.entity(root).add_child(secondary);
+    }
+
+    // Tertiary small canopy layer for extra depth (20% chance)
+    if rng.gen_range(0.0..1.0) < 0.2 {
+        let tertiary_radius = canopy_radius * 0.35;
+        let tertiary_offset_x = rng.gen_range(-0.3..0.3);
+        let tertiary_offset_z = rng.gen_range(-0.3..0.3);
+        let tertiary_y = canopy_y + rng.gen_range(-0.5..0.7);
+        let tertiary_color = Vec3::new(
+            (canopy_color_r * 0.75).clamp(0.0, 1.0),
+            (canopy_color_g * 1.2).clamp(0.0, 1.0),
+            (canopy_color_b * 0.75).clamp(0.0, 1.0),
+        );
+
+        let tertiary = commands
+            .spawn((
+                PbrBundle {
+                    mesh: meshes.add(Sphere {
+                        radius: tertiary_radius,
+                    }),
+                    material: materials.add(StandardMaterial {
+                        base_color: Color::srgb(tertiary_color.x, tertiary_color.y, tertiary_color.z),
+                        metallic: 0.0,
+                        perceptual_roughness: 0.65,
+                        reflectance: 0.08,
+                        ..default()
+                    }),
+                    transform: Transform::from_xyz(tertiary_offset_x, tertiary_y, tertiary_offset_z),
+                    ..default()
+                },
+                TreeCanopy {
+                    color: tertiary_color,
+                    scale: Vec3::ONE,
+                    deciduous: true,
+                    turn,
+                },
+                WindAffected {
+                    base_rotation: Quat::IDENTITY,
+                },
+            ))
+            .id();
+        commands.entity(root).add_child(tertiary);
+    }
+
+    root
 }
 
-fn animate_wind(time: Res<Time>, mut query: Query<&mut Transform, With<WindAffected>>) {
-    let wind_strength = 0.1;
-    let wind_speed = 2.0;
-    let wind_angle = (time.elapsed_seconds() * wind_speed).sin() * wind_strength;
+fn animate_wind(wind: Res<Wind>, mut query: Query<(&mut Transform, &GlobalTransform, &WindAffected)>) {
+    // Tilt canopies towards the wind direction
+    let axis = Vec3::new(wind.direction.y, 0.0, -wind.direction.x);
 
-    for mut transform in query.iter_mut() {
-        // Simple wind animation - rotate slightly
-        transform.rotation = Quat::from_rotation_z(wind_angle * 0.1) * Quat::from_rotation_x(wind_angle * 0.05);
+    for (mut transform, global_transform, wind_affected) in query.iter_mut() {
+        let sway = wind.sway(global_transform.translation().xz());
+        transform.rotation = wind_affected.base_rotation * Quat::from_axis_angle(axis, sway * 0.05);
     }
 }
 
+// Broadleaf canopies turn from fresh spring green through summer to autumn colours, then
+// drop their leaves for the winter; pines stay green and darken a little in the cold.
+// Every canopy is refreshed once the season has moved on a little, new ones straight away.
+fn update_foliage(
+    seasons: Res<Seasons>,
+    mut last_phase: Local<Option<f32>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut canopy_query: Query<(
+        Ref<TreeCanopy>,
+        &Handle<StandardMaterial>,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+) {
+    let refresh_all = last_phase.is_none_or(|phase| (seasons.phase - phase).abs() > 0.01);
+    if refresh_all {
+        *last_phase = Some(seasons.phase);
+    }
+
+    for (canopy, material, mut transform, mut visibility) in canopy_query.iter_mut() {
+        if !refresh_all && !canopy.is_added() {
+            continue;
+        }
+        let (color, leaves) = if canopy.deciduous {
+            let fresh = canopy.color.lerp(Vec3::new(0.35, 0.62, 0.12), 0.4 * seasons.spring_freshness());
+            // Each tree picks its autumn colour from its turn offset
+            let autumn = if canopy.turn < -0.02 {
+                Vec3::new(0.8, 0.38, 0.06)
+            } else if canopy.turn < 0.05 {
+                Vec3::new(0.82, 0.62, 0.12)
+            } else {
+                Vec3::new(0.62, 0.14, 0.06)
+            };
+            let leaves = seasons.leaf_cover(canopy.turn);
+            let color = fresh
+                .lerp(autumn, seasons.autumn_colour(canopy.turn))
+                .lerp(Vec3::new(0.45, 0.28, 0.12), 1.0 - leaves);
+            (color, leaves)
+        } else {
+            (canopy.color * (1.0 - 0.2 * seasons.snow_cover()), 1.0)
+        };
+
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color = Color::srgb(color.x, color.y, color.z);
+        }
+        transform.scale = canopy.scale * leaves.max(0.05);
+        *visibility = if leaves > 0.02 { Visibility::Inherited } else { Visibility::Hidden };
+    }
+}
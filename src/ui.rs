@@ -1,11 +1,13 @@
 use bevy::prelude::*;
 
+use crate::input::{Action, ActionState, Binding, InputDevice, InputMap};
+
 pub struct UIPlugin;
 
 impl Plugin for UIPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_ui)
-            .add_systems(Update, update_ui);
+        app.add_systems(Startup, (setup_ui, setup_stamina_bar, setup_health_bar))
+            .add_systems(Update, (update_ui, update_stamina_bar, update_health_bar));
     }
 }
 
@@ -15,6 +17,12 @@ struct UIText;
 #[derive(Component)]
 struct UIPanel;
 
+#[derive(Component)]
+struct StaminaBar;
+
+#[derive(Component)]
+struct HealthBar;
+
 fn setup_ui(mut commands: Commands) {
     // Create main container with modern design
     commands.spawn((
@@ -55,11 +63,108 @@ fn setup_ui(mut commands: Commands) {
     ));
 }
 
+// Stamina meter centred at the bottom of the screen
+fn setup_stamina_bar(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(30.0),
+                left: Val::Percent(50.0),
+                width: Val::Px(240.0),
+                height: Val::Px(10.0),
+                margin: UiRect::left(Val::Px(-120.0)),
+                padding: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::srgba(0.08, 0.12, 0.18, 0.8)),
+            ..default()
+        })
+        .with_children(|bar| {
+            bar.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(Color::srgb(0.55, 0.85, 0.35)),
+                    ..default()
+                },
+                StaminaBar,
+            ));
+        });
+}
+
+fn update_stamina_bar(
+    stamina_query: Query<&super::player::Stamina>,
+    mut bar_query: Query<(&mut Style, &mut BackgroundColor), With<StaminaBar>>,
+) {
+    let (Ok(stamina), Ok((mut style, mut color))) = (stamina_query.get_single(), bar_query.get_single_mut()) else {
+        return;
+    };
+    style.width = Val::Percent(stamina.fraction() * 100.0);
+    // Red while exhausted so it is clear why sprint stopped working
+    color.0 = if stamina.exhausted {
+        Color::srgb(0.85, 0.3, 0.25)
+    } else {
+        Color::srgb(0.55, 0.85, 0.35)
+    };
+}
+
+// Health meter just above the stamina meter
+fn setup_health_bar(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(46.0),
+                left: Val::Percent(50.0),
+                width: Val::Px(240.0),
+                height: Val::Px(10.0),
+                margin: UiRect::left(Val::Px(-120.0)),
+                padding: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::srgba(0.08, 0.12, 0.18, 0.8)),
+            ..default()
+        })
+        .with_children(|bar| {
+            bar.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(Color::srgb(0.8, 0.25, 0.25)),
+                    ..default()
+                },
+                HealthBar,
+            ));
+        });
+}
+
+fn update_health_bar(
+    health_query: Query<&super::health::Health>,
+    mut bar_query: Query<&mut Style, With<HealthBar>>,
+) {
+    let (Ok(health), Ok(mut style)) = (health_query.get_single(), bar_query.get_single_mut()) else {
+        return;
+    };
+    style.width = Val::Percent(health.fraction() * 100.0);
+}
+
+#[allow(clippy::too_many_arguments)]
 fn update_ui(
     mut ui_query: Query<&mut Text, With<UIText>>,
     time_of_day: Option<Res<super::day_night::TimeOfDay>>,
+    weather: Option<Res<super::weather::Weather>>,
+    seasons: Option<Res<super::seasons::Seasons>>,
     mushroom_count: Option<Res<super::interactivity::MushroomCount>>,
     diagnostics: Res<bevy::diagnostic::DiagnosticsStore>,
+    input_map: Res<InputMap>,
+    actions: Res<ActionState>,
 ) {
     if let Ok(mut text) = ui_query.get_single_mut() {
         let mut info = String::from("🌲 FOREST EXPLORER 🌲\n");
@@ -87,8 +192,33 @@ fn update_ui(
         if let Some(time) = time_of_day {
             let hours = time.time.floor() as u32;
             let minutes = ((time.time - hours as f32) * 60.0) as u32;
-            let time_icon = if time.time >= 6.0 && time.time < 18.0 { "☀️ DAY" } else { "🌙 NIGHT" };
-            info.push_str(&format!("  {} {:02}:{:02}\n\n", time_icon, hours, minutes));
+            let time_icon = if time.is_day() { "☀️ DAY" } else { "🌙 NIGHT" };
+            let clock = if time.paused {
+                " ⏸ paused".to_string()
+            } else if time.time_scale > 1.0 {
+                format!(" ⏩ x{}", time.time_scale)
+            } else {
+                String::new()
+            };
+            info.push_str(&format!("  {} {:02}:{:02}{}\n", time_icon, hours, minutes, clock));
+            let day_length = time.day_length();
+            info.push_str(&format!(
+                "  Daylight {}h {:02}m · Moon {:.0}% lit\n",
+                day_length.floor() as u32,
+                (day_length.fract() * 60.0) as u32,
+                time.moon_illumination() * 100.0
+            ));
+            if let Some(seasons) = seasons {
+                info.push_str(&format!(
+                    "  {:?}, {:.0}% through\n",
+                    seasons.season(),
+                    seasons.progress() * 100.0
+                ));
+            }
+            if let Some(weather) = weather {
+                info.push_str(&format!("  Weather: {:?}\n", weather.current));
+            }
+            info.push('\n');
         }
 
         // Mushroom count with better formatting
@@ -100,12 +230,47 @@ fn update_ui(
         info.push_str("━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
         info.push_str("🎮 CONTROLS\n");
         info.push_str("━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
-        info.push_str("  [WASD]     Movement\n");
-        info.push_str("  [Shift]    Sprint Mode\n");
-        info.push_str("  [Space]    Jump\n");
-        info.push_str("  [Mouse]    Camera Look\n");
-        info.push_str("  [E]        Interact\n");
-        info.push_str("  [ESC]      Menu\n");
+        // Generated from the live bindings of the device in use, so rebinding shows up here
+        let gamepad = actions.device == InputDevice::Gamepad;
+        let keys = |action: Action| -> Vec<String> {
+            input_map
+                .bindings_for(action)
+                .iter()
+                .filter(|binding| matches!(binding, Binding::Gamepad(_)) == gamepad)
+                .map(|binding| binding.label())
+                .collect()
+        };
+        if gamepad {
+            info.push_str("  [L Stick]  Movement\n");
+            info.push_str("  [R Stick]  Camera Look\n");
+        } else {
+            let movement: Vec<String> = [Action::MoveForward, Action::MoveLeft, Action::MoveBackward, Action::MoveRight]
+                .into_iter()
+                .filter_map(|action| keys(action).into_iter().next())
+                .collect();
+            info.push_str(&format!("  {:<10} Movement\n", format!("[{}]", movement.join(""))));
+            info.push_str("  [Mouse]    Camera Look\n");
+        }
+        for action in Action::ALL.into_iter().skip(4) {
+            // The bookmark slots share one line, like movement
+            if Action::BOOKMARK_SLOTS.contains(&action) {
+                if action == Action::BOOKMARK_SLOTS[0] {
+                    let slots: Vec<String> = Action::BOOKMARK_SLOTS
+                        .into_iter()
+                        .filter_map(|slot| keys(slot).into_iter().next())
+                        .collect();
+                    if !slots.is_empty() {
+                        info.push_str(&format!("  {:<10} Go to Bookmark\n", format!("[{}]", slots.join(""))));
+                    }
+                }
+                continue;
+            }
+            let mut bound = keys(action);
+            bound.dedup();
+            if !bound.is_empty() {
+                info.push_str(&format!("  {:<10} {}\n", format!("[{}]", bound.join("/")), action.label()));
+            }
+        }
 
         text.sections[0].value = info;
     }
@@ -1,57 +1,82 @@
 use bevy::prelude::*;
 
+use crate::day_night::{self, DayPhase, DayStarted, NightStarted, SunriseStarted, SunsetStarted, TimeOfDay};
+use crate::interactivity::{Mushroom, MUSHROOM_GLOW};
+
 pub struct VisualEnhancementsPlugin;
 
 impl Plugin for VisualEnhancementsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (
-            pulse_mushroom_glow,
-            update_grass_animation,
-        ));
+        app.init_resource::<MushroomGlow>().add_systems(
+            Update,
+            (follow_day_phase, pulse_mushroom_glow)
+                .chain()
+                .after(day_night::send_day_phase_events),
+        );
+    }
+}
+
+// How brightly mushrooms glow for the part of the day: brightest at night
+#[derive(Resource)]
+struct MushroomGlow {
+    target: f32,
+    level: f32, // Eases towards the target so the change is gradual
+}
+
+impl FromWorld for MushroomGlow {
+    fn from_world(world: &mut World) -> Self {
+        let level = world
+            .get_resource::<TimeOfDay>()
+            .map_or(1.0, |time_of_day| glow_for(time_of_day.day_phase()));
+        Self { target: level, level }
     }
 }
 
-// Pulse mushroom glow based on time and day/night cycle
+fn glow_for(phase: DayPhase) -> f32 {
+    match phase {
+        DayPhase::Night => 1.5,
+        DayPhase::Sunrise | DayPhase::Sunset => 1.2,
+        DayPhase::Day => 1.0,
+    }
+}
+
+fn follow_day_phase(
+    time: Res<Time>,
+    mut glow: ResMut<MushroomGlow>,
+    mut sunrise: EventReader<SunriseStarted>,
+    mut day: EventReader<DayStarted>,
+    mut sunset: EventReader<SunsetStarted>,
+    mut night: EventReader<NightStarted>,
+) {
+    if sunrise.read().count() > 0 {
+        glow.target = glow_for(DayPhase::Sunrise);
+    }
+    if day.read().count() > 0 {
+        glow.target = glow_for(DayPhase::Day);
+    }
+    if sunset.read().count() > 0 {
+        glow.target = glow_for(DayPhase::Sunset);
+    }
+    if night.read().count() > 0 {
+        glow.target = glow_for(DayPhase::Night);
+    }
+    let step = time.delta_seconds() * 0.25;
+    glow.level += (glow.target - glow.level).clamp(-step, step);
+}
+
+// Pulse mushroom glow, brighter at night
 fn pulse_mushroom_glow(
     time: Res<Time>,
-    time_of_day: Option<Res<super::day_night::TimeOfDay>>,
-    mushroom_query: Query<&Handle<StandardMaterial>, With<super::interactivity::Mushroom>>,
+    glow: Res<MushroomGlow>,
+    mushroom_query: Query<(&Mushroom, &Handle<StandardMaterial>)>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
     let pulse_speed = 2.0;
     let base_pulse = (time.elapsed_seconds() * pulse_speed).sin() * 0.3 + 0.7;
-    
-    // Increase glow at night
-    let night_multiplier = if let Some(time) = time_of_day {
-        let t = time.time;
-        if t >= 20.0 || t < 6.0 {
-            1.5 // Night - brighter glow
-        } else if t >= 18.0 || t < 8.0 {
-            1.2 // Dusk/dawn - moderate glow
-        } else {
-            1.0 // Day - normal glow
-        }
-    } else {
-        1.0
-    };
-    
-    let glow_intensity = base_pulse * night_multiplier;
-    
-    for material_handle in mushroom_query.iter() {
+
+    for (mushroom, material_handle) in mushroom_query.iter() {
         if let Some(material) = materials.get_mut(material_handle) {
-            // Get base emissive color and multiply intensity
-            let base_emissive = material.emissive;
-            // LinearRgba can be multiplied by f32 directly
-            material.emissive = base_emissive * glow_intensity;
+            material.emissive = MUSHROOM_GLOW.to_linear() * mushroom.glow_intensity * base_pulse * glow.level;
         }
     }
 }
-
-// Animate grass/foliage (placeholder for future grass system)
-fn update_grass_animation(
-    _time: Res<Time>,
-    // This will be used when grass system is implemented
-) {
-    // Placeholder for grass animation
-}
-
@@ -0,0 +1,135 @@
+use bevy::pbr::NotShadowCaster;
+use bevy::prelude::*;
+
+use crate::player::PlayerCamera;
+use crate::terrain::{TerrainChunk, TerrainQuery};
+
+pub struct WaterPlugin;
+
+impl Plugin for WaterPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WaterView>()
+            .add_systems(Startup, (setup_water_assets, setup_underwater_overlay))
+            .add_systems(Update, (spawn_water_surfaces, update_underwater_view));
+    }
+}
+
+// Whether the camera is below the water surface; fog and the overlay follow it
+#[derive(Resource, Default)]
+pub struct WaterView {
+    pub underwater: bool,
+}
+
+#[derive(Resource)]
+struct WaterAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+// Flat surface at the water level, parented to its terrain chunk
+#[derive(Component)]
+pub struct WaterSurface;
+
+#[derive(Component)]
+struct UnderwaterOverlay;
+
+fn setup_water_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    terrain: TerrainQuery,
+) {
+    let size = terrain.config.chunk_size;
+    commands.insert_resource(WaterAssets {
+        mesh: meshes.add(Plane3d::default().mesh().size(size, size)),
+        material: materials.add(StandardMaterial {
+            base_color: Color::srgba(0.12, 0.32, 0.42, 0.75),
+            alpha_mode: AlphaMode::Blend,
+            perceptual_roughness: 0.08,
+            reflectance: 0.3,
+            double_sided: true,
+            cull_mode: None,
+            ..default()
+        }),
+    });
+}
+
+fn setup_underwater_overlay(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::srgba(0.05, 0.25, 0.3, 0.35)),
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(-1),
+            ..default()
+        },
+        UnderwaterOverlay,
+    ));
+}
+
+// Chunks that dip below the water level get a surface plane
+fn spawn_water_surfaces(
+    mut commands: Commands,
+    assets: Option<Res<WaterAssets>>,
+    terrain: TerrainQuery,
+    new_chunks: Query<(Entity, &TerrainChunk), Added<TerrainChunk>>,
+) {
+    let Some(assets) = assets else {
+        return;
+    };
+    let size = terrain.config.chunk_size;
+    let samples = 8;
+
+    for (chunk_entity, chunk) in new_chunks.iter() {
+        let origin = Vec2::new(chunk.chunk_x as f32, chunk.chunk_z as f32) * size;
+        let has_water = (0..=samples).any(|i| {
+            (0..=samples).any(|j| {
+                let x = origin.x + i as f32 / samples as f32 * size;
+                let z = origin.y + j as f32 / samples as f32 * size;
+                terrain.height(x, z) < terrain.water_level()
+            })
+        });
+        if !has_water {
+            continue;
+        }
+
+        let centre = origin + Vec2::splat(size * 0.5);
+        let surface = commands
+            .spawn((
+                PbrBundle {
+                    mesh: assets.mesh.clone(),
+                    material: assets.material.clone(),
+                    transform: Transform::from_xyz(centre.x, terrain.water_level(), centre.y),
+                    ..default()
+                },
+                WaterSurface,
+                NotShadowCaster,
+            ))
+            .id();
+        commands.entity(chunk_entity).add_child(surface);
+    }
+}
+
+fn update_underwater_view(
+    terrain: TerrainQuery,
+    mut view: ResMut<WaterView>,
+    camera_query: Query<&GlobalTransform, With<PlayerCamera>>,
+    mut overlay_query: Query<&mut Visibility, With<UnderwaterOverlay>>,
+) {
+    let Ok(camera_transform) = camera_query.get_single() else {
+        return;
+    };
+    let underwater = camera_transform.translation().y < terrain.water_level();
+    if underwater == view.underwater {
+        return;
+    }
+    view.underwater = underwater;
+    for mut visibility in overlay_query.iter_mut() {
+        *visibility = if underwater { Visibility::Visible } else { Visibility::Hidden };
+    }
+}
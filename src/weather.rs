@@ -0,0 +1,272 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::input::{Action, ActionState};
+use crate::seasons::Seasons;
+use crate::terrain::TerrainConfig;
+use crate::wind::Wind;
+
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WeatherSettings>()
+            .add_systems(Startup, (setup_weather, setup_lightning))
+            .add_systems(Update, (cycle_weather, update_weather, update_lightning).chain());
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WeatherKind {
+    Clear,
+    Overcast,
+    Rain,
+    Snow,
+    Thunderstorm,
+}
+
+impl WeatherKind {
+    pub const ALL: [WeatherKind; 5] = [
+        WeatherKind::Clear,
+        WeatherKind::Overcast,
+        WeatherKind::Rain,
+        WeatherKind::Snow,
+        WeatherKind::Thunderstorm,
+    ];
+
+    fn effects(self) -> WeatherEffects {
+        let (sunlight, fog_distance, wind, cloud_cover, rain, snow, lightning) = match self {
+            WeatherKind::Clear => (1.0, 1.0, 0.4, 0.15, 0.0, 0.0, 0.0),
+            WeatherKind::Overcast => (0.35, 0.75, 0.55, 0.8, 0.0, 0.0, 0.0),
+            WeatherKind::Rain => (0.2, 0.55, 0.7, 0.95, 1.0, 0.0, 0.0),
+            WeatherKind::Snow => (0.3, 0.45, 0.5, 0.9, 0.0, 1.0, 0.0),
+            WeatherKind::Thunderstorm => (0.1, 0.45, 1.0, 1.0, 1.0, 0.0, 1.0),
+        };
+        WeatherEffects {
+            sunlight,
+            fog_distance,
+            wind,
+            cloud_cover,
+            rain,
+            snow,
+            lightning,
+        }
+    }
+
+    // Relative chances of what comes next. Rain falls as snow in the cold, and storms
+    // only build when it is warm.
+    fn next_weights(self, cold: bool) -> Vec<(WeatherKind, f32)> {
+        let wet = if cold { WeatherKind::Snow } else { WeatherKind::Rain };
+        let storm = if cold { 0.0 } else { 1.0 };
+        match self {
+            WeatherKind::Clear => vec![(WeatherKind::Overcast, 3.0), (wet, 0.5)],
+            WeatherKind::Overcast => vec![(WeatherKind::Clear, 2.0), (wet, 2.0), (WeatherKind::Thunderstorm, storm)],
+            WeatherKind::Rain | WeatherKind::Snow => vec![
+                (WeatherKind::Overcast, 2.0),
+                (WeatherKind::Clear, 1.0),
+                (WeatherKind::Thunderstorm, storm),
+            ],
+            WeatherKind::Thunderstorm => vec![(wet, 2.0), (WeatherKind::Overcast, 1.0)],
+        }
+    }
+}
+
+// How a kind of weather changes the world; blended while one kind gives way to the next
+#[derive(Clone, Copy, Debug)]
+pub struct WeatherEffects {
+    pub sunlight: f32,     // Multiplies the sun's illuminance
+    pub fog_distance: f32, // Multiplies the fog distances; lower is thicker
+    pub wind: f32,         // Wind strength
+    pub cloud_cover: f32,  // 0 clear sky to 1 fully overcast
+    pub rain: f32,         // Precipitation intensity, 0 to 1
+    pub snow: f32,
+    pub lightning: f32, // Scales the chance of lightning strikes
+}
+
+impl WeatherEffects {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        Self {
+            sunlight: mix(self.sunlight, other.sunlight),
+            fog_distance: mix(self.fog_distance, other.fog_distance),
+            wind: mix(self.wind, other.wind),
+            cloud_cover: mix(self.cloud_cover, other.cloud_cover),
+            rain: mix(self.rain, other.rain),
+            snow: mix(self.snow, other.snow),
+            lightning: mix(self.lightning, other.lightning),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct WeatherSettings {
+    pub min_duration: f32, // Seconds each spell of weather lasts
+    pub max_duration: f32,
+    pub transition: f32, // Seconds to blend into the next weather
+    pub strikes_per_minute: f32, // At the height of a thunderstorm
+    pub raindrops: usize,
+    pub snowflakes: usize,
+    pub precipitation_radius: f32, // Half size of the box of particles around the camera
+    pub precipitation_height: f32,
+}
+
+impl Default for WeatherSettings {
+    fn default() -> Self {
+        Self {
+            min_duration: 120.0,
+            max_duration: 360.0,
+            transition: 20.0,
+            strikes_per_minute: 6.0,
+            raindrops: 1500,
+            snowflakes: 1200,
+            precipitation_radius: 14.0,
+            precipitation_height: 12.0,
+        }
+    }
+}
+
+// Current weather and the blend from the one before it. Transitions are drawn from an
+// rng seeded with the world seed, so a world always has the same run of weather.
+#[derive(Resource)]
+pub struct Weather {
+    pub current: WeatherKind,
+    pub previous: WeatherKind,
+    pub blend: f32,     // 0 at the start of a transition, 1 once it is complete
+    pub remaining: f32, // Seconds until the next change
+    pub flash: f32,     // Brightness of the current lightning flash
+    transition: f32,
+    rng: StdRng,
+    lightning_rng: StdRng, // Rolled every frame, so kept apart from the weather sequence
+}
+
+impl Weather {
+    fn new(seed: u32, settings: &WeatherSettings) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed as u64 ^ 0x5745_4154);
+        let remaining = rng.gen_range(settings.min_duration..=settings.max_duration);
+        Self {
+            current: WeatherKind::Clear,
+            previous: WeatherKind::Clear,
+            blend: 1.0,
+            remaining,
+            flash: 0.0,
+            transition: settings.transition,
+            rng,
+            lightning_rng: StdRng::seed_from_u64(seed as u64 ^ 0x4c49_4748),
+        }
+    }
+
+    pub fn effects(&self) -> WeatherEffects {
+        let t = self.blend.clamp(0.0, 1.0);
+        self.previous.effects().lerp(self.current.effects(), t * t * (3.0 - 2.0 * t))
+    }
+
+    // Start blending towards the given weather
+    pub fn set(&mut self, kind: WeatherKind, transition: f32, duration: f32) {
+        if kind == self.current {
+            return;
+        }
+        self.previous = self.current;
+        self.current = kind;
+        self.blend = 0.0;
+        self.transition = transition;
+        self.remaining = duration;
+        info!("Weather: {:?}", kind);
+    }
+
+    fn pick_next(&mut self, cold: bool) -> WeatherKind {
+        let weights = self.current.next_weights(cold);
+        let total: f32 = weights.iter().map(|(_, weight)| weight).sum();
+        let mut roll = self.rng.gen_range(0.0..total);
+        for &(kind, weight) in &weights {
+            if roll < weight {
+                return kind;
+            }
+            roll -= weight;
+        }
+        weights[0].0
+    }
+}
+
+fn setup_weather(mut commands: Commands, config: Res<TerrainConfig>, settings: Res<WeatherSettings>) {
+    commands.insert_resource(Weather::new(config.seed, &settings));
+}
+
+// Debug command: jump straight to the next kind of weather
+fn cycle_weather(actions: Res<ActionState>, settings: Res<WeatherSettings>, mut weather: ResMut<Weather>) {
+    if actions.just_pressed(Action::CycleWeather) {
+        let index = WeatherKind::ALL.iter().position(|kind| *kind == weather.current).unwrap_or(0);
+        let next = WeatherKind::ALL[(index + 1) % WeatherKind::ALL.len()];
+        weather.set(next, 3.0, settings.max_duration);
+    }
+}
+
+pub(crate) fn update_weather(
+    time: Res<Time>,
+    settings: Res<WeatherSettings>,
+    seasons: Res<Seasons>,
+    mut weather: ResMut<Weather>,
+    mut wind: ResMut<Wind>,
+) {
+    let dt = time.delta_seconds();
+    weather.blend = (weather.blend + dt / weather.transition.max(0.01)).min(1.0);
+    weather.remaining -= dt;
+    if weather.remaining <= 0.0 {
+        // Rain falls as snow while there is snow on the ground
+        let next = weather.pick_next(seasons.snow_cover() > 0.5);
+        let duration = weather.rng.gen_range(settings.min_duration..=settings.max_duration);
+        if next == weather.current {
+            weather.remaining = duration;
+        } else {
+            weather.set(next, settings.transition, duration);
+        }
+    }
+    wind.strength = weather.effects().wind;
+}
+
+// Brief blue-white light that strikes during thunderstorms
+#[derive(Component)]
+pub struct LightningLight;
+
+fn setup_lightning(mut commands: Commands) {
+    commands.spawn((
+        DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                color: Color::srgb(0.8, 0.85, 1.0),
+                illuminance: 0.0,
+                shadows_enabled: false,
+                ..default()
+            },
+            transform: Transform::from_rotation(Quat::from_rotation_x(-1.2)),
+            ..default()
+        },
+        LightningLight,
+    ));
+}
+
+fn update_lightning(
+    time: Res<Time>,
+    settings: Res<WeatherSettings>,
+    mut weather: ResMut<Weather>,
+    mut light_query: Query<(&mut DirectionalLight, &mut Transform), With<LightningLight>>,
+) {
+    let dt = time.delta_seconds();
+    let chance = weather.effects().lightning * settings.strikes_per_minute / 60.0 * dt;
+    let strike = weather.lightning_rng.r#gen::<f32>() < chance;
+    if strike {
+        weather.flash = 1.0;
+    } else {
+        weather.flash = (weather.flash - dt * 4.0).max(0.0);
+    }
+    let Ok((mut light, mut transform)) = light_query.get_single_mut() else {
+        return;
+    };
+    if strike {
+        // Each strike comes from a different part of the sky
+        let yaw = weather.lightning_rng.gen_range(0.0..std::f32::consts::TAU);
+        transform.rotation = Quat::from_euler(EulerRot::YXZ, yaw, -1.1, 0.0);
+    }
+    // Flickers as it fades, like a multi-stroke strike
+    let flicker = if (weather.flash * 12.0) as u32 % 3 == 1 { 0.3 } else { 1.0 };
+    light.illuminance = 30000.0 * weather.flash * weather.flash * flicker;
+}
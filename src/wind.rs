@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+
+pub struct WindPlugin;
+
+impl Plugin for WindPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Wind>()
+            .add_systems(Update, update_wind);
+    }
+}
+
+// Shared wind field used by tree canopies and grass
+#[derive(Resource)]
+pub struct Wind {
+    pub direction: Vec2, // Normalised, in the XZ plane
+    pub strength: f32,   // 0 = calm, 1 = strong breeze
+    pub gust_frequency: f32,
+    pub time: f32, // Wind clock, advances faster in stronger wind
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Self {
+            direction: Vec2::new(1.0, 0.3).normalize(),
+            strength: 0.4,
+            gust_frequency: 0.6,
+            time: 0.0,
+        }
+    }
+}
+
+impl Wind {
+    // Sway at a world position in the range -1..1, scaled by strength. Nearby points move
+    // together while distant ones are out of phase, so gusts roll across the forest.
+    pub fn sway(&self, position: Vec2) -> f32 {
+        let phase = position.dot(self.direction) * 0.15;
+        let base = (self.time * 2.0 - phase).sin();
+        let gust = (self.time * self.gust_frequency - phase * 0.5).sin().max(0.0);
+        (base * 0.6 + gust * 0.4) * self.strength
+    }
+}
+
+fn update_wind(time: Res<Time>, mut wind: ResMut<Wind>) {
+    let speed = 0.5 + wind.strength;
+    wind.time += time.delta_seconds() * speed;
+
+    // Let the direction drift slowly
+    let drift = (wind.time * 0.01).sin() * 0.002;
+    wind.direction = Vec2::from_angle(drift).rotate(wind.direction).normalize();
+}